@@ -0,0 +1,75 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ultimaonline_net::{
+    packets::{
+        mobile::{Appearance, EntityFlags, Item, State},
+        Packet,
+    },
+    ser::to_writer,
+    types::{Direction, Notoriety},
+};
+
+fn appearance() -> Appearance {
+    Appearance {
+        state: State {
+            serial: 0x00000001,
+            body: 0x0190,
+            x: 3667,
+            y: 2625,
+            z: 0,
+            direction: Direction::South,
+            hue: 0x83EA,
+            flags: EntityFlags::None,
+            notoriety: Notoriety::Innocent,
+        },
+        items: (0..24)
+            .map(|layer| Item {
+                serial: 0x40000000 + layer,
+                type_id: 0x1F00 + layer as u16,
+                layer: layer as u8,
+                hue: 0x0000,
+            })
+            .collect::<Vec<_>>()
+            .into(),
+    }
+}
+
+// Serializing the packet directly finds its size with a first pass over
+// the contents, while Packet fills in the size afterwards
+fn appearance_packet(c: &mut Criterion) {
+    let appearance = appearance();
+    let mut group = c.benchmark_group("appearance");
+
+    group.bench_function("two_pass", |b| {
+        let mut buffer = Vec::with_capacity(1024);
+        b.iter(|| {
+            buffer.clear();
+            to_writer(&mut buffer, &Packet::<_>::from(black_box(&appearance))).unwrap()
+        })
+    });
+
+    group.bench_function("one_pass", |b| {
+        let mut buffer = Vec::with_capacity(1024);
+        b.iter(|| {
+            buffer.clear();
+            Packet::<_>::from(black_box(&appearance))
+                .append_to(&mut buffer)
+                .unwrap()
+        })
+    });
+
+    // Including the allocation of a buffer for the packet
+    group.bench_function("one_pass_writer", |b| {
+        let mut buffer = Vec::with_capacity(1024);
+        b.iter(|| {
+            buffer.clear();
+            Packet::<_>::from(black_box(&appearance))
+                .to_writer(&mut buffer)
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, appearance_packet);
+criterion_main!(benches);
@@ -23,8 +23,52 @@ pub fn packet(args: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
-    let main_struct = parse_macro_input!(item as ItemStruct);
-    let main_ident = &main_struct.ident;
+    let main_item = parse_macro_input!(item as Item);
+    let (main_item, main_ident, size_assert, serde_impl) = match main_item {
+        Item::Struct(mut main_struct) => {
+            let field_sizes = match apply_field_attrs(&mut main_struct) {
+                Ok(v) => v,
+                Err(e) => {
+                    return TokenStream::from(e.to_compile_error());
+                }
+            };
+            let size_assert = fixed_size_assert(&main_struct, field_sizes, &args);
+
+            (
+                quote! {
+                    #[derive(Clone, Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+                    #main_struct
+                },
+                main_struct.ident,
+                size_assert,
+                quote! {},
+            )
+        }
+        Item::Enum(mut main_enum) => {
+            let serde_impl = match tagged_enum_serde(&mut main_enum) {
+                Ok(v) => v,
+                Err(e) => {
+                    return TokenStream::from(e.to_compile_error());
+                }
+            };
+
+            (
+                quote! {
+                    #[derive(Clone, Debug, PartialEq)]
+                    #main_enum
+                },
+                main_enum.ident,
+                quote! {},
+                serde_impl,
+            )
+        }
+        item => {
+            return TokenStream::from(
+                Error::new_spanned(item, "packet must be a struct or an enum").to_compile_error(),
+            );
+        }
+    };
+    let main_ident = &main_ident;
 
     let from_value = packet_from_content(&parse_quote! {#main_ident}, &args);
     let from_ref = packet_from_content(&parse_quote! {&'a #main_ident}, &args);
@@ -47,8 +91,9 @@ pub fn packet(args: TokenStream, item: TokenStream) -> TokenStream {
     };
 
     quote! {
-        #[derive(Clone, Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
-        #main_struct
+        #main_item
+
+        #serde_impl
 
         impl #main_ident {
             pub const PACKET_ID: u8 = #packet_id;
@@ -56,6 +101,8 @@ pub fn packet(args: TokenStream, item: TokenStream) -> TokenStream {
             pub const SIZE: Option<usize> = #packet_size;
         }
 
+        #size_assert
+
         #from_value
 
         #from_ref
@@ -65,49 +112,426 @@ pub fn packet(args: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+// Derive WireSize for a struct as the sum of its fields' sizes, or for a
+// fieldless enum as the size of its primitive repr.
+#[proc_macro_derive(WireSize)]
+pub fn wire_size(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+
+    match wire_size_impl(&input) {
+        Ok(v) => v.into(),
+        Err(e) => TokenStream::from(e.to_compile_error()),
+    }
+}
+
+fn wire_size_impl(input: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let size = match &input.data {
+        Data::Struct(data) => {
+            let mut field_sizes = Vec::with_capacity(data.fields.len());
+            for field in data.fields.iter() {
+                let ty = &field.ty;
+                let mut field_size = quote! {<#ty as crate::types::WireSize>::WIRE_SIZE};
+                for attr in field
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path.is_ident("serde"))
+                {
+                    if let Some(with) = serde_with_path(attr)? {
+                        field_size = quote! {#with::WIRE_SIZE};
+                    }
+                }
+                field_sizes.push(field_size);
+            }
+
+            quote! {0 #(+ #field_sizes)*}
+        }
+        Data::Enum(_) => {
+            let repr = input
+                .attrs
+                .iter()
+                .find(|attr| attr.path.is_ident("repr"))
+                .ok_or_else(|| {
+                    Error::new_spanned(ident, "WireSize needs enums to have a primitive repr")
+                })?;
+            let int: Ident = repr.parse_args()?;
+
+            quote! {<#int as crate::types::WireSize>::WIRE_SIZE}
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                ident,
+                "WireSize cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics crate::types::WireSize for #ident #ty_generics #where_clause {
+            const WIRE_SIZE: usize = #size;
+        }
+    })
+}
+
+// How the serialized size of a fixed packet field is found
+enum FieldSize {
+    // The type's WireSize
+    Wire,
+    // The declared size of a nested fixed packet
+    Nested,
+    // The WIRE_SIZE given by the #[serde(with = "...")] module
+    With(Path),
+}
+
+// Check that the serialized fields of a fixed packet add up to its declared
+// size, so that editing the fields can't silently desync it from SIZE.
+fn fixed_size_assert(
+    item: &ItemStruct,
+    field_sizes: Vec<FieldSize>,
+    args: &PacketArgs,
+) -> proc_macro2::TokenStream {
+    use PacketArgs::*;
+
+    let main_ident = &item.ident;
+    match args {
+        Fixed { size, .. } => {
+            let field_sizes = item
+                .fields
+                .iter()
+                .zip(field_sizes)
+                .map(|(field, field_size)| {
+                    let ty = &field.ty;
+                    match field_size {
+                        FieldSize::Wire => quote! {<#ty as crate::types::WireSize>::WIRE_SIZE},
+                        FieldSize::Nested => quote! {
+                            match <#ty>::SIZE {
+                                Some(size) => size,
+                                None => panic!("nested packet fields must be fixed size"),
+                            }
+                        },
+                        FieldSize::With(with) => quote! {#with::WIRE_SIZE},
+                    }
+                });
+
+            quote! {
+                const _: () = assert!(
+                    0 #(+ #field_sizes)* == #size,
+                    concat!("fields of ", stringify!(#main_ident), " do not add up to its declared size")
+                );
+            }
+        }
+        _ => quote! {},
+    }
+}
+
+// Replace any #[packet_field(...)] attributes with the equivalent serde attributes,
+// returning how the serialized size of each field is found.
+//
+// Nested fixed packets are sized by their declared size, and fields with their
+// own serde module by that module's WIRE_SIZE, since neither can be told from
+// the field's type alone.
+fn apply_field_attrs(item: &mut ItemStruct) -> Result<Vec<FieldSize>> {
+    let mut field_sizes = Vec::with_capacity(item.fields.len());
+
+    for field in item.fields.iter_mut() {
+        let mut attrs = Vec::with_capacity(field.attrs.len());
+        let mut field_size = FieldSize::Wire;
+
+        for attr in field.attrs.drain(..) {
+            if attr.path.is_ident("serde") {
+                if let Some(with) = serde_with_path(&attr)? {
+                    field_size = FieldSize::With(with);
+                }
+            }
+
+            if !attr.path.is_ident("packet_field") {
+                attrs.push(attr);
+                continue;
+            }
+
+            let meta = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(Error::new_spanned(meta, "expected #[packet_field(...)]")),
+            };
+
+            for nested in meta.nested {
+                match nested {
+                    // Little-endian fields are the same size as their type
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("le") => {
+                        attrs.push(parse_quote! {
+                            #[serde(with = "crate::types::little_endian")]
+                        });
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("nested") => {
+                        field_size = FieldSize::Nested;
+                    }
+                    nested => {
+                        return Err(Error::new_spanned(nested, "unknown packet_field option"))
+                    }
+                }
+            }
+        }
+
+        field.attrs = attrs;
+        field_sizes.push(field_size);
+    }
+
+    Ok(field_sizes)
+}
+
+// The module path of a #[serde(with = "...")] attribute, if that is what it is
+fn serde_with_path(attr: &Attribute) -> Result<Option<Path>> {
+    match attr.parse_meta()? {
+        Meta::List(list) if list.nested.len() == 1 => match list.nested.first() {
+            Some(NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(lit),
+                ..
+            }))) if path.is_ident("with") => Ok(Some(lit.parse()?)),
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+// Generate the serde impls for an enum packet, where each variant is selected
+// by a leading tag byte given by its #[packet_tag(...)] attribute.
+fn tagged_enum_serde(item: &mut ItemEnum) -> Result<proc_macro2::TokenStream> {
+    let ident = &item.ident;
+    let mut ser_arms = vec![];
+    let mut de_arms = vec![];
+
+    for variant in item.variants.iter_mut() {
+        let mut tag = None;
+        let mut attrs = Vec::with_capacity(variant.attrs.len());
+        for attr in variant.attrs.drain(..) {
+            if !attr.path.is_ident("packet_tag") {
+                attrs.push(attr);
+                continue;
+            }
+
+            match attr.parse_meta()? {
+                Meta::List(list) if list.nested.len() == 1 => match list.nested.first() {
+                    Some(NestedMeta::Lit(Lit::Int(lit))) => tag = Some(lit.base10_parse::<u8>()?),
+                    _ => return Err(Error::new_spanned(list, "expected #[packet_tag(<u8>)]")),
+                },
+                meta => return Err(Error::new_spanned(meta, "expected #[packet_tag(<u8>)]")),
+            }
+        }
+        variant.attrs = attrs;
+
+        let tag = match tag {
+            Some(tag) => tag,
+            None => {
+                return Err(Error::new_spanned(
+                    &variant.ident,
+                    "enum packet variants need a #[packet_tag(...)]",
+                ))
+            }
+        };
+
+        let variant_ident = &variant.ident;
+        let bindings: Vec<Ident> = (0..variant.fields.len())
+            .map(|i| Ident::new(&format!("field_{}", i), variant_ident.span()))
+            .collect();
+        let len = bindings.len() + 1;
+
+        let mut ser_elements = Vec::with_capacity(bindings.len());
+        let mut next_elements = Vec::with_capacity(bindings.len());
+        for (i, (field, binding)) in variant.fields.iter_mut().zip(&bindings).enumerate() {
+            let index = i + 1;
+            let ty = field.ty.clone();
+            match take_serde_with(field)? {
+                // Wrap the field so that it goes through the given module,
+                // as #[serde(with = "...")] would for a derived impl
+                Some(with) => {
+                    ser_elements.push(quote! {{
+                        struct SerializeWith<'a>(&'a #ty);
+
+                        impl ::serde::Serialize for SerializeWith<'_> {
+                            fn serialize<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+                            where
+                                S: ::serde::Serializer,
+                            {
+                                #with::serialize(self.0, serializer)
+                            }
+                        }
+
+                        tuple.serialize_element(&SerializeWith(#binding))?;
+                    }});
+                    next_elements.push(quote! {{
+                        struct DeserializeWith(#ty);
+
+                        impl<'de> ::serde::Deserialize<'de> for DeserializeWith {
+                            fn deserialize<D>(deserializer: D) -> ::core::result::Result<Self, D::Error>
+                            where
+                                D: ::serde::Deserializer<'de>,
+                            {
+                                #with::deserialize(deserializer).map(DeserializeWith)
+                            }
+                        }
+
+                        seq.next_element::<DeserializeWith>()?
+                            .ok_or_else(|| ::serde::de::Error::invalid_length(#index, &self))?
+                            .0
+                    }});
+                }
+                None => {
+                    ser_elements.push(quote! {
+                        tuple.serialize_element(#binding)?;
+                    });
+                    next_elements.push(quote! {
+                        seq.next_element()?
+                            .ok_or_else(|| ::serde::de::Error::invalid_length(#index, &self))?
+                    });
+                }
+            }
+        }
+
+        let (pattern, construct) = match &variant.fields {
+            Fields::Named(fields) => {
+                let names: Vec<&Ident> = fields
+                    .named
+                    .iter()
+                    .map(|field| field.ident.as_ref().unwrap())
+                    .collect();
+                (
+                    quote! {#ident::#variant_ident { #(#names: #bindings),* }},
+                    quote! {#ident::#variant_ident { #(#names: #next_elements),* }},
+                )
+            }
+            Fields::Unnamed(_) => (
+                quote! {#ident::#variant_ident(#(#bindings),*)},
+                quote! {#ident::#variant_ident(#(#next_elements),*)},
+            ),
+            Fields::Unit => (
+                quote! {#ident::#variant_ident},
+                quote! {#ident::#variant_ident},
+            ),
+        };
+
+        ser_arms.push(quote! {
+            #pattern => {
+                let mut tuple = serializer.serialize_tuple(#len)?;
+                tuple.serialize_element(&#tag)?;
+                #(#ser_elements)*
+                tuple.end()
+            }
+        });
+
+        de_arms.push(quote! {
+            #tag => Ok(#construct),
+        });
+    }
+
+    Ok(quote! {
+        impl ::serde::Serialize for #ident {
+            fn serialize<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: ::serde::Serializer,
+            {
+                use ::serde::ser::SerializeTuple;
+
+                match self {
+                    #(#ser_arms)*
+                }
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for #ident {
+            fn deserialize<D>(deserializer: D) -> ::core::result::Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                struct TagVisitor;
+
+                impl<'de> ::serde::de::Visitor<'de> for TagVisitor {
+                    type Value = #ident;
+
+                    fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                        formatter.write_str(concat!("a tagged ", stringify!(#ident), " variant"))
+                    }
+
+                    fn visit_seq<A>(self, mut seq: A) -> ::core::result::Result<Self::Value, A::Error>
+                    where
+                        A: ::serde::de::SeqAccess<'de>,
+                    {
+                        let tag: u8 = seq
+                            .next_element()?
+                            .ok_or_else(|| ::serde::de::Error::invalid_length(0, &self))?;
+
+                        match tag {
+                            #(#de_arms)*
+                            _ => Err(::serde::de::Error::custom(format!(
+                                concat!("unknown ", stringify!(#ident), " tag {:#0X}"),
+                                tag
+                            ))),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_tuple(usize::MAX, TagVisitor)
+            }
+        }
+    })
+}
+
+// Remove a #[serde(with = "...")] attribute from an enum variant field, returning
+// the module path. Nothing derives serde for enum packets, so it would otherwise
+// be left as an unknown attribute.
+fn take_serde_with(field: &mut Field) -> Result<Option<Path>> {
+    let mut with = None;
+    let mut attrs = Vec::with_capacity(field.attrs.len());
+
+    for attr in field.attrs.drain(..) {
+        if !attr.path.is_ident("serde") {
+            attrs.push(attr);
+            continue;
+        }
+
+        match serde_with_path(&attr)? {
+            Some(path) => with = Some(path),
+            None => {
+                return Err(Error::new_spanned(
+                    attr,
+                    "only #[serde(with = \"...\")] is supported on enum packet fields",
+                ))
+            }
+        }
+    }
+
+    field.attrs = attrs;
+    Ok(with)
+}
+
 fn packet_from_content(content_type: &Type, args: &PacketArgs) -> proc_macro2::TokenStream {
     use PacketArgs::*;
 
-    let (impl_param, to_size_param) = match content_type {
-        Type::Reference(r) => (
-            match &r.lifetime {
-                Some(l) => quote! {<#l>},
-                None => quote! {},
-            },
-            quote! {val},
-        ),
-        _ => (quote! {}, quote! {&val}),
+    let impl_param = match content_type {
+        Type::Reference(r) => match &r.lifetime {
+            Some(l) => quote! {<#l>},
+            None => quote! {},
+        },
+        _ => quote! {},
     };
 
-    // If this packet has a variable size, generate code to
-    // calculate the size and include it when serializing
-    let (size_calc, size_field) = match args {
-        Fixed { .. } => (quote! {}, quote! {size: None}),
-        _ => (
-            quote! {
-                let size = crate::ser::to_size(#to_size_param).expect("Could not serialize packet for size");
-                let size = ::core::mem::size_of::<u8>() + // packet id
-                           ::core::mem::size_of::<u16>() + // packet size
-                           size;
-            },
-            quote! {
-               size: Some(size as u16)
-            },
-        ),
+    // Variable size packets have their size filled in when serialized
+    let var_size = match args {
+        Fixed { .. } => quote! {false},
+        _ => quote! {true},
     };
 
     let from_type = content_type;
-    let (size_calc, content_type, content_val) = match args {
-        Extended { id } => (
-            quote! {
-                #size_calc
-                let size = ::core::mem::size_of::<u16>() + // extended id
-                           size;
-            },
-            quote! {(u16, #content_type)},
-            quote! {(#id, val)},
-        ),
-        _ => (size_calc, quote! {#content_type}, quote! {val}),
+    let (content_type, content_val) = match args {
+        Extended { id } => (quote! {(u16, #content_type)}, quote! {(#id, val)}),
+        _ => (quote! {#content_type}, quote! {val}),
+    };
+
+    let size = match args {
+        Fixed { size, .. } => quote! {Some(#size)},
+        _ => quote! {None},
     };
 
     let id = match args {
@@ -118,15 +542,14 @@ fn packet_from_content(content_type: &Type, args: &PacketArgs) -> proc_macro2::T
     quote! {
         impl#impl_param crate::packets::IntoPacket for #from_type {
             type Content = #content_type;
+            const SIZE: Option<usize> = #size;
         }
 
         impl#impl_param ::std::convert::From<#from_type> for crate::packets::Packet<#content_type> {
             fn from(val: #from_type) -> Self {
-                #size_calc
-
                 crate::packets::Packet {
                     id: #id,
-                    #size_field,
+                    var_size: #var_size,
                     contents: #content_val,
                 }
             }
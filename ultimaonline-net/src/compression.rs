@@ -1,4 +1,7 @@
 pub mod huffman {
+    use crate::error::{Error, Result};
+    use std::sync::OnceLock;
+
     // Coding table for compression taken ModernUO (https://github.com/modernuo/ModernUO)
     #[rustfmt::skip]
     static COMP_TABLE: [(usize, u16); 256] = [
@@ -76,10 +79,56 @@ pub mod huffman {
         }
 
         // Write terminator
-        write_bits(4, 0b1101);
+        write_bits(TERMINATOR.0, TERMINATOR.1);
 
         compressed
     }
+
+    // Ends each compressed packet, which is then padded to a whole byte
+    const TERMINATOR: (usize, u16) = (4, 0b1101);
+    const END: u16 = 0x100;
+
+    // Decoded values indexed by their code, with a leading 1 bit to tell
+    // apart codes of different lengths
+    fn decode_table() -> &'static [Option<u16>] {
+        static TABLE: OnceLock<Vec<Option<u16>>> = OnceLock::new();
+
+        TABLE.get_or_init(|| {
+            let mut table = vec![None; 1 << 12];
+            for (byte, &(len, bits)) in COMP_TABLE.iter().enumerate() {
+                table[1 << len | bits as usize] = Some(byte as u16);
+            }
+            table[1 << TERMINATOR.0 | TERMINATOR.1 as usize] = Some(END);
+            table
+        })
+    }
+
+    // Decompresses the first packet in buf, returning it along with the
+    // number of compressed bytes it took up. Returns None when buf doesn't
+    // contain the whole packet yet.
+    pub fn decompress(buf: &[u8]) -> Result<Option<(Vec<u8>, usize)>> {
+        let table = decode_table();
+
+        let mut decompressed = vec![];
+        let mut code = 1usize;
+        for (pos, byte) in buf.iter().enumerate() {
+            for shift in (0..8).rev() {
+                code = code << 1 | (byte >> shift & 1) as usize;
+
+                match table.get(code) {
+                    Some(Some(END)) => return Ok(Some((decompressed, pos + 1))),
+                    Some(Some(val)) => {
+                        decompressed.push(*val as u8);
+                        code = 1;
+                    }
+                    Some(None) => {}
+                    None => return Err(Error::data("invalid compressed data")),
+                }
+            }
+        }
+
+        Ok(None)
+    }
 }
 
 #[cfg(test)]
@@ -122,6 +171,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn decompress_round_trip() {
+        let input = [0xa9u8, 0x04, 0xd2, 0x07, 0x00, 0xff, 0x80];
+        let mut compressed = huffman::compress(&input[..]);
+        let len = compressed.len();
+
+        // Nothing is decompressed until the terminator
+        assert_eq!(huffman::decompress(&compressed[..len - 1]).unwrap(), None);
+
+        // Only the first packet is decompressed
+        compressed.extend(huffman::compress(&[0x73u8]));
+        assert_eq!(
+            huffman::decompress(&compressed).unwrap(),
+            Some((input.to_vec(), len))
+        );
+        assert_eq!(
+            huffman::decompress(&compressed[len..]).unwrap(),
+            Some((vec![0x73u8], compressed.len() - len))
+        );
+    }
+
     #[test]
     fn with_no_padding_bits() {
         let input = [0xbdu8, 0x00, 0x03];
@@ -20,11 +20,7 @@ where
     R: io::BufRead,
     T: Deserialize<'a>,
 {
-    let mut deserializer = Deserializer {
-        reader,
-        peek: false,
-        remaining: size,
-    };
+    let mut deserializer = Deserializer::new(reader, size);
 
     let t = T::deserialize(&mut deserializer)?;
 
@@ -34,6 +30,82 @@ where
     }
 }
 
+// Deserializes a value which takes up the entire slice
+pub fn from_slice<T>(bytes: &[u8]) -> Result<T>
+where
+    T: de::DeserializeOwned,
+{
+    let mut reader = bytes;
+    from_reader(&mut reader, bytes.len())
+}
+
+// Deserializes a value which is embedded within other content, reading at
+// most size bytes. Unlike from_reader the value doesn't need to use all of
+// them, and the reader is left just after the value so that the content
+// following it can be read.
+pub fn from_reader_nested<'a, R, T>(reader: &'a mut R, size: usize) -> Result<T>
+where
+    R: io::BufRead,
+    T: Deserialize<'a>,
+{
+    T::deserialize(&mut Deserializer::new(reader, size))
+}
+
+impl<'de, R> Deserializer<'de, R>
+where
+    R: io::BufRead,
+{
+    // Creates a deserializer for manually parsing size bytes of the reader
+    pub fn new(reader: &'de mut R, size: usize) -> Self {
+        Self {
+            reader,
+            peek: false,
+            remaining: size,
+        }
+    }
+
+    // How many bytes are left to be deserialized
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    // Deserializes a section of exactly size bytes using parse, for content
+    // with its own length such as a nested blob. Fails without reading if
+    // fewer than size bytes remain, or if parse doesn't use all of them.
+    // Afterwards the deserializer continues with what follows the section.
+    pub fn bounded<T, F>(&mut self, size: usize, parse: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let outer = self.remaining;
+        if size > outer {
+            return Err(Error::de(PAST_END));
+        }
+
+        self.remaining = size;
+        let result = parse(self);
+        let unused = self.remaining;
+        self.remaining = outer - (size - unused);
+
+        match result {
+            Ok(_) if unused > 0 => Err(Error::de("data remains after deserializing section")),
+            result => result,
+        }
+    }
+}
+
+const PAST_END: &str = "read past end of serialized value";
+
+// Whether reading failed because the value continues past the end of the
+// serialized data
+fn is_past_end(err: &Error) -> bool {
+    match err {
+        Error::Io(err) => err.kind() == io::ErrorKind::UnexpectedEof,
+        Error::Deserialization(msg) => msg == PAST_END,
+        _ => false,
+    }
+}
+
 macro_rules! impl_read_literal {
     ($name:ident : $ty:ty = $read_func:ident()) => {
         #[inline]
@@ -86,7 +158,7 @@ where
         self.remaining = self
             .remaining
             .checked_sub(amount)
-            .ok_or(Error::de("read past end of serialized value"))?;
+            .ok_or_else(|| Error::de(PAST_END))?;
         Ok(())
     }
 }
@@ -289,35 +361,10 @@ where
     where
         V: Visitor<'de>,
     {
-        struct Access<'de, 'a, R: io::BufRead> {
-            deserializer: &'a mut Deserializer<'de, R>,
-            len: usize,
-        }
-
-        impl<'de, 'a, R: io::BufRead> de::SeqAccess<'de> for Access<'de, 'a, R> {
-            type Error = Error;
-
-            fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
-            where
-                T: de::DeserializeSeed<'de>,
-            {
-                if self.len > 0 {
-                    self.len -= 1;
-                    let value = de::DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
-                    Ok(Some(value))
-                } else {
-                    Ok(None)
-                }
-            }
-
-            fn size_hint(&self) -> Option<usize> {
-                Some(self.len)
-            }
-        }
-
-        visitor.visit_seq(Access {
+        visitor.visit_seq(TupleAccess {
             deserializer: self,
             len,
+            truncated: false,
         })
     }
 
@@ -350,10 +397,15 @@ where
             {
                 match self.deserializer.remaining {
                     0 => Ok(None),
-                    _ => Ok(Some(de::DeserializeSeed::deserialize(
-                        seed,
-                        &mut *self.deserializer,
-                    )?)),
+                    remaining => de::DeserializeSeed::deserialize(seed, &mut *self.deserializer)
+                        .map(Some)
+                        .map_err(|err| {
+                            if is_past_end(&err) {
+                                Error::IncompleteElement(remaining)
+                            } else {
+                                err
+                            }
+                        }),
                 }
             }
 
@@ -415,23 +467,35 @@ where
         unimplemented!();
     }
 
-    fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value>
+    // Optional values only make sense at the end of a packet, where they're
+    // present if there's any data left
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        match self.remaining {
+            0 => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
     }
 
+    // Unlike tuples, tuple structs may be cut short by the end of a packet,
+    // in which case the visitor sees fewer than len elements. This is used by
+    // strings like FixedStr which clients don't always pad out.
     fn deserialize_tuple_struct<V>(
         self,
         _name: &'static str,
-        _len: usize,
-        _visitor: V,
+        len: usize,
+        visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        visitor.visit_seq(TupleAccess {
+            deserializer: self,
+            len,
+            truncated: true,
+        })
     }
 
     fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value>
@@ -460,6 +524,32 @@ where
     }
 }
 
+struct TupleAccess<'de, 'a, R: io::BufRead> {
+    deserializer: &'a mut Deserializer<'de, R>,
+    len: usize,
+    truncated: bool, // Whether the end of the value may come first
+}
+
+impl<'de, 'a, R: io::BufRead> de::SeqAccess<'de> for TupleAccess<'de, 'a, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.len == 0 || (self.truncated && self.deserializer.remaining == 0) {
+            return Ok(None);
+        }
+
+        self.len -= 1;
+        de::DeserializeSeed::deserialize(seed, &mut *self.deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
 struct TerminatorEnum<'de, 'a, R: io::BufRead> {
     deserializer: &'a mut Deserializer<'de, R>,
 }
@@ -523,3 +613,125 @@ impl<'de, 'a, R: io::BufRead> de::VariantAccess<'de> for TerminatorVariant<'de,
         unimplemented!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::list::ListNonTerm;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Inner {
+        kind: u8,
+        value: u16,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Outer {
+        id: u8,
+        inner: Inner,
+        flags: u16,
+    }
+
+    #[test]
+    fn deserializes_slice() {
+        let outer: Outer = from_slice(&[0x01, 0x02, 0x12, 0x34, 0x00, 0x80]).unwrap();
+        assert_eq!(
+            outer,
+            Outer {
+                id: 0x01,
+                inner: Inner {
+                    kind: 0x02,
+                    value: 0x1234,
+                },
+                flags: 0x0080,
+            }
+        );
+
+        assert!(from_slice::<Inner>(&[0x02, 0x12, 0x34, 0xFF]).is_err());
+        assert!(from_slice::<Inner>(&[0x02, 0x12]).is_err());
+    }
+
+    #[test]
+    fn deserializes_nested() {
+        let mut input: &[u8] = &[0x02, 0x12, 0x34, 0x00, 0x80, 0x7F];
+
+        let inner: Inner = from_reader_nested(&mut input, 5).unwrap();
+        assert_eq!(
+            inner,
+            Inner {
+                kind: 0x02,
+                value: 0x1234,
+            }
+        );
+
+        // The trailing fields are left to be read
+        assert_eq!(input, [0x00, 0x80, 0x7F]);
+        let trailing: (u16, u8) = from_reader(&mut input, 3).unwrap();
+        assert_eq!(trailing, (0x0080, 0x7F));
+
+        let mut input: &[u8] = &[0x02, 0x12, 0x34];
+        assert!(from_reader_nested::<_, Inner>(&mut input, 2).is_err());
+    }
+
+    #[test]
+    fn deserializes_bounded() {
+        let bytes = [0x00, 0x03, 0x02, 0x12, 0x34, 0x7F];
+        let mut input: &[u8] = &bytes;
+        let mut deserializer = Deserializer::new(&mut input, bytes.len());
+
+        let len = u16::deserialize(&mut deserializer).unwrap();
+        let inner = deserializer
+            .bounded(len as usize, |de| {
+                let inner = Inner::deserialize(&mut *de)?;
+                assert_eq!(de.remaining(), 0);
+                Ok(inner)
+            })
+            .unwrap();
+        assert_eq!(
+            inner,
+            Inner {
+                kind: 0x02,
+                value: 0x1234,
+            }
+        );
+
+        // The parent picks up after the section
+        assert_eq!(deserializer.remaining(), 1);
+        assert_eq!(u8::deserialize(&mut deserializer).unwrap(), 0x7F);
+        assert_eq!(deserializer.remaining(), 0);
+
+        // Sections longer than what's left fail before reading anything
+        let mut input: &[u8] = &bytes;
+        let mut deserializer = Deserializer::new(&mut input, bytes.len());
+        assert!(deserializer
+            .bounded(7, |de| u8::deserialize(&mut *de))
+            .is_err());
+        assert_eq!(deserializer.remaining(), bytes.len());
+
+        // As do sections which aren't used up
+        assert!(deserializer
+            .bounded(3, |de| u16::deserialize(&mut *de))
+            .is_err());
+        assert_eq!(deserializer.remaining(), 4);
+    }
+
+    #[test]
+    fn rejects_incomplete_element() {
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0xAB, 0xCD];
+
+        let list: ListNonTerm<u32> = from_slice(&bytes[..8]).unwrap();
+        assert_eq!(list.as_ref(), [1, 2]);
+
+        assert!(matches!(
+            from_slice::<ListNonTerm<u32>>(&bytes),
+            Err(Error::IncompleteElement(2))
+        ));
+
+        // Where the size ends before the data does
+        let mut input: &[u8] = &bytes;
+        assert!(matches!(
+            from_reader::<_, ListNonTerm<u32>>(&mut input, 6),
+            Err(Error::IncompleteElement(2))
+        ));
+    }
+}
@@ -16,6 +16,21 @@ pub enum Error {
     Io(#[from] io::Error),
     #[error("packet data is invalid because {0}")]
     Data(String),
+    #[error("deserialization failed because the trailing {0} bytes don't form a complete element")]
+    IncompleteElement(usize),
+    #[error("packet id {} is unexpected in the {expected} state", packet_id(.got))]
+    UnexpectedPacket {
+        expected: &'static str,
+        got: (u8, Option<u16>),
+    },
+}
+
+// Extended packets are shown with their extended id after the packet id
+fn packet_id(&(packet_id, extended_id): &(u8, Option<u16>)) -> String {
+    match extended_id {
+        Some(extended_id) => format!("{:#0X}({:#0X})", packet_id, extended_id),
+        None => format!("{:#0X}", packet_id),
+    }
 }
 
 impl ser::Error for Error {
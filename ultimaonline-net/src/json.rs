@@ -0,0 +1,13 @@
+use crate::error::{Error, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+// Converts packets to and from JSON for inspecting them, such as when
+// debugging captures. This is not a wire format: the lengths, terminators
+// and padding implied by the structure of the JSON are left out of it.
+pub fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(value).map_err(|err| Error::ser(err.to_string()))
+}
+
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T> {
+    serde_json::from_str(json).map_err(|err| Error::de(err.to_string()))
+}
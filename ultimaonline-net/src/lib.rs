@@ -1,6 +1,8 @@
 pub mod compression;
 pub mod de;
 pub mod error;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod packets;
 pub mod ser;
 pub mod types;
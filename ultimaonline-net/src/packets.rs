@@ -1,6 +1,10 @@
-use crate::error::Result;
-use serde::Serialize;
-use std::io::{BufRead, Write};
+use crate::error::{Error, Result};
+use serde::ser::{self, Serialize, SerializeStruct, Serializer};
+use std::{
+    convert::TryFrom,
+    io::{BufRead, Write},
+    mem::size_of,
+};
 
 pub mod action;
 pub mod char_login;
@@ -10,33 +14,130 @@ pub mod client_info;
 pub mod entity;
 pub mod gump;
 pub mod housing;
+pub mod item;
 pub mod login;
 pub mod map;
 pub mod mobile;
 pub mod movement;
 pub mod network;
+pub mod trade;
+pub mod vendor;
 pub mod world;
 
 pub const EXTENDED_PACKET_ID: u8 = 0xBF;
 
-#[derive(Serialize)]
 pub struct Packet<T> {
     id: u8,
-    size: Option<u16>,
+    var_size: bool,
     contents: T,
 }
 
+impl<T> Packet<T> {
+    // Packet id and size
+    const VAR_HEADER_SIZE: usize = size_of::<u8>() + size_of::<u16>();
+
+    // Enough for most variable size packets to be written without growing
+    const VAR_INITIAL_CAPACITY: usize = 256;
+}
+
 impl<T> Packet<T>
 where
     T: Serialize,
 {
-    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
-        crate::ser::to_writer(writer, self)
+    // Appends the packet to dst, returning its size. Variable size packets
+    // are serialized once, with their size filled in afterwards.
+    pub fn append_to(&self, dst: &mut Vec<u8>) -> Result<usize> {
+        let start = dst.len();
+        let result = if self.var_size {
+            self.append_var_to(dst, start)
+        } else {
+            crate::ser::to_writer(dst, self)
+        };
+
+        // Don't leave part of a packet behind
+        if result.is_err() {
+            dst.truncate(start);
+        }
+        result
+    }
+
+    fn append_var_to(&self, dst: &mut Vec<u8>, start: usize) -> Result<usize> {
+        dst.push(self.id);
+        dst.extend_from_slice(&[0, 0]); // Filled in below
+        crate::ser::to_writer(dst, &self.contents)?;
+
+        let size = dst.len() - start;
+        let size_field = u16::try_from(size).map_err(|_| Error::ser("packet is too large"))?;
+        dst[(start + size_of::<u8>())..(start + Self::VAR_HEADER_SIZE)]
+            .copy_from_slice(&size_field.to_be_bytes());
+
+        Ok(size)
+    }
+
+    // Returns the size of the packet written
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<usize> {
+        if !self.var_size {
+            return crate::ser::to_writer(writer, self);
+        }
+
+        let mut buffer = Vec::with_capacity(Self::VAR_INITIAL_CAPACITY);
+        let size = self.append_to(&mut buffer)?;
+        writer.write_all(&buffer)?;
+
+        Ok(size)
+    }
+}
+
+// Serializing a variable size packet directly, rather than through
+// append_to or to_writer, serializes its contents twice: once to find its
+// size.
+impl<T> Serialize for Packet<T>
+where
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut packet = serializer.serialize_struct("Packet", 3)?;
+        packet.serialize_field("id", &self.id)?;
+        if self.var_size {
+            let size = crate::ser::to_size(&self.contents).map_err(ser::Error::custom)?
+                + Self::VAR_HEADER_SIZE;
+            let size =
+                u16::try_from(size).map_err(|_| ser::Error::custom("packet is too large"))?;
+            packet.serialize_field("size", &size)?;
+        } else {
+            packet.skip_field("size")?;
+        }
+        packet.serialize_field("contents", &self.contents)?;
+        packet.end()
     }
 }
 
 pub trait IntoPacket {
     type Content;
+    const SIZE: Option<usize>; // Not including the id, None for variable size
+}
+
+// Checks that the bytes written for a packet are the size it declares: its
+// fixed size, or the size field of a variable size packet. Encoders call
+// this in debug builds with what they actually wrote, since a Serialize impl
+// which writes something other than what was measured would leave the
+// client reading the wrong amount.
+pub fn check_written_size<P: IntoPacket>(written: &[u8]) {
+    let declared = match P::SIZE {
+        Some(size) => size_of::<u8>() + size,
+        None => match written {
+            [_, hi, lo, ..] => u16::from_be_bytes([*hi, *lo]) as usize,
+            _ => 0,
+        },
+    };
+
+    assert_eq!(
+        written.len(),
+        declared,
+        "{} packet 0x{:02X} wrote a different size than it declared",
+        std::any::type_name::<P>(),
+        written.first().copied().unwrap_or_default()
+    );
 }
 
 pub trait FromPacketData
@@ -46,6 +147,18 @@ where
     fn from_packet_data<R: BufRead>(reader: &mut R) -> Result<Self>;
 }
 
+// Serializes the packet on its own, with its id and size
+pub fn encode<'a, P>(pkt: &'a P) -> Result<Vec<u8>>
+where
+    &'a P: IntoPacket,
+    <&'a P as IntoPacket>::Content: Serialize,
+    Packet<<&'a P as IntoPacket>::Content>: From<&'a P>,
+{
+    let mut buffer = Vec::new();
+    Packet::from(pkt).append_to(&mut buffer)?;
+    Ok(buffer)
+}
+
 pub fn write_packet<T, U, W: Write>(content: T, dst: &mut W) -> Result<()>
 where
     T: Serialize,
@@ -55,3 +168,396 @@ where
     Packet::<U>::from(content).to_writer(dst)?;
     Ok(())
 }
+
+// A packet known to this crate, for tools which enumerate the protocol
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PacketInfo {
+    pub name: &'static str, // The module and type, e.g. "mobile::WarMode"
+    pub id: u8,
+    pub extended_id: Option<u16>,
+    pub size: Option<usize>, // Not including the id
+}
+
+macro_rules! registry {
+    ($($module:ident::$name:ident),* $(,)?) => {
+        const REGISTRY: &[PacketInfo] = &[
+            $(PacketInfo {
+                name: concat!(stringify!($module), "::", stringify!($name)),
+                id: $module::$name::PACKET_ID,
+                extended_id: $module::$name::EXTENDED_ID,
+                size: $module::$name::SIZE,
+            }),*
+        ];
+    };
+}
+
+// Every packet type should be listed here, which the tests check
+registry! {
+        action::ClickUse,
+        action::ClickLook,
+        action::AttackRequest,
+        action::AttackOk,
+        action::AttackEnd,
+        action::TargetRequest,
+        action::TargetResponse,
+        char_login::LoginConfirmation,
+        char_login::LoginComplete,
+        char_login::CharStatus,
+        char_login::ExtendedStats,
+        char_login::ResurrectMenu,
+        char_login::Skills,
+        char_login::SkillLockChange,
+        char_login::StatLockChange,
+        char_select::GameLogin,
+        char_select::Features,
+        char_select::CharList,
+        char_select::CreateCharacter,
+        char_select::VersionReq,
+        char_select::VersionResp,
+        chat::OpenWindow,
+        chat::AsciiMessage,
+        chat::UnicodeMessage,
+        chat::ClilocMessage,
+        chat::ClilocAffixMessage,
+        chat::TalkRequest,
+        client_info::WindowSize,
+        client_info::Language,
+        client_info::Flags,
+        client_info::ContextMenuRequest,
+        client_info::ContextMenuDisplay,
+        client_info::ContextMenuResponse,
+        client_info::ViewRange,
+        entity::EntityBatchQuery,
+        entity::MegaCliloc,
+        entity::DeleteObject,
+        gump::CloseStatus,
+        gump::CloseGeneric,
+        gump::DisplayGump,
+        gump::CompressedGump,
+        gump::GumpResponse,
+        housing::ShowPublicContent,
+        housing::CustomHouse,
+        housing::DesignRequest,
+        item::OpenContainer,
+        item::ContainerContents,
+        item::WorldItem,
+        item::WorldItemLegacy,
+        item::LiftRequest,
+        item::DropRequest,
+        item::EquipRequest,
+        item::DropReject,
+        item::Corpse,
+        login::ClientHello,
+        login::AccountLogin,
+        login::LoginRejection,
+        login::ServerList,
+        login::ServerListV6,
+        login::ServerSelection,
+        login::GameServerHandoff,
+        map::MapChange,
+        mobile::MobLightLevel,
+        mobile::PlayerUpdate,
+        mobile::State,
+        mobile::Appearance,
+        mobile::WornItem,
+        mobile::Query,
+        mobile::WarMode,
+        mobile::Paperdoll,
+        mobile::StatusBarFlag,
+        mobile::UpdateHealth,
+        mobile::UpdateMana,
+        mobile::UpdateStamina,
+        mobile::BuffBar,
+        mobile::DeathAction,
+        mobile::ProfileRequest,
+        mobile::ProfileResponse,
+        movement::Request,
+        movement::Success,
+        movement::Resync,
+        movement::Reject,
+        network::PingReq,
+        network::PingAck,
+        trade::SecureTrade,
+        trade::TradeResponse,
+        vendor::BuyList,
+        vendor::BuyRequest,
+        vendor::SellList,
+        world::WorldLightLevel,
+        world::SoundEffect,
+        world::PlayMusic,
+        world::Weather,
+        world::Season,
+        world::GameTime,
+        world::GraphicalEffect,
+        world::HuedEffectLegacy,
+        world::HuedEffect,
+        world::OpenMap,
+        world::MapPins,
+}
+
+pub fn registry() -> &'static [PacketInfo] {
+    REGISTRY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::to_writer;
+    use crate::types::LittleEndian;
+    use macros::packet;
+
+    mod registry {
+        use super::*;
+
+        // The packet types declared in each module's source
+        fn declared() -> Vec<String> {
+            let sources = [
+                ("action", include_str!("packets/action.rs")),
+                ("char_login", include_str!("packets/char_login.rs")),
+                ("char_select", include_str!("packets/char_select.rs")),
+                ("chat", include_str!("packets/chat.rs")),
+                ("client_info", include_str!("packets/client_info.rs")),
+                ("entity", include_str!("packets/entity.rs")),
+                ("gump", include_str!("packets/gump.rs")),
+                ("housing", include_str!("packets/housing.rs")),
+                ("item", include_str!("packets/item.rs")),
+                ("login", include_str!("packets/login.rs")),
+                ("map", include_str!("packets/map.rs")),
+                ("mobile", include_str!("packets/mobile.rs")),
+                ("movement", include_str!("packets/movement.rs")),
+                ("network", include_str!("packets/network.rs")),
+                ("trade", include_str!("packets/trade.rs")),
+                ("vendor", include_str!("packets/vendor.rs")),
+                ("world", include_str!("packets/world.rs")),
+            ];
+
+            let mut declared = vec![];
+            for (module, source) in sources.iter() {
+                let mut lines = source.lines();
+                while let Some(line) = lines.next() {
+                    if !line.starts_with("#[packet(") {
+                        continue;
+                    }
+
+                    // Skip any other attributes to find the type's name
+                    let name = lines
+                        .find_map(|line| {
+                            line.strip_prefix("pub struct ")
+                                .or_else(|| line.strip_prefix("pub enum "))
+                        })
+                        .and_then(|rest| rest.split(|c: char| !c.is_alphanumeric()).next())
+                        .expect("Packet attribute without a type");
+                    declared.push(format!("{}::{}", module, name));
+                }
+            }
+
+            declared
+        }
+
+        #[test]
+        fn lists_every_packet() {
+            let registered = registry().iter().map(|info| info.name).collect::<Vec<_>>();
+
+            for name in declared() {
+                assert!(
+                    registered.contains(&name.as_str()),
+                    "{} is missing from the registry",
+                    name
+                );
+            }
+            assert_eq!(registered.len(), declared().len());
+        }
+
+        #[test]
+        fn describes_packets() {
+            let find = |name| registry().iter().find(|info| info.name == name).unwrap();
+
+            assert_eq!(
+                *find("mobile::WarMode"),
+                PacketInfo {
+                    name: "mobile::WarMode",
+                    id: 0x72,
+                    extended_id: None,
+                    size: Some(4),
+                }
+            );
+            assert_eq!(find("housing::DesignRequest").id, EXTENDED_PACKET_ID);
+            assert_eq!(find("housing::DesignRequest").extended_id, Some(0x1E));
+            assert_eq!(find("login::ServerList").size, None);
+        }
+    }
+
+    mod little_endian {
+        use super::*;
+
+        #[packet(fixed(id = 0xFF, size = 14))]
+        struct MixedEndian {
+            big: u32,
+            #[packet_field(le)]
+            little: u32,
+            wrapped: LittleEndian<u16>,
+            #[packet_field(le)]
+            signed: i32,
+        }
+
+        #[test]
+        fn round_trip() {
+            let mixed = MixedEndian {
+                big: 0x12345678,
+                little: 0x12345678,
+                wrapped: 0xABCD.into(),
+                signed: -2,
+            };
+
+            let expected_bytes = [
+                0xFFu8, 0x12, 0x34, 0x56, 0x78, 0x78, 0x56, 0x34, 0x12, 0xCD, 0xAB, 0xFE, 0xFF,
+                0xFF, 0xFF,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&mixed)).expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed = MixedEndian::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, mixed);
+        }
+    }
+
+    mod var_size {
+        use super::*;
+        use crate::types::{FixedStr, List, ListTerm};
+
+        #[packet(var(id = 0xFE))]
+        struct Names {
+            names: ListTerm<u8, u8>,
+        }
+
+        #[packet(extended(id = 0xFFFE))]
+        struct Values {
+            values: List<u16, u8>,
+        }
+
+        #[packet(var(id = 0xFC))]
+        struct Named {
+            kind: u8,
+            name: FixedStr<32>,
+        }
+
+        fn both_passes<T: Serialize>(packet: Packet<T>) -> Vec<u8> {
+            let mut two_pass = Vec::<u8>::new();
+            to_writer(&mut two_pass, &packet).expect("Failed to write packet");
+
+            let mut one_pass = Vec::<u8>::new();
+            let size = packet
+                .to_writer(&mut one_pass)
+                .expect("Failed to write packet");
+
+            assert_eq!(one_pass, two_pass);
+            assert_eq!(size, one_pass.len());
+            one_pass
+        }
+
+        #[test]
+        fn fills_in_size() {
+            let names = Names {
+                names: vec![0x41, 0x42, 0x43].into(),
+            };
+            assert_eq!(
+                both_passes(Packet::<_>::from(&names)),
+                [0xFE, 0x00, 0x07, 0x41, 0x42, 0x43, 0x00]
+            );
+
+            let values = Values {
+                values: vec![0x1234, 0x5678].into(),
+            };
+            assert_eq!(
+                both_passes(Packet::<_>::from(&values)),
+                [0xBF, 0x00, 0x0A, 0xFF, 0xFE, 0x02, 0x12, 0x34, 0x56, 0x78]
+            );
+        }
+
+        #[test]
+        fn unpadded_final_string() {
+            // The name stops where the packet does, rather than being padded
+            let mut input: &[u8] = &[0xFC, 0x00, 0x08, 0x01, 0x6E, 0x61, 0x6D, 0x65];
+            let parsed = Named::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(
+                parsed,
+                Named {
+                    kind: 0x01,
+                    name: "name".into(),
+                }
+            );
+            assert!(input.is_empty());
+        }
+
+        // Writes one more byte each time it's serialized
+        #[derive(Default)]
+        struct Inconsistent {
+            times: std::cell::Cell<usize>,
+        }
+
+        impl Serialize for Inconsistent {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                self.times.set(self.times.get() + 1);
+                serializer.collect_seq(std::iter::repeat_n(0u8, self.times.get()))
+            }
+        }
+
+        impl IntoPacket for Inconsistent {
+            type Content = Self;
+            const SIZE: Option<usize> = None;
+        }
+
+        #[test]
+        fn checks_written_size() {
+            let values = Values {
+                values: vec![0x1234, 0x5678].into(),
+            };
+            check_written_size::<&Values>(&both_passes(Packet::<_>::from(&values)));
+        }
+
+        #[test]
+        #[should_panic(expected = "wrote a different size than it declared")]
+        fn rejects_wrong_written_size() {
+            let packet = Packet {
+                id: 0xFD,
+                var_size: true,
+                contents: Inconsistent::default(),
+            };
+
+            let mut written = vec![];
+            to_writer(&mut written, &packet).unwrap();
+            check_written_size::<Inconsistent>(&written);
+        }
+
+        #[test]
+        fn encodes() {
+            let values = Values {
+                values: vec![0x1234].into(),
+            };
+            assert_eq!(
+                encode(&values).unwrap(),
+                [0xBF, 0x00, 0x08, 0xFF, 0xFE, 0x01, 0x12, 0x34]
+            );
+        }
+
+        #[test]
+        fn rejects_oversized() {
+            let names = Names {
+                names: vec![0x41; u16::MAX as usize].into(),
+            };
+
+            assert!(Packet::<_>::from(&names).to_writer(&mut vec![]).is_err());
+            assert!(encode(&names).is_err());
+            assert!(to_writer(&mut vec![], &Packet::<_>::from(&names)).is_err());
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use crate::types::Serial;
+use crate::types::{Graphic, Serial, WireSize};
 use macros::packet;
+use serde_repr::{Deserialize_repr, Serialize_repr};
 
 #[packet(fixed(id = 0x06, size = 4))]
 pub struct ClickUse {
@@ -10,3 +11,257 @@ pub struct ClickUse {
 pub struct ClickLook {
     serial: Serial,
 }
+
+#[packet(fixed(id = 0x05, size = 4))]
+pub struct AttackRequest {
+    pub serial: Serial,
+}
+
+#[packet(fixed(id = 0xAA, size = 4))]
+pub struct AttackOk {
+    pub serial: Serial,
+}
+
+// Same layout as AttackOk, but clears the client's current combatant. Since
+// they share an id, codecs list only AttackOk, which decodes both.
+#[packet(fixed(id = 0xAA, size = 4))]
+pub struct AttackEnd {
+    pub serial: Serial, // 0x00000000
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum TargetType {
+    Object = 0x00,
+    Ground = 0x01,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum CursorType {
+    Neutral = 0x00,
+    Harmful = 0x01,
+    Helpful = 0x02,
+    Cancel = 0x03,
+}
+
+#[packet(fixed(id = 0x6C, size = 18))]
+pub struct TargetRequest {
+    pub target_type: TargetType,
+    pub cursor: u32,
+    pub cursor_type: CursorType,
+    pub unused_06: [u8; 12], // All zero
+}
+
+#[packet(fixed(id = 0x6C, size = 18))]
+pub struct TargetResponse {
+    pub target_type: TargetType,
+    pub cursor: u32,
+    pub cursor_type: CursorType,
+    pub serial: Serial,
+    pub x: u16,
+    pub y: u16,
+    pub unknown_0e: u8, // 0x00
+    pub z: i8,
+    pub graphic: Graphic,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Object(Serial),
+    Ground {
+        x: u16,
+        y: u16,
+        z: i8,
+        graphic: Graphic,
+    },
+    Cancel,
+}
+
+impl TargetResponse {
+    // The client signals a cancelled cursor either with the cancel cursor type,
+    // or by sending back a response with no serial and no coordinates.
+    pub fn target(&self) -> Target {
+        if self.cursor_type == CursorType::Cancel
+            || (self.serial == 0 && self.x == 0 && self.y == 0)
+        {
+            return Target::Cancel;
+        }
+
+        match self.target_type {
+            TargetType::Object => Target::Object(self.serial),
+            TargetType::Ground => Target::Ground {
+                x: self.x,
+                y: self.y,
+                z: self.z,
+                graphic: self.graphic,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::{FromPacketData, Packet};
+    use crate::ser::to_writer;
+    mod attack_request {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let attack_request = AttackRequest { serial: 55858 };
+
+            let mut input: &[u8] = &[0x05u8, 0x00, 0x00, 0xDA, 0x32];
+
+            let parsed =
+                AttackRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, attack_request);
+        }
+    }
+
+    mod attack_ok {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0xAAu8, 0x00, 0x00, 0xDA, 0x32];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&AttackOk { serial: 55858 }))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod attack_end {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0xAAu8, 0x00, 0x00, 0x00, 0x00];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&AttackEnd { serial: 0 }))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+
+        #[test]
+        fn deserialize() {
+            let attack_end = AttackEnd { serial: 0 };
+
+            let mut input: &[u8] = &[0xAAu8, 0x00, 0x00, 0x00, 0x00];
+
+            let parsed = AttackEnd::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, attack_end);
+        }
+    }
+
+    mod target_request {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0x6Cu8, 0x00, 0x00, 0x00, 0x00, 0x2A, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&TargetRequest {
+                    target_type: TargetType::Object,
+                    cursor: 42,
+                    cursor_type: CursorType::Harmful,
+                    unused_06: [0u8; 12],
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod target_response {
+        use super::*;
+
+        #[test]
+        fn deserialize_object() {
+            let target_response = TargetResponse {
+                target_type: TargetType::Object,
+                cursor: 42,
+                cursor_type: CursorType::Harmful,
+                serial: 55858,
+                x: 3668,
+                y: 2625,
+                unknown_0e: 0,
+                z: 0,
+                graphic: 401,
+            };
+
+            let mut input: &[u8] = &[
+                0x6Cu8, 0x00, 0x00, 0x00, 0x00, 0x2A, 0x01, 0x00, 0x00, 0xDA, 0x32, 0x0E, 0x54,
+                0x0A, 0x41, 0x00, 0x00, 0x01, 0x91,
+            ];
+
+            let parsed =
+                TargetResponse::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, target_response);
+            assert_eq!(parsed.target(), Target::Object(55858));
+        }
+
+        #[test]
+        fn deserialize_ground() {
+            let target_response = TargetResponse {
+                target_type: TargetType::Ground,
+                cursor: 7,
+                cursor_type: CursorType::Neutral,
+                serial: 0,
+                x: 3670,
+                y: 2630,
+                unknown_0e: 0,
+                z: -3,
+                graphic: 0x0004,
+            };
+
+            let mut input: &[u8] = &[
+                0x6Cu8, 0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0E, 0x56,
+                0x0A, 0x46, 0x00, 0xFD, 0x00, 0x04,
+            ];
+
+            let parsed =
+                TargetResponse::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, target_response);
+            assert_eq!(
+                parsed.target(),
+                Target::Ground {
+                    x: 3670,
+                    y: 2630,
+                    z: -3,
+                    graphic: 0x0004,
+                }
+            );
+        }
+
+        #[test]
+        fn deserialize_cancel() {
+            let mut input: &[u8] = &[
+                0x6Cu8, 0x00, 0x00, 0x00, 0x00, 0x2A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ];
+
+            let parsed =
+                TargetResponse::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed.target(), Target::Cancel);
+        }
+    }
+}
@@ -1,6 +1,7 @@
-use crate::types::{Direction, Graphic, Name, Race, Serial};
+use crate::packets::char_select::SkillType;
+use crate::types::{Direction, Graphic, ListTerm, Name, Race, Serial};
 use macros::packet;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
@@ -49,7 +50,7 @@ pub struct CharStatus {
     pub name: Name,
     pub hitpoints: Attribute,
     pub renamable: bool,
-    pub version: u8, // 0x06
+    pub version: u8, // 0x05 or 0x06
     pub gender: bool,
     pub strength: Stat,
     pub dexterity: Stat,
@@ -72,8 +73,185 @@ pub struct CharStatus {
     pub damage_max: u16,
     pub tithing_points: u32,
 
-    // Age of Shadows stats
-    pub aos_stats: [Stat; 15],
+    // Age of Shadows stats, only in version 6
+    pub aos_stats: Option<[Stat; 15]>,
+}
+
+// Stats lock the same way that skills do, packed into a single byte with
+// two bits for each stat
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatLocks {
+    pub strength: SkillLock,
+    pub dexterity: SkillLock,
+    pub intelligence: SkillLock,
+}
+
+impl StatLocks {
+    const STRENGTH_SHIFT: u8 = 4;
+    const DEXTERITY_SHIFT: u8 = 2;
+    const INTELLIGENCE_SHIFT: u8 = 0;
+
+    pub fn set(&mut self, stat: StatType, lock: SkillLock) {
+        match stat {
+            StatType::Strength => self.strength = lock,
+            StatType::Dexterity => self.dexterity = lock,
+            StatType::Intelligence => self.intelligence = lock,
+        }
+    }
+}
+
+impl Serialize for StatLocks {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(
+            (self.strength as u8) << Self::STRENGTH_SHIFT
+                | (self.dexterity as u8) << Self::DEXTERITY_SHIFT
+                | (self.intelligence as u8) << Self::INTELLIGENCE_SHIFT,
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for StatLocks {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{self, IntoDeserializer};
+
+        let bits = u8::deserialize(deserializer)?;
+        if bits >> 6 != 0 {
+            return Err(de::Error::custom("unknown stat lock bits"));
+        }
+
+        let lock = |shift: u8| SkillLock::deserialize(((bits >> shift) & 0x03).into_deserializer());
+        Ok(Self {
+            strength: lock(Self::STRENGTH_SHIFT)?,
+            dexterity: lock(Self::DEXTERITY_SHIFT)?,
+            intelligence: lock(Self::INTELLIGENCE_SHIFT)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum StatType {
+    Strength = 0x00,
+    Dexterity = 0x01,
+    Intelligence = 0x02,
+}
+
+// Sent by the client when the player changes a stat's lock
+#[packet(extended(id = 0x1A))]
+pub struct StatLockChange {
+    pub stat: StatType,
+    pub lock: SkillLock,
+}
+
+// Companion to CharStatus for clients since Age of Shadows
+#[packet(extended(id = 0x19))]
+pub struct ExtendedStats {
+    pub kind: u8, // 0x02 for stat locks
+    pub serial: Serial,
+    pub unknown_05: u8, // 0x00
+    pub locks: StatLocks,
+}
+
+#[packet(fixed(id = 0x2C, size = 1))]
+pub struct ResurrectMenu {
+    // From the server, 0x00 when dead and 0x02 when alive.
+    // From the client, 0x01 to resurrect or 0x02 to remain a ghost.
+    pub action: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum SkillLock {
+    Up = 0x00,
+    Down = 0x01,
+    Locked = 0x02,
+}
+
+// Skill ids are sent as 16-bit values. Within a skill list they are numbered
+// from one, since a zero id terminates the list.
+mod skill_id {
+    use super::*;
+    use serde::de::{self, IntoDeserializer};
+    use std::convert::TryFrom;
+
+    fn from_id<'de, D: Deserializer<'de>>(id: Option<u16>) -> Result<SkillType, D::Error> {
+        let id = id
+            .and_then(|id| u8::try_from(id).ok())
+            .ok_or_else(|| de::Error::custom("skill id out of range"))?;
+
+        SkillType::deserialize(id.into_deserializer())
+    }
+
+    pub mod zero_based {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            skill: &SkillType,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.serialize_u16(*skill as u16)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<SkillType, D::Error> {
+            from_id::<D>(Some(u16::deserialize(deserializer)?))
+        }
+    }
+
+    pub mod one_based {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            skill: &SkillType,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.serialize_u16(*skill as u16 + 1)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<SkillType, D::Error> {
+            from_id::<D>(u16::deserialize(deserializer)?.checked_sub(1))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Skill {
+    #[serde(with = "skill_id::one_based")]
+    pub skill: SkillType,
+    pub value: u16, // Tenths of a point, including modifiers
+    pub base: u16,
+    pub lock: SkillLock,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CappedSkill {
+    #[serde(with = "skill_id::one_based")]
+    pub skill: SkillType,
+    pub value: u16, // Tenths of a point, including modifiers
+    pub base: u16,
+    pub lock: SkillLock,
+    pub cap: u16,
+}
+
+// The leading kind byte decides whether each skill has a cap.
+// TODO: Single updates (0xFF, or 0xDF with a cap) number the skill from
+// zero and have no terminator, so they need their own layout.
+#[packet(var(id = 0x3A))]
+pub enum Skills {
+    #[packet_tag(0x00)]
+    Full { skills: ListTerm<Skill, u16> },
+    #[packet_tag(0x02)]
+    FullWithCap { skills: ListTerm<CappedSkill, u16> },
+}
+
+#[packet(var(id = 0x3A))]
+pub struct SkillLockChange {
+    #[serde(with = "skill_id::zero_based")]
+    pub skill: SkillType,
+    pub lock: SkillLock,
 }
 
 #[cfg(test)]
@@ -81,6 +259,220 @@ mod tests {
     use super::*;
     use crate::packets::{FromPacketData, Packet};
     use crate::ser::to_writer;
+    mod char_status {
+        use super::*;
+
+        fn char_status(version: u8) -> CharStatus {
+            CharStatus {
+                serial: 3833,
+                name: "Hamled".into(),
+                hitpoints: Attribute {
+                    current: 90,
+                    maximum: 100,
+                },
+                renamable: false,
+                version,
+                gender: false,
+                strength: 20,
+                dexterity: 25,
+                intelligence: 30,
+                stamina: Default::default(),
+                mana: Default::default(),
+                gold: 1000,
+                phys_resist: 50,
+                weight: Default::default(),
+                race: Race::Human,
+                stat_cap: 300,
+                follower_count: 0,
+                follower_max: 5,
+                fire_resist: 50,
+                cold_resist: 50,
+                poison_resist: 50,
+                energy_resist: 50,
+                luck: 20,
+                damage_min: 1,
+                damage_max: 4,
+                tithing_points: 0,
+                aos_stats: match version {
+                    6 => Some([7; 15]),
+                    _ => None,
+                },
+            }
+        }
+
+        #[test]
+        fn round_trip() {
+            for version in [5, 6] {
+                let status = char_status(version);
+
+                let mut packet = Vec::<u8>::new();
+                Packet::<_>::from(&status)
+                    .to_writer(&mut packet)
+                    .expect("Failed to write packet");
+                assert_eq!(packet.len(), if version == 6 { 121 } else { 91 });
+
+                let parsed = CharStatus::from_packet_data(&mut packet.as_slice())
+                    .expect("Failed to parse packet");
+                assert_eq!(parsed, status);
+            }
+        }
+    }
+
+    mod extended_stats {
+        use super::*;
+
+        fn extended_stats() -> ExtendedStats {
+            ExtendedStats {
+                kind: 0x02,
+                serial: 3833,
+                unknown_05: 0x00,
+                locks: StatLocks {
+                    strength: SkillLock::Up,
+                    dexterity: SkillLock::Down,
+                    intelligence: SkillLock::Locked,
+                },
+            }
+        }
+
+        const EXTENDED_STATS_BYTES: [u8; 12] = [
+            0xBFu8, 0x00, 0x0C, 0x00, 0x19, 0x02, 0x00, 0x00, 0x0E, 0xF9, 0x00, 0x06,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&extended_stats()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), EXTENDED_STATS_BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &EXTENDED_STATS_BYTES;
+            let parsed =
+                ExtendedStats::from_packet_data(&mut input).expect("Failed to parse packet");
+            assert_eq!(parsed, extended_stats());
+
+            let mut bytes = EXTENDED_STATS_BYTES;
+            bytes[11] = 0x03; // Not a lock
+            assert!(ExtendedStats::from_packet_data(&mut &bytes[..]).is_err());
+        }
+    }
+
+    mod skills {
+        use super::*;
+
+        #[test]
+        fn serialize_full() {
+            let expected_bytes = [
+                0x3Au8, 0x00, 0x14, 0x00, 0x00, 0x01, 0x03, 0xE8, 0x03, 0xE8, 0x00, 0x00, 0x1C,
+                0x01, 0xF4, 0x01, 0xC2, 0x02, 0x00, 0x00,
+            ];
+
+            let skills = Skills::Full {
+                skills: vec![
+                    Skill {
+                        skill: SkillType::Alchemy,
+                        value: 1000,
+                        base: 1000,
+                        lock: SkillLock::Up,
+                    },
+                    Skill {
+                        skill: SkillType::Tactics,
+                        value: 500,
+                        base: 450,
+                        lock: SkillLock::Locked,
+                    },
+                ]
+                .into(),
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&skills)).expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+
+            let parsed =
+                Skills::from_packet_data(&mut packet.as_slice()).expect("Failed to parse packet");
+            assert_eq!(parsed, skills);
+        }
+
+        #[test]
+        fn serialize_with_cap() {
+            let expected_bytes = [
+                0x3Au8, 0x00, 0x18, 0x02, 0x00, 0x01, 0x03, 0xE8, 0x03, 0xE8, 0x00, 0x03, 0xE8,
+                0x00, 0x1C, 0x01, 0xF4, 0x01, 0xC2, 0x02, 0x03, 0xE8, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&Skills::FullWithCap {
+                    skills: vec![
+                        CappedSkill {
+                            skill: SkillType::Alchemy,
+                            value: 1000,
+                            base: 1000,
+                            lock: SkillLock::Up,
+                            cap: 1000,
+                        },
+                        CappedSkill {
+                            skill: SkillType::Tactics,
+                            value: 500,
+                            base: 450,
+                            lock: SkillLock::Locked,
+                            cap: 1000,
+                        },
+                    ]
+                    .into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod stat_lock_change {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &[0xBFu8, 0x00, 0x07, 0x00, 0x1A, 0x01, 0x02];
+
+            let parsed =
+                StatLockChange::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(
+                parsed,
+                StatLockChange {
+                    stat: StatType::Dexterity,
+                    lock: SkillLock::Locked,
+                }
+            );
+        }
+    }
+
+    mod skill_lock_change {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let skill_lock_change = SkillLockChange {
+                skill: SkillType::Magery,
+                lock: SkillLock::Down,
+            };
+
+            let mut input: &[u8] = &[0x3Au8, 0x00, 0x06, 0x00, 0x19, 0x01];
+
+            let parsed =
+                SkillLockChange::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, skill_lock_change);
+        }
+    }
+
     mod login_complete {
         use super::*;
 
@@ -1,4 +1,4 @@
-use crate::types::{CharIdentity, FixedStr, Graphic, Hue, List, Name};
+use crate::types::{CharIdentity, FixedStr, Flags32, Graphic, Hue, List, Name, WireSize};
 use macros::packet;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
@@ -12,17 +12,40 @@ pub struct GameLogin {
 
 #[packet(fixed(id = 0xB9, size = 4))]
 pub struct Features {
-    pub flags: u32,
+    pub flags: Flags32,
+}
+
+// Expansion features which the client may use
+impl Features {
+    pub const THIRD_DAWN: Flags32 = Flags32(0x0000_0004);
+    pub const SIXTH_CHAR_SLOT: Flags32 = Flags32(0x0000_0020);
+    pub const EIGHTH_AGE: Flags32 = Flags32(0x0000_0100);
+    pub const TENTH_AGE: Flags32 = Flags32(0x0000_0400);
+    pub const INCREASED_STORAGE: Flags32 = Flags32(0x0000_0800);
+    pub const ROLEPLAY_FACES: Flags32 = Flags32(0x0000_2000);
+    pub const TRIAL_ACCOUNT: Flags32 = Flags32(0x0000_4000);
+
+    // Every feature up to Endless Journey
+    pub const ALL: Flags32 = Flags32(0x00FF_FFFF);
 }
 
 #[packet(var(id = 0xA9))]
 pub struct CharList {
     pub chars: List<CharInfo, u8>,
     pub cities: List<CityInfo, u8>,
-    pub flags: u32,
+    pub flags: Flags32,
     pub unknown_var1: i32,
 }
 
+// Options for the character list and the client in general
+impl CharList {
+    pub const CONTEXT_MENUS: Flags32 = Flags32(0x0000_0008);
+    pub const AOS_EXPANSION: Flags32 = Flags32(0x0000_0020);
+    pub const SE_EXPANSION: Flags32 = Flags32(0x0000_0080);
+    pub const ML_EXPANSION: Flags32 = Flags32(0x0000_0100);
+    pub const SEVENTH_CHAR_SLOT: Flags32 = Flags32(0x0000_1000);
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CharInfo {
     pub name: Name,
@@ -65,7 +88,7 @@ pub struct CityInfo {
     pub unknown_15: i32,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
 #[repr(u8)]
 pub enum SkillType {
     Alchemy,
@@ -128,13 +151,13 @@ pub enum SkillType {
     Throwing,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, WireSize)]
 pub struct SkillChoice {
     ty: SkillType,
     val: u8,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
 #[repr(u8)]
 pub enum Profession {
     Warrior = 1,
@@ -146,7 +169,7 @@ pub enum Profession {
     Ninja,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, WireSize)]
 pub struct CharAppearance {
     hue: Hue,
     hair_hue: Hue,
@@ -155,7 +178,7 @@ pub struct CharAppearance {
     beard_graphic: Graphic,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, WireSize)]
 pub struct Character {
     profession: Profession,
 
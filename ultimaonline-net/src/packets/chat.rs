@@ -1,4 +1,11 @@
+use crate::error::{Error, Result};
+use crate::types::{
+    list::{ListNonTerm, ListTerm},
+    FixedStr, Graphic, Hue, LittleEndian, Name, Serial, UnicodeStr,
+};
 use macros::packet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 
 // TODO: Figure out if this will have actual content
 // ModernUO implementation says it doesn't.
@@ -7,3 +14,450 @@ pub struct OpenWindow {
     pub unused_00: [u8; 0x20], // All zeros?
     pub unused_20: [u8; 0x1F], // All zeros?
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum MessageType {
+    Regular = 0x00,
+    System = 0x01,
+    Emote = 0x02,
+    Label = 0x06,
+    Focus = 0x07,
+    Whisper = 0x08,
+    Yell = 0x09,
+    Spell = 0x0A,
+    Guild = 0x0D,
+    Alliance = 0x0E,
+    Command = 0x0F,
+}
+
+#[packet(var(id = 0x1C))]
+pub struct AsciiMessage {
+    pub serial: Serial,
+    pub graphic: Graphic,
+    pub kind: MessageType,
+    pub hue: Hue,
+    pub font: u16,
+    pub name: Name,
+    pub text: String,
+}
+
+#[packet(var(id = 0xAE))]
+pub struct UnicodeMessage {
+    pub serial: Serial,
+    pub graphic: Graphic,
+    pub kind: MessageType,
+    pub hue: Hue,
+    pub font: u16,
+    pub language: FixedStr<4>,
+    pub name: Name,
+    pub text: UnicodeStr,
+}
+
+// The arguments of a cliloc message replace the ~N_NAME~ placeholders in
+// the localized text, in order. They are sent as a single string of
+// null-terminated UTF-16LE, separated by tabs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClilocArgs(String);
+
+impl ClilocArgs {
+    pub const SEPARATOR: char = '\t';
+
+    pub fn new<S: AsRef<str>>(args: &[S]) -> Self {
+        let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+        Self(args.join(&Self::SEPARATOR.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn args(&self) -> Vec<&str> {
+        match self.0.as_str() {
+            "" => vec![],
+            args => args.split(Self::SEPARATOR).collect(),
+        }
+    }
+}
+
+impl Serialize for ClilocArgs {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&self.0);
+        }
+
+        let units: Vec<LittleEndian<u16>> = self.0.encode_utf16().map(Into::into).collect();
+        if units.iter().any(|unit| unit.0 == 0) {
+            return Err(serde::ser::Error::custom(
+                "cliloc arguments cannot contain a null character",
+            ));
+        }
+
+        ListTerm::<LittleEndian<u16>, u16>::from(units).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ClilocArgs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            return String::deserialize(deserializer).map(Self);
+        }
+
+        let units: Vec<LittleEndian<u16>> =
+            ListTerm::<LittleEndian<u16>, u16>::deserialize(deserializer)?.into();
+        let units: Vec<u16> = units.into_iter().map(|unit| unit.0).collect();
+
+        String::from_utf16(&units)
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+// A message localized by the client from its cliloc files
+#[packet(var(id = 0xC1))]
+pub struct ClilocMessage {
+    pub serial: Serial,
+    pub graphic: Graphic,
+    pub kind: MessageType,
+    pub hue: Hue,
+    pub font: u16,
+    pub cliloc_id: u32,
+    pub name: Name,
+    pub args: ClilocArgs,
+}
+
+// A cliloc message with an ASCII affix added before or after the localized
+// text. Unlike ClilocMessage, the arguments are sent as UTF-16BE.
+#[packet(var(id = 0xCC))]
+pub struct ClilocAffixMessage {
+    pub serial: Serial,
+    pub graphic: Graphic,
+    pub kind: MessageType,
+    pub hue: Hue,
+    pub font: u16,
+    pub cliloc_id: u32,
+    pub flags: u8,
+    pub name: Name,
+    pub affix: String,
+    pub args: UnicodeStr, // Separated by tabs, like ClilocArgs
+}
+
+impl ClilocAffixMessage {
+    pub const PREPEND_FLAG: u8 = 0x01; // Otherwise the affix is appended
+    pub const SYSTEM_FLAG: u8 = 0x02;
+    pub const NEW_MESSAGE_FLAG: u8 = 0x04;
+}
+
+// The layout of the speech text depends upon the message type byte.
+// When the encoded flag is set the text is preceded by a list of
+// speech.mul keyword ids packed as 12-bit values, and the text itself
+// is UTF-8. Otherwise the text is UTF-16.
+#[packet(var(id = 0xAD))]
+pub struct TalkRequest {
+    pub kind: u8,
+    pub hue: Hue,
+    pub font: u16,
+    pub language: FixedStr<4>,
+    pub payload: ListNonTerm<u8>,
+}
+
+impl TalkRequest {
+    pub const ENCODED_FLAG: u8 = 0xC0;
+
+    pub fn unicode(kind: MessageType, hue: Hue, font: u16, language: &str, text: &str) -> Self {
+        let mut payload: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        payload.extend_from_slice(&[0, 0]);
+
+        Self {
+            kind: kind as u8,
+            hue,
+            font,
+            language: language.into(),
+            payload: payload.into(),
+        }
+    }
+
+    pub fn encoded(
+        kind: MessageType,
+        hue: Hue,
+        font: u16,
+        language: &str,
+        keywords: &[u16],
+        text: &str,
+    ) -> Self {
+        // Pack the keyword count and each keyword as 12-bit values,
+        // padding out the final byte with zero bits
+        let mut payload = vec![];
+        let mut pending: Option<u16> = None;
+        for &val in std::iter::once(&(keywords.len() as u16)).chain(keywords) {
+            let val = val & 0x0FFF;
+            match pending.take() {
+                None => {
+                    payload.push((val >> 4) as u8);
+                    pending = Some(val & 0x0F);
+                }
+                Some(nibble) => {
+                    payload.push(((nibble << 4) | (val >> 8)) as u8);
+                    payload.push(val as u8);
+                }
+            }
+        }
+        if let Some(nibble) = pending {
+            payload.push((nibble << 4) as u8);
+        }
+
+        payload.extend_from_slice(text.as_bytes());
+        payload.push(0);
+
+        Self {
+            kind: kind as u8 | Self::ENCODED_FLAG,
+            hue,
+            font,
+            language: language.into(),
+            payload: payload.into(),
+        }
+    }
+
+    pub fn is_encoded(&self) -> bool {
+        self.kind & Self::ENCODED_FLAG != 0
+    }
+
+    pub fn message_type(&self) -> u8 {
+        self.kind & !Self::ENCODED_FLAG
+    }
+
+    pub fn keywords(&self) -> Result<Vec<u16>> {
+        if !self.is_encoded() {
+            return Ok(vec![]);
+        }
+
+        let (keywords, _) = self.split_encoded()?;
+        Ok(keywords)
+    }
+
+    pub fn text(&self) -> Result<String> {
+        let payload: &[u8] = self.payload.as_ref();
+
+        if self.is_encoded() {
+            let (_, text) = self.split_encoded()?;
+            let text = text.split(|&by| by == 0).next().unwrap_or_default();
+
+            String::from_utf8(text.to_vec())
+                .map_err(|_| Error::data("speech text is not valid UTF-8"))
+        } else {
+            let units: Vec<u16> = payload
+                .chunks_exact(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                .take_while(|&unit| unit != 0)
+                .collect();
+
+            String::from_utf16(&units).map_err(|_| Error::data("speech text is not valid UTF-16"))
+        }
+    }
+
+    fn split_encoded(&self) -> Result<(Vec<u16>, &[u8])> {
+        let payload: &[u8] = self.payload.as_ref();
+
+        let read_value = |index: usize| -> Result<u16> {
+            let bit = index * 12;
+            let (byte, offset) = (bit / 8, bit % 8);
+            match payload.get(byte..byte + 2) {
+                Some(&[hi, lo]) => {
+                    let word = u16::from_be_bytes([hi, lo]);
+                    Ok((word >> (4 - offset)) & 0x0FFF)
+                }
+                _ => Err(Error::data("speech keywords are truncated")),
+            }
+        };
+
+        let count = read_value(0)? as usize;
+        let keywords = (1..=count).map(read_value).collect::<Result<Vec<u16>>>()?;
+
+        let keywords_len = (12 * (count + 1)).div_ceil(8);
+        Ok((keywords, &payload[keywords_len..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::{FromPacketData, Packet};
+    use crate::ser::to_writer;
+    mod talk_request {
+        use super::*;
+
+        #[test]
+        fn deserialize_unicode() {
+            let talk_request =
+                TalkRequest::unicode(MessageType::Regular, 0x0034, 3, "ENU", "hello");
+
+            let mut input: &[u8] = &[
+                0xADu8, 0x00, 0x18, 0x00, 0x00, 0x34, 0x00, 0x03, 0x45, 0x4E, 0x55, 0x00, 0x00,
+                0x68, 0x00, 0x65, 0x00, 0x6C, 0x00, 0x6C, 0x00, 0x6F, 0x00, 0x00,
+            ];
+
+            let parsed = TalkRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, talk_request);
+            assert!(!parsed.is_encoded());
+            assert_eq!(parsed.message_type(), MessageType::Regular as u8);
+            assert_eq!(parsed.text().expect("Failed to decode text"), "hello");
+        }
+
+        #[test]
+        fn deserialize_encoded() {
+            let talk_request =
+                TalkRequest::encoded(MessageType::Regular, 0x0034, 3, "ENU", &[0x00A], "hello");
+
+            let mut input: &[u8] = &[
+                0xADu8, 0x00, 0x15, 0xC0, 0x00, 0x34, 0x00, 0x03, 0x45, 0x4E, 0x55, 0x00, 0x00,
+                0x10, 0x0A, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x00,
+            ];
+
+            let parsed = TalkRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, talk_request);
+            assert!(parsed.is_encoded());
+            assert_eq!(parsed.message_type(), MessageType::Regular as u8);
+            assert_eq!(
+                parsed.keywords().expect("Failed to decode keywords"),
+                [0x00A]
+            );
+            assert_eq!(parsed.text().expect("Failed to decode text"), "hello");
+        }
+
+        #[test]
+        fn encoded_keyword_packing() {
+            let talk_request =
+                TalkRequest::encoded(MessageType::Regular, 0, 0, "ENU", &[0x123, 0x456], "hi");
+
+            assert_eq!(
+                Vec::from(talk_request.payload.clone()),
+                [0x00, 0x21, 0x23, 0x45, 0x60, 0x68, 0x69, 0x00]
+            );
+            assert_eq!(talk_request.keywords().unwrap(), [0x123, 0x456]);
+            assert_eq!(talk_request.text().unwrap(), "hi");
+        }
+    }
+
+    mod cliloc_message {
+        use super::*;
+
+        fn you_see() -> ClilocMessage {
+            ClilocMessage {
+                serial: 0x00003FE1,
+                graphic: 0x0190,
+                kind: MessageType::Label,
+                hue: 0x03B2,
+                font: 3,
+                cliloc_id: 1005043,
+                name: "Hamled".into(),
+                args: ClilocArgs::new(&["Hamled"]),
+            }
+        }
+
+        const YOU_SEE_BYTES: [u8; 62] = [
+            0xC1u8, 0x00, 0x3E, 0x00, 0x00, 0x3F, 0xE1, 0x01, 0x90, 0x06, 0x03, 0xB2, 0x00, 0x03,
+            0x00, 0x0F, 0x55, 0xF3, 0x48, 0x61, 0x6D, 0x6C, 0x65, 0x64, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x48, 0x00, 0x61, 0x00, 0x6D, 0x00, 0x6C, 0x00,
+            0x65, 0x00, 0x64, 0x00, 0x00, 0x00,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&you_see())).expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), YOU_SEE_BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &YOU_SEE_BYTES;
+
+            let parsed =
+                ClilocMessage::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, you_see());
+            assert_eq!(parsed.args.args(), ["Hamled"]);
+        }
+
+        #[test]
+        fn args() {
+            let args = ClilocArgs::new(&["first", "second"]);
+            assert_eq!(args.as_str(), "first\tsecond");
+            assert_eq!(args.args(), ["first", "second"]);
+
+            assert!(ClilocArgs::new::<&str>(&[]).args().is_empty());
+        }
+    }
+
+    mod cliloc_affix_message {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0xCCu8, 0x00, 0x3D, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 0x03, 0xB2, 0x00,
+                0x03, 0x00, 0x0F, 0x55, 0xF3, 0x03, 0x53, 0x79, 0x73, 0x74, 0x65, 0x6D, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5B, 0x47, 0x4D, 0x5D, 0x20, 0x00,
+                0x00, 0x6F, 0x00, 0x6B, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&ClilocAffixMessage {
+                    serial: 0xFFFFFFFF,
+                    graphic: 0xFFFF,
+                    kind: MessageType::System,
+                    hue: 0x03B2,
+                    font: 3,
+                    cliloc_id: 1005043,
+                    flags: ClilocAffixMessage::PREPEND_FLAG | ClilocAffixMessage::SYSTEM_FLAG,
+                    name: "System".into(),
+                    affix: "[GM] ".to_string(),
+                    args: "ok".into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod unicode_message {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0xAEu8, 0x00, 0x38, 0x00, 0x00, 0x0E, 0xF9, 0x01, 0x91, 0x00, 0x00, 0x34, 0x00,
+                0x03, 0x45, 0x4E, 0x55, 0x00, 0x48, 0x61, 0x6D, 0x6C, 0x65, 0x64, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x68, 0x00, 0x69, 0x00, 0x21, 0x00,
+                0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&UnicodeMessage {
+                    serial: 3833,
+                    graphic: 401,
+                    kind: MessageType::Regular,
+                    hue: 0x0034,
+                    font: 3,
+                    language: "ENU".into(),
+                    name: "Hamled".into(),
+                    text: "hi!".into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use macros::packet;
+use serde::{Deserialize, Serialize};
 
-use crate::types::FixedStr;
+use crate::types::{FixedStr, Flags32, List, Serial};
 
 #[packet(extended(id = 0x05))]
 pub struct WindowSize {
@@ -19,7 +20,34 @@ pub struct Language {
 #[packet(extended(id = 0x0F))]
 pub struct Flags {
     pub unknown_00: u8, // Always 0x0A
-    pub flags: u32,     // Always 0xFFFFFFFF
+    pub flags: Flags32, // Always 0xFFFFFFFF
+}
+
+#[packet(extended(id = 0x13))]
+pub struct ContextMenuRequest {
+    pub serial: Serial,
+}
+
+// Only the original layout is supported, where entries refer to clilocs
+// by their offset from 3000000 and may not be hued (flag 0x20).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContextMenuEntry {
+    pub entry_id: u16,
+    pub cliloc: u16,
+    pub flags: u16, // 0x01 disabled
+}
+
+#[packet(extended(id = 0x14))]
+pub struct ContextMenuDisplay {
+    pub unknown_00: u16, // 0x0001
+    pub serial: Serial,
+    pub entries: List<ContextMenuEntry, u8>,
+}
+
+#[packet(extended(id = 0x15))]
+pub struct ContextMenuResponse {
+    pub serial: Serial,
+    pub entry_id: u16,
 }
 
 #[packet(fixed(id = 0xC8, size = 1))]
@@ -32,6 +60,80 @@ mod tests {
     use super::*;
     use crate::packets::{FromPacketData, Packet};
     use crate::ser::to_writer;
+    mod context_menu_request {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let context_menu_request = ContextMenuRequest { serial: 55858 };
+
+            let mut input: &[u8] = &[0xBFu8, 0x00, 0x09, 0x00, 0x13, 0x00, 0x00, 0xDA, 0x32];
+
+            let parsed =
+                ContextMenuRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, context_menu_request);
+        }
+    }
+
+    mod context_menu_display {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0xBFu8, 0x00, 0x18, 0x00, 0x14, 0x00, 0x01, 0x00, 0x00, 0xDA, 0x32, 0x02, 0x00,
+                0x01, 0x17, 0x7A, 0x00, 0x00, 0x00, 0x02, 0x17, 0x6D, 0x00, 0x01,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&ContextMenuDisplay {
+                    unknown_00: 0x0001,
+                    serial: 55858,
+                    entries: vec![
+                        ContextMenuEntry {
+                            entry_id: 1,
+                            cliloc: 6010, // Buy
+                            flags: 0,
+                        },
+                        ContextMenuEntry {
+                            entry_id: 2,
+                            cliloc: 5997, // Sell
+                            flags: 0x01,
+                        },
+                    ]
+                    .into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod context_menu_response {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let context_menu_response = ContextMenuResponse {
+                serial: 55858,
+                entry_id: 2,
+            };
+
+            let mut input: &[u8] = &[
+                0xBFu8, 0x00, 0x0B, 0x00, 0x15, 0x00, 0x00, 0xDA, 0x32, 0x00, 0x02,
+            ];
+
+            let parsed =
+                ContextMenuResponse::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, context_menu_response);
+        }
+    }
+
     mod window_size {
         use super::*;
 
@@ -1,11 +1,77 @@
-use crate::types::{list::ListNonTerm, Serial};
+use crate::types::{list::ListNonTerm, List, ListTerm, Serial};
 use macros::packet;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
+// Sent by the client to request the tooltips (0xD6) for one or more entities
 #[packet(var(id = 0xD6))]
 pub struct EntityBatchQuery {
     pub serials: ListNonTerm<Serial>,
 }
 
+// The cliloc arguments are sent as UTF-16LE, prefixed by their length in bytes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ClilocEntryRaw", into = "ClilocEntryRaw")]
+pub struct ClilocEntry {
+    pub cliloc_id: u32,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ClilocEntryRaw {
+    cliloc_id: u32,
+    text: List<u8, u16>,
+}
+
+impl From<ClilocEntry> for ClilocEntryRaw {
+    fn from(entry: ClilocEntry) -> Self {
+        Self {
+            cliloc_id: entry.cliloc_id,
+            text: entry
+                .text
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect::<Vec<u8>>()
+                .into(),
+        }
+    }
+}
+
+impl TryFrom<ClilocEntryRaw> for ClilocEntry {
+    type Error = String;
+
+    fn try_from(raw: ClilocEntryRaw) -> Result<Self, Self::Error> {
+        let bytes: Vec<u8> = raw.text.into();
+        if !bytes.len().is_multiple_of(2) {
+            return Err("cliloc arguments have an odd number of bytes".to_string());
+        }
+
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+
+        Ok(Self {
+            cliloc_id: raw.cliloc_id,
+            text: String::from_utf16(&units).map_err(|err| err.to_string())?,
+        })
+    }
+}
+
+#[packet(var(id = 0xD6))]
+pub struct MegaCliloc {
+    pub unknown_00: u16, // 0x0001
+    pub serial: Serial,
+    pub unknown_06: u16, // 0x0000
+    pub revision: u32,   // Hash of the entries, so the client can cache them
+    pub entries: ListTerm<ClilocEntry, u32>,
+}
+
+#[packet(fixed(id = 0x1D, size = 4))]
+pub struct DeleteObject {
+    pub serial: Serial,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,4 +112,78 @@ mod tests {
             assert_eq!(parsed, batch_query);
         }
     }
+
+    mod mega_cliloc {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let mega_cliloc = MegaCliloc {
+                unknown_00: 0x0001,
+                serial: 0x40000010,
+                unknown_06: 0,
+                revision: 0x12345678,
+                entries: vec![
+                    ClilocEntry {
+                        cliloc_id: 1050045, // ~1_PREFIX~~2_NAME~~3_SUFFIX~
+                        text: " \tgold coins\t ".to_string(),
+                    },
+                    ClilocEntry {
+                        cliloc_id: 1072788, // Weight: ~1_WEIGHT~ stone
+                        text: "5".to_string(),
+                    },
+                ]
+                .into(),
+            };
+
+            let expected_bytes = [
+                0xD6u8, 0x00, 0x3D, 0x00, 0x01, 0x40, 0x00, 0x00, 0x10, 0x00, 0x00, 0x12, 0x34,
+                0x56, 0x78, 0x00, 0x10, 0x05, 0xBD, 0x00, 0x1C, 0x20, 0x00, 0x09, 0x00, 0x67, 0x00,
+                0x6F, 0x00, 0x6C, 0x00, 0x64, 0x00, 0x20, 0x00, 0x63, 0x00, 0x6F, 0x00, 0x69, 0x00,
+                0x6E, 0x00, 0x73, 0x00, 0x09, 0x00, 0x20, 0x00, 0x00, 0x10, 0x5E, 0x94, 0x00, 0x02,
+                0x35, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&mega_cliloc))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed = MegaCliloc::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, mega_cliloc);
+        }
+    }
+
+    mod delete_object {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0x1Du8, 0x00, 0x00, 0xDA, 0x32];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&DeleteObject { serial: 55858 }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+
+        #[test]
+        fn deserialize() {
+            let delete_object = DeleteObject { serial: 0x40000010 };
+
+            let mut input: &[u8] = &[0x1Du8, 0x40, 0x00, 0x00, 0x10];
+
+            let parsed =
+                DeleteObject::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, delete_object);
+        }
+    }
 }
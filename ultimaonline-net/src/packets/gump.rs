@@ -1,5 +1,8 @@
-use crate::types::Serial;
+use crate::types::{List, Serial};
 use macros::packet;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{convert::TryFrom, fmt};
 
 // TODO: Figure out if we should do something with this.
 // It appears to signal that the HP status tracking UI was
@@ -11,3 +14,455 @@ use macros::packet;
 pub struct CloseStatus {
     serial: Serial,
 }
+
+#[packet(extended(id = 0x04))]
+pub struct CloseGeneric {
+    pub gump: u32,
+    pub button: u32, // Reported back in the gump response
+}
+
+// Gump text is sent as big-endian UTF-16, prefixed by its length in code units
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "List<u16, u16>", into = "List<u16, u16>")]
+pub struct GumpText(String);
+
+impl GumpText {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl From<&str> for GumpText {
+    fn from(string: &str) -> Self {
+        Self(string.to_string())
+    }
+}
+
+impl From<GumpText> for List<u16, u16> {
+    fn from(text: GumpText) -> Self {
+        text.0.encode_utf16().collect::<Vec<u16>>().into()
+    }
+}
+
+impl TryFrom<List<u16, u16>> for GumpText {
+    type Error = std::string::FromUtf16Error;
+
+    fn try_from(units: List<u16, u16>) -> Result<Self, Self::Error> {
+        Ok(Self(String::from_utf16(&Vec::from(units))?))
+    }
+}
+
+// The layout is an ASCII string of commands, such as "{ page 0 }",
+// prefixed by its length including the null terminator.
+mod layout {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(layout: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = layout.as_bytes().to_vec();
+        bytes.push(0);
+
+        List::<u8, u16>::from(bytes).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        let mut bytes = Vec::from(List::<u8, u16>::deserialize(deserializer)?);
+        if bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+
+        String::from_utf8(bytes).map_err(de::Error::custom)
+    }
+}
+
+#[packet(var(id = 0xB0))]
+pub struct DisplayGump {
+    pub serial: Serial,
+    pub gump: u32,
+    pub x: u32,
+    pub y: u32,
+    #[serde(with = "layout")]
+    pub layout: String,
+    pub lines: List<GumpText, u16>,
+}
+
+// A block of zlib-compressed data, prefixed by its compressed length
+// (which also counts the following field) and its decompressed length
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ZlibBlock(Vec<u8>);
+
+// The lengths come from the client, so no more than a whole packet's worth
+// is allocated up front
+const MAX_PREALLOCATED: usize = u16::MAX as usize;
+
+impl Serialize for ZlibBlock {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use flate2::{write::ZlibEncoder, Compression};
+        use serde::ser::{self, SerializeTuple};
+        use std::io::Write;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.0).map_err(ser::Error::custom)?;
+        let compressed = encoder.finish().map_err(ser::Error::custom)?;
+
+        let mut tuple = serializer.serialize_tuple(3)?;
+        tuple.serialize_element(&(compressed.len() as u32 + 4))?;
+        tuple.serialize_element(&(self.0.len() as u32))?;
+        tuple.serialize_element(compressed.as_slice())?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ZlibBlock {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ZlibBlockVisitor;
+
+        impl<'de> Visitor<'de> for ZlibBlockVisitor {
+            type Value = ZlibBlock;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a length-prefixed block of zlib-compressed data")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                use flate2::read::ZlibDecoder;
+                use std::io::Read;
+
+                let mut next_len = |index| {
+                    seq.next_element::<u32>()?
+                        .ok_or_else(|| de::Error::invalid_length(index, &self))
+                };
+                let compressed_len = next_len(0)?
+                    .checked_sub(4)
+                    .ok_or_else(|| de::Error::custom("compressed length is too short"))?;
+                let decompressed_len = next_len(1)?;
+
+                let mut compressed =
+                    Vec::with_capacity((compressed_len as usize).min(MAX_PREALLOCATED));
+                for _ in 0..compressed_len {
+                    compressed.push(
+                        seq.next_element::<u8>()?
+                            .ok_or_else(|| de::Error::invalid_length(2, &self))?,
+                    );
+                }
+
+                // Inflating one byte past the expected length is enough to
+                // tell that it was wrong
+                let mut data =
+                    Vec::with_capacity((decompressed_len as usize).min(MAX_PREALLOCATED));
+                ZlibDecoder::new(compressed.as_slice())
+                    .take(u64::from(decompressed_len) + 1)
+                    .read_to_end(&mut data)
+                    .map_err(de::Error::custom)?;
+                if data.len() != decompressed_len as usize {
+                    return Err(de::Error::custom(
+                        "decompressed length did not match expected length",
+                    ));
+                }
+
+                Ok(ZlibBlock(data))
+            }
+        }
+
+        deserializer.deserialize_tuple(usize::MAX, ZlibBlockVisitor)
+    }
+}
+
+mod compressed_layout {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(layout: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = layout.as_bytes().to_vec();
+        bytes.push(0);
+
+        ZlibBlock(bytes).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        let ZlibBlock(mut bytes) = ZlibBlock::deserialize(deserializer)?;
+        if bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+
+        String::from_utf8(bytes).map_err(de::Error::custom)
+    }
+}
+
+// The text lines are preceded by their count, and the compressed
+// block is omitted entirely when there are no lines.
+mod compressed_lines {
+    use super::*;
+    use crate::types::list::ListNonTerm;
+    use serde::ser::{self, SerializeTuple};
+
+    pub fn serialize<S: Serializer>(lines: &[GumpText], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&(lines.len() as u32))?;
+        if !lines.is_empty() {
+            let mut bytes = Vec::new();
+            crate::ser::to_writer(&mut bytes, &lines).map_err(ser::Error::custom)?;
+            tuple.serialize_element(&ZlibBlock(bytes))?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<GumpText>, D::Error> {
+        struct LinesVisitor;
+
+        impl<'de> Visitor<'de> for LinesVisitor {
+            type Value = Vec<GumpText>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a count of text lines followed by a compressed block")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let count = seq
+                    .next_element::<u32>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                if count == 0 {
+                    return Ok(vec![]);
+                }
+
+                let ZlibBlock(bytes) = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let lines: Vec<GumpText> = crate::de::from_reader::<_, ListNonTerm<GumpText>>(
+                    &mut bytes.as_slice(),
+                    bytes.len(),
+                )
+                .map_err(de::Error::custom)?
+                .into();
+
+                if lines.len() != count as usize {
+                    return Err(de::Error::custom(
+                        "number of text lines did not match expected count",
+                    ));
+                }
+
+                Ok(lines)
+            }
+        }
+
+        deserializer.deserialize_tuple(usize::MAX, LinesVisitor)
+    }
+}
+
+#[packet(var(id = 0xDD))]
+pub struct CompressedGump {
+    pub serial: Serial,
+    pub gump: u32,
+    pub x: u32,
+    pub y: u32,
+    #[serde(with = "compressed_layout")]
+    pub layout: String,
+    #[serde(with = "compressed_lines")]
+    pub lines: Vec<GumpText>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextEntry {
+    pub id: u16,
+    pub text: GumpText,
+}
+
+#[packet(var(id = 0xB1))]
+pub struct GumpResponse {
+    pub serial: Serial,
+    pub gump: u32,
+    pub button: u32, // 0 when the gump was closed
+    pub switches: List<u32, u32>,
+    pub text_entries: List<TextEntry, u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::{FromPacketData, Packet};
+    use crate::ser::to_writer;
+    mod close_generic {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0xBFu8, 0x00, 0x0D, 0x00, 0x04, 0x00, 0x00, 0x12, 0x34, 0x00, 0x00, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&CloseGeneric {
+                    gump: 0x1234,
+                    button: 0,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod display_gump {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let display_gump = DisplayGump {
+                serial: 3833,
+                gump: 0x1234,
+                x: 50,
+                y: 50,
+                layout: "{ page 0 }{ button 10 10 4005 4007 1 0 1 }{ text 45 10 0 0 }\
+                         { button 10 40 4005 4007 1 0 2 }{ text 45 40 0 1 }"
+                    .to_string(),
+                lines: vec!["Yes".into(), "No".into()].into(),
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&display_gump))
+                .expect("Failed to write packet");
+
+            assert_eq!(
+                packet[..19],
+                [
+                    0xB0u8, 0x00, 0x94, 0x00, 0x00, 0x0E, 0xF9, 0x00, 0x00, 0x12, 0x34, 0x00, 0x00,
+                    0x00, 0x32, 0x00, 0x00, 0x00, 0x32,
+                ]
+            );
+            assert_eq!(
+                packet[packet.len() - 17..],
+                [
+                    0x00u8, 0x00, 0x02, 0x00, 0x03, 0x00, 0x59, 0x00, 0x65, 0x00, 0x73, 0x00, 0x02,
+                    0x00, 0x4E, 0x00, 0x6F,
+                ]
+            );
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed = DisplayGump::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, display_gump);
+        }
+    }
+
+    mod compressed_gump {
+        use super::*;
+        use flate2::read::ZlibDecoder;
+        use std::{convert::TryInto, io::Read};
+
+        #[test]
+        fn round_trip() {
+            let compressed_gump = CompressedGump {
+                serial: 3833,
+                gump: 0x1234,
+                x: 50,
+                y: 50,
+                layout: "{ page 0 }{ resizepic 0 0 5054 200 100 }{ text 20 20 0 0 }".to_string(),
+                lines: vec!["Hello".into()],
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&compressed_gump))
+                .expect("Failed to write packet");
+
+            assert_eq!(
+                usize::from(u16::from_be_bytes([packet[1], packet[2]])),
+                packet.len()
+            );
+
+            // Inflate the layout block the same way that the client does
+            let compressed_len = u32::from_be_bytes(packet[19..23].try_into().unwrap()) as usize;
+            let decompressed_len = u32::from_be_bytes(packet[23..27].try_into().unwrap()) as usize;
+            let mut layout = Vec::new();
+            ZlibDecoder::new(&packet[27..23 + compressed_len])
+                .read_to_end(&mut layout)
+                .expect("Failed to inflate layout");
+
+            assert_eq!(layout.len(), decompressed_len);
+            assert_eq!(
+                layout,
+                b"{ page 0 }{ resizepic 0 0 5054 200 100 }{ text 20 20 0 0 }\0"
+            );
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed =
+                CompressedGump::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, compressed_gump);
+        }
+
+        #[test]
+        fn serialize_without_lines() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&CompressedGump {
+                    serial: 3833,
+                    gump: 0x1234,
+                    x: 0,
+                    y: 0,
+                    layout: "{ page 0 }".to_string(),
+                    lines: vec![],
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet[packet.len() - 4..], [0x00u8, 0x00, 0x00, 0x00]);
+        }
+
+        #[test]
+        fn rejects_wrong_lengths() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&CompressedGump {
+                    serial: 3833,
+                    gump: 0x1234,
+                    x: 0,
+                    y: 0,
+                    layout: "{ page 0 }".to_string(),
+                    lines: vec![],
+                }),
+            )
+            .expect("Failed to write packet");
+
+            // Lengths far beyond the packet fail without allocating them
+            for (range, len) in [(19..23, u32::MAX), (23..27, u32::MAX), (23..27, 1)] {
+                let mut packet = packet.clone();
+                packet[range].copy_from_slice(&len.to_be_bytes());
+                assert!(CompressedGump::from_packet_data(&mut packet.as_slice()).is_err());
+            }
+        }
+    }
+
+    mod gump_response {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let gump_response = GumpResponse {
+                serial: 3833,
+                gump: 0x1234,
+                button: 2,
+                switches: vec![5].into(),
+                text_entries: vec![TextEntry {
+                    id: 1,
+                    text: "Hi".into(),
+                }]
+                .into(),
+            };
+
+            let mut input: &[u8] = &[
+                0xB1u8, 0x00, 0x23, 0x00, 0x00, 0x0E, 0xF9, 0x00, 0x00, 0x12, 0x34, 0x00, 0x00,
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x01,
+                0x00, 0x01, 0x00, 0x02, 0x00, 0x48, 0x00, 0x69,
+            ];
+
+            let parsed =
+                GumpResponse::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, gump_response);
+        }
+    }
+}
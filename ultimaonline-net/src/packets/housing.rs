@@ -1,6 +1,294 @@
+use crate::types::{Graphic, Serial};
 use macros::packet;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 
 #[packet(fixed(id = 0xFB, size = 1))]
 pub struct ShowPublicContent {
     show: bool,
 }
+
+// One tile of a custom house design, relative to the house's center
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HouseTile {
+    pub graphic: Graphic,
+    pub x: i8,
+    pub y: i8,
+    pub z: i8,
+}
+
+// The tiles are split into planes which are each compressed separately.
+// Every plane is sent as a list of tiles, since the client's more compact
+// plane modes depend on the floor each plane is for.
+mod house_planes {
+    use super::*;
+    use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+    use serde::ser::{self, SerializeTuple};
+    use std::{
+        convert::TryFrom,
+        io::{Read, Write},
+        mem::size_of,
+    };
+
+    const TILE_SIZE: usize = size_of::<Graphic>() + size_of::<i8>() * 3;
+
+    // Plane sizes are 12 bits
+    const MAX_PLANE_SIZE: usize = 0xFFF;
+    const MAX_PLANE_TILES: usize = MAX_PLANE_SIZE / TILE_SIZE;
+
+    const TILE_LIST_MODE: u8 = 0;
+
+    // The mode and plane index are in the top byte, the low bytes of the
+    // decompressed and compressed sizes follow, and the last byte has the
+    // high nibbles of both sizes
+    fn plane_header(index: u8, size: usize, compressed_size: usize) -> u32 {
+        (TILE_LIST_MODE as u32) << 28
+            | (index as u32 & 0x0F) << 24
+            | (size as u32 & 0xFF) << 16
+            | (compressed_size as u32 & 0xFF) << 8
+            | (size as u32 >> 4 & 0xF0)
+            | (compressed_size as u32 >> 8 & 0x0F)
+    }
+
+    pub fn serialize<S: Serializer>(tiles: &[HouseTile], serializer: S) -> Result<S::Ok, S::Error> {
+        let count = u16::try_from(tiles.len())
+            .map_err(|_| ser::Error::custom("custom house has too many tiles"))?;
+
+        let mut planes = vec![];
+        for (index, tiles) in tiles.chunks(MAX_PLANE_TILES).enumerate() {
+            let mut bytes = Vec::with_capacity(tiles.len() * TILE_SIZE);
+            crate::ser::to_writer(&mut bytes, &tiles).map_err(ser::Error::custom)?;
+
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&bytes).map_err(ser::Error::custom)?;
+            let compressed = encoder.finish().map_err(ser::Error::custom)?;
+            if compressed.len() > MAX_PLANE_SIZE {
+                return Err(ser::Error::custom("custom house plane is too large"));
+            }
+
+            planes.push((
+                plane_header(index as u8, bytes.len(), compressed.len()),
+                compressed,
+            ));
+        }
+
+        let planes_count = u8::try_from(planes.len())
+            .map_err(|_| ser::Error::custom("custom house has too many planes"))?;
+        let planes_size = planes
+            .iter()
+            .map(|(_, compressed)| size_of::<u32>() + compressed.len())
+            .sum::<usize>();
+        let planes_size = u16::try_from(planes_size)
+            .map_err(|_| ser::Error::custom("custom house planes are too large"))?;
+
+        let mut tuple = serializer.serialize_tuple(3 + planes.len() * 2)?;
+        tuple.serialize_element(&count)?;
+        tuple.serialize_element(&planes_size)?;
+        tuple.serialize_element(&planes_count)?;
+        for (header, compressed) in planes.iter() {
+            tuple.serialize_element(header)?;
+            tuple.serialize_element(compressed.as_slice())?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<HouseTile>, D::Error> {
+        struct PlanesVisitor;
+
+        impl<'de> Visitor<'de> for PlanesVisitor {
+            type Value = Vec<HouseTile>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a tile count followed by compressed planes")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let count = seq
+                    .next_element::<u16>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                seq.next_element::<u16>()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let planes_count = seq
+                    .next_element::<u8>()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+
+                let mut tiles = Vec::with_capacity(count as usize);
+                for _ in 0..planes_count {
+                    let header = seq
+                        .next_element::<u32>()?
+                        .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+                    if (header >> 28) as u8 != TILE_LIST_MODE {
+                        return Err(de::Error::custom(format!(
+                            "custom house plane mode {} is not supported",
+                            header >> 28
+                        )));
+                    }
+
+                    let size = (header >> 16 & 0xFF | (header & 0xF0) << 4) as usize;
+                    let compressed_size = (header >> 8 & 0xFF | (header & 0x0F) << 8) as usize;
+
+                    let mut compressed = Vec::with_capacity(compressed_size);
+                    for _ in 0..compressed_size {
+                        compressed.push(
+                            seq.next_element::<u8>()?
+                                .ok_or_else(|| de::Error::invalid_length(4, &self))?,
+                        );
+                    }
+
+                    let mut bytes = Vec::with_capacity(size);
+                    ZlibDecoder::new(compressed.as_slice())
+                        .read_to_end(&mut bytes)
+                        .map_err(de::Error::custom)?;
+                    if bytes.len() != size {
+                        return Err(de::Error::custom(
+                            "decompressed length did not match expected length",
+                        ));
+                    }
+
+                    let plane: Vec<HouseTile> =
+                        crate::de::from_slice(&bytes).map_err(de::Error::custom)?;
+                    tiles.extend(plane);
+                }
+
+                if tiles.len() != count as usize {
+                    return Err(de::Error::custom(
+                        "number of tiles did not match expected count",
+                    ));
+                }
+
+                Ok(tiles)
+            }
+        }
+
+        deserializer.deserialize_tuple(usize::MAX, PlanesVisitor)
+    }
+}
+
+// The design of a custom house, sent when the client's revision of it is
+// out of date
+#[packet(var(id = 0xD8))]
+pub struct CustomHouse {
+    pub compression: u8, // 0x03
+    pub unknown_01: u8,  // 0x00
+    pub serial: Serial,
+    pub revision: u32,
+    #[serde(with = "house_planes")]
+    pub tiles: Vec<HouseTile>,
+}
+
+// Asks for the design of a custom house, when the client doesn't have the
+// latest revision
+#[packet(extended(id = 0x1E))]
+pub struct DesignRequest {
+    pub serial: Serial,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::{FromPacketData, Packet};
+    use crate::ser::to_writer;
+
+    mod custom_house {
+        use super::*;
+        use flate2::read::ZlibDecoder;
+        use std::io::Read;
+
+        fn tiles(count: usize) -> Vec<HouseTile> {
+            (0..count)
+                .map(|num| HouseTile {
+                    graphic: 0x0031 + (num % 16) as u16, // Floors
+                    x: (num % 7) as i8 - 3,
+                    y: (num / 7 % 7) as i8 - 3,
+                    z: (num / 49 * 20) as i8,
+                })
+                .collect()
+        }
+
+        #[test]
+        fn serialize() {
+            let custom_house = CustomHouse {
+                compression: 0x03,
+                unknown_01: 0x00,
+                serial: 0x40001234,
+                revision: 7,
+                tiles: tiles(2),
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&custom_house))
+                .expect("Failed to write packet");
+
+            assert_eq!(
+                usize::from(u16::from_be_bytes([packet[1], packet[2]])),
+                packet.len()
+            );
+            assert_eq!(
+                packet[3..18],
+                [
+                    0x03u8, 0x00, 0x40, 0x00, 0x12, 0x34, 0x00, 0x00, 0x00, 0x07, 0x00, 0x02,
+                    packet[15], packet[16], 0x01,
+                ]
+            );
+            let planes_size = usize::from(u16::from_be_bytes([packet[15], packet[16]]));
+            assert_eq!(planes_size, packet.len() - 18);
+
+            // Inflate the plane the same way that the client does
+            let header = u32::from_be_bytes([packet[18], packet[19], packet[20], packet[21]]);
+            assert_eq!(header >> 24, 0x00); // Tile list mode, first plane
+            let size = (header >> 16 & 0xFF | (header & 0xF0) << 4) as usize;
+            let compressed_size = (header >> 8 & 0xFF | (header & 0x0F) << 8) as usize;
+            assert_eq!(compressed_size, planes_size - 4);
+
+            let mut plane = Vec::new();
+            ZlibDecoder::new(&packet[22..])
+                .read_to_end(&mut plane)
+                .expect("Failed to inflate plane");
+
+            assert_eq!(plane.len(), size);
+            assert_eq!(
+                plane,
+                [0x00u8, 0x31, 0xFD, 0xFD, 0x00, 0x00, 0x32, 0xFE, 0xFD, 0x00]
+            );
+        }
+
+        #[test]
+        fn round_trip() {
+            // Enough tiles for more than one plane
+            let custom_house = CustomHouse {
+                compression: 0x03,
+                unknown_01: 0x00,
+                serial: 0x40001234,
+                revision: 12,
+                tiles: tiles(1000),
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&custom_house))
+                .expect("Failed to write packet");
+            assert_eq!(packet[17], 2);
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed = CustomHouse::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, custom_house);
+        }
+    }
+
+    mod design_request {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &[0xBFu8, 0x00, 0x09, 0x00, 0x1E, 0x40, 0x00, 0x12, 0x34];
+
+            let parsed =
+                DesignRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, DesignRequest { serial: 0x40001234 });
+        }
+    }
+}
@@ -0,0 +1,667 @@
+use crate::types::{Direction, Graphic, Hue, List, ListTerm, Serial, WireSize};
+use macros::packet;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+#[packet(fixed(id = 0x24, size = 6))]
+pub struct OpenContainer {
+    pub serial: Serial,
+    pub gump: u16,
+}
+
+// Every item in a container includes its amount, even if it isn't a stack
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContainerItem {
+    pub serial: Serial,
+    pub graphic: Graphic,
+    pub unknown_06: u8, // 0x00
+    pub amount: u16,
+    pub x: u16,
+    pub y: u16,
+    pub grid_index: u8,
+    pub container: Serial,
+    pub hue: Hue,
+}
+
+#[packet(var(id = 0x3C))]
+pub struct ContainerContents {
+    pub items: List<ContainerItem, u16>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum WorldItemKind {
+    Item = 0x00,
+    Mobile = 0x01,
+    Multi = 0x02,
+}
+
+// Unlike the legacy world item packet (0x1A), which sets the high bits of
+// the serial and position to flag the presence of the amount and direction,
+// this layout always includes both. The graphic is sent as-is without any
+// flag bits.
+#[packet(fixed(id = 0xF3, size = 25))]
+pub struct WorldItem {
+    pub unknown_00: u16, // 0x0001
+    pub kind: WorldItemKind,
+    pub serial: Serial,
+    pub graphic: Graphic,
+    pub graphic_inc: u8,
+    pub amount: u16,
+    pub amount_max: u16, // Same as amount
+    pub x: u16,
+    pub y: u16,
+    pub z: i8,
+    pub direction: Direction,
+    pub hue: Hue,
+    pub flags: u8,
+    pub unknown_17: u16, // 0x0000, High Seas and later
+}
+
+// The legacy world item layout, which only includes the amount, graphic
+// increment, direction, hue and flags when they aren't zero. Whether each
+// one is there is flagged by the high bits of the serial, graphic and
+// position. Multis are flagged in the graphic, and mobiles can't be sent.
+mod legacy_world_item {
+    use super::*;
+    use serde::de::{self, Deserializer, SeqAccess, Visitor};
+    use serde::ser::{self, SerializeTuple, Serializer};
+    use std::{convert::TryFrom, fmt};
+
+    const AMOUNT_FLAG: u32 = 0x80000000;
+    const GRAPHIC_INC_FLAG: u16 = 0x8000;
+    const MULTI_FLAG: u16 = 0x4000;
+    const DIRECTION_FLAG: u16 = 0x8000;
+    const HUE_FLAG: u16 = 0x8000;
+    const ITEM_FLAGS_FLAG: u16 = 0x4000;
+
+    pub fn serialize<S: Serializer>(item: &WorldItem, serializer: S) -> Result<S::Ok, S::Error> {
+        let graphic = match item.kind {
+            WorldItemKind::Item => item.graphic,
+            WorldItemKind::Multi => item.graphic | MULTI_FLAG,
+            WorldItemKind::Mobile => {
+                return Err(ser::Error::custom("legacy world items can't be mobiles"))
+            }
+        };
+
+        let amount = Some(item.amount).filter(|&amount| amount != 0);
+        let graphic_inc = Some(item.graphic_inc).filter(|&inc| inc != 0);
+        let direction = Some(item.direction).filter(|&dir| dir != Direction::North);
+        let hue = Some(item.hue).filter(|&hue| hue != 0);
+        let flags = Some(item.flags).filter(|&flags| flags != 0);
+
+        let optional = [
+            amount.is_some(),
+            graphic_inc.is_some(),
+            direction.is_some(),
+            hue.is_some(),
+            flags.is_some(),
+        ];
+        let mut tuple =
+            serializer.serialize_tuple(5 + optional.iter().filter(|&&present| present).count())?;
+
+        tuple.serialize_element(&(item.serial | amount.map_or(0, |_| AMOUNT_FLAG)))?;
+        tuple.serialize_element(&(graphic | graphic_inc.map_or(0, |_| GRAPHIC_INC_FLAG)))?;
+        if let Some(amount) = amount {
+            tuple.serialize_element(&amount)?;
+        }
+        if let Some(graphic_inc) = graphic_inc {
+            tuple.serialize_element(&graphic_inc)?;
+        }
+        tuple.serialize_element(&(item.x | direction.map_or(0, |_| DIRECTION_FLAG)))?;
+        tuple.serialize_element(
+            &(item.y | hue.map_or(0, |_| HUE_FLAG) | flags.map_or(0, |_| ITEM_FLAGS_FLAG)),
+        )?;
+        if let Some(direction) = direction {
+            tuple.serialize_element(&direction)?;
+        }
+        tuple.serialize_element(&item.z)?;
+        if let Some(hue) = hue {
+            tuple.serialize_element(&hue)?;
+        }
+        if let Some(flags) = flags {
+            tuple.serialize_element(&flags)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<WorldItem, D::Error> {
+        struct LegacyVisitor;
+
+        impl LegacyVisitor {
+            fn next<'de, T: serde::Deserialize<'de>, A: SeqAccess<'de>>(
+                &self,
+                seq: &mut A,
+                index: usize,
+            ) -> Result<T, A::Error> {
+                seq.next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(index, self))
+            }
+        }
+
+        impl<'de> Visitor<'de> for LegacyVisitor {
+            type Value = WorldItem;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a world item with flagged optional fields")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let serial: Serial = self.next(&mut seq, 0)?;
+                let graphic: Graphic = self.next(&mut seq, 1)?;
+                let amount = match serial & AMOUNT_FLAG {
+                    0 => 0,
+                    _ => self.next(&mut seq, 2)?,
+                };
+                let graphic_inc = match graphic & GRAPHIC_INC_FLAG {
+                    0 => 0,
+                    _ => self.next(&mut seq, 3)?,
+                };
+                let x: u16 = self.next(&mut seq, 4)?;
+                let y: u16 = self.next(&mut seq, 5)?;
+                let direction = match x & DIRECTION_FLAG {
+                    0 => Direction::North,
+                    _ => Direction::try_from(self.next::<u8, _>(&mut seq, 6)?)
+                        .map_err(de::Error::custom)?,
+                };
+                let z = self.next(&mut seq, 7)?;
+                let hue = match y & HUE_FLAG {
+                    0 => 0,
+                    _ => self.next(&mut seq, 8)?,
+                };
+                let flags = match y & ITEM_FLAGS_FLAG {
+                    0 => 0,
+                    _ => self.next(&mut seq, 9)?,
+                };
+
+                let graphic = graphic & !GRAPHIC_INC_FLAG;
+                Ok(WorldItem {
+                    unknown_00: 0x0001,
+                    kind: match graphic & MULTI_FLAG {
+                        0 => WorldItemKind::Item,
+                        _ => WorldItemKind::Multi,
+                    },
+                    serial: serial & !AMOUNT_FLAG,
+                    graphic: graphic & !MULTI_FLAG,
+                    graphic_inc,
+                    amount,
+                    amount_max: amount,
+                    x: x & !DIRECTION_FLAG,
+                    y: y & !(HUE_FLAG | ITEM_FLAGS_FLAG),
+                    z,
+                    direction,
+                    hue,
+                    flags,
+                    unknown_17: 0,
+                })
+            }
+        }
+
+        // Only the fields which are flagged are read
+        deserializer.deserialize_tuple(usize::MAX, LegacyVisitor)
+    }
+}
+
+// Shows an item to clients which don't understand WorldItem
+#[packet(var(id = 0x1A))]
+pub struct WorldItemLegacy {
+    #[serde(with = "legacy_world_item")]
+    pub item: WorldItem,
+}
+
+impl From<WorldItem> for WorldItemLegacy {
+    fn from(item: WorldItem) -> Self {
+        Self { item }
+    }
+}
+
+#[packet(fixed(id = 0x07, size = 6))]
+pub struct LiftRequest {
+    pub serial: Serial,
+    pub amount: u16,
+}
+
+#[packet(fixed(id = 0x08, size = 14))]
+pub struct DropRequest {
+    pub serial: Serial,
+    pub x: u16,
+    pub y: u16,
+    pub z: i8,
+    pub grid_index: u8,
+    pub container: Serial, // 0xFFFFFFFF when dropped on the ground
+}
+
+#[packet(fixed(id = 0x13, size = 9))]
+pub struct EquipRequest {
+    pub serial: Serial,
+    pub layer: u8,
+    pub mobile: Serial,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum DropRejectReason {
+    CannotLift = 0,
+    OutOfRange = 1,
+    OutOfSight = 2,
+    BelongsToAnother = 3,
+    AlreadyHolding = 4,
+    Unspecified = 5,
+}
+
+#[packet(fixed(id = 0x27, size = 1))]
+pub struct DropReject {
+    pub reason: DropRejectReason,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CorpseItem {
+    pub layer: u8,
+    pub serial: Serial,
+}
+
+#[packet(var(id = 0x89))]
+pub struct Corpse {
+    pub serial: Serial,
+    pub items: ListTerm<CorpseItem, u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::{FromPacketData, Packet};
+    use crate::ser::to_writer;
+    mod container_contents {
+        use super::*;
+
+        fn container_contents() -> ContainerContents {
+            ContainerContents {
+                items: vec![
+                    ContainerItem {
+                        serial: 0x40000010,
+                        graphic: 0x0EED, // Gold coin
+                        unknown_06: 0,
+                        amount: 500,
+                        x: 0x0045,
+                        y: 0x0067,
+                        grid_index: 0,
+                        container: 0x40000001,
+                        hue: 0,
+                    },
+                    ContainerItem {
+                        serial: 0x40000011,
+                        graphic: 0x0F0E, // Empty bottle
+                        unknown_06: 0,
+                        amount: 1,
+                        x: 0x0089,
+                        y: 0x00AB,
+                        grid_index: 1,
+                        container: 0x40000001,
+                        hue: 0x0481,
+                    },
+                ]
+                .into(),
+            }
+        }
+
+        const BYTES: [u8; 45] = [
+            0x3Cu8, 0x00, 0x2D, 0x00, 0x02, 0x40, 0x00, 0x00, 0x10, 0x0E, 0xED, 0x00, 0x01, 0xF4,
+            0x00, 0x45, 0x00, 0x67, 0x00, 0x40, 0x00, 0x00, 0x01, 0x00, 0x00, 0x40, 0x00, 0x00,
+            0x11, 0x0F, 0x0E, 0x00, 0x00, 0x01, 0x00, 0x89, 0x00, 0xAB, 0x01, 0x40, 0x00, 0x00,
+            0x01, 0x04, 0x81,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&container_contents()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &BYTES;
+
+            let parsed =
+                ContainerContents::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, container_contents());
+        }
+    }
+
+    mod open_container {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0x24u8, 0x40, 0x00, 0x00, 0x01, 0x00, 0x3C];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&OpenContainer {
+                    serial: 0x40000001,
+                    gump: 0x003C, // Backpack
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+
+        #[test]
+        fn deserialize() {
+            let open_container = OpenContainer {
+                serial: 0x40000002,
+                gump: 0x0042,
+            };
+
+            let mut input: &[u8] = &[0x24u8, 0x40, 0x00, 0x00, 0x02, 0x00, 0x42];
+
+            let parsed =
+                OpenContainer::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, open_container);
+        }
+    }
+
+    mod world_item {
+        use super::*;
+
+        pub fn gold_pile() -> WorldItem {
+            WorldItem {
+                unknown_00: 0x0001,
+                kind: WorldItemKind::Item,
+                serial: 0x40000123,
+                graphic: 0x0EED, // Gold coin
+                graphic_inc: 0,
+                amount: 100,
+                amount_max: 100,
+                x: 3668,
+                y: 2625,
+                z: 0,
+                direction: Direction::North,
+                hue: 0,
+                flags: 0x20, // Movable
+                unknown_17: 0,
+            }
+        }
+
+        const BYTES: [u8; 26] = [
+            0xF3u8, 0x00, 0x01, 0x00, 0x40, 0x00, 0x01, 0x23, 0x0E, 0xED, 0x00, 0x00, 0x64, 0x00,
+            0x64, 0x0E, 0x54, 0x0A, 0x41, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&gold_pile()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &BYTES;
+
+            let parsed = WorldItem::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, gold_pile());
+        }
+
+        #[test]
+        fn always_includes_amount() {
+            for amount in [1, 50] {
+                let item = WorldItem {
+                    amount,
+                    amount_max: amount,
+                    ..gold_pile()
+                };
+
+                let mut packet = Vec::<u8>::new();
+                to_writer(&mut packet, &Packet::<_>::from(&item)).expect("Failed to write packet");
+
+                assert_eq!(packet.len(), BYTES.len());
+                assert_eq!(
+                    packet[8..15],
+                    [0x0E, 0xED, 0x00, 0x00, amount as u8, 0x00, amount as u8]
+                );
+            }
+        }
+    }
+
+    mod world_item_legacy {
+        use super::world_item::gold_pile;
+        use super::*;
+
+        const BYTES: [u8; 17] = [
+            0x1Au8, 0x00, 0x11, 0xC0, 0x00, 0x01, 0x23, 0x0E, 0xED, 0x00, 0x64, 0x0E, 0x54, 0x4A,
+            0x41, 0x00, 0x20,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&WorldItemLegacy::from(gold_pile())),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &BYTES;
+
+            let parsed =
+                WorldItemLegacy::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed.item, gold_pile());
+        }
+
+        #[test]
+        fn round_trip_all_fields() {
+            let multi = WorldItem {
+                kind: WorldItemKind::Multi,
+                graphic: 0x0064,
+                graphic_inc: 2,
+                direction: Direction::West,
+                hue: 1150,
+                ..gold_pile()
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&WorldItemLegacy::from(multi.clone())),
+            )
+            .expect("Failed to write packet");
+            assert_eq!(packet.len(), 21);
+
+            let parsed = WorldItemLegacy::from_packet_data(&mut packet.as_slice())
+                .expect("Failed to parse packet");
+
+            assert_eq!(parsed.item, multi);
+        }
+
+        #[test]
+        fn omits_missing_amount() {
+            let write = |amount| {
+                let item = WorldItem {
+                    amount,
+                    amount_max: amount,
+                    ..gold_pile()
+                };
+
+                let mut packet = Vec::<u8>::new();
+                to_writer(
+                    &mut packet,
+                    &Packet::<_>::from(&WorldItemLegacy::from(item)),
+                )
+                .expect("Failed to write packet");
+                packet
+            };
+
+            // Only the serial's high bit says whether the amount follows the graphic
+            let single = write(0);
+            assert_eq!(single.len(), 15);
+            assert_eq!(single[3..9], [0x40, 0x00, 0x01, 0x23, 0x0E, 0xED]);
+
+            let stack = write(50);
+            assert_eq!(stack.len(), 17);
+            assert_eq!(
+                stack[3..11],
+                [0xC0, 0x00, 0x01, 0x23, 0x0E, 0xED, 0x00, 0x32]
+            );
+        }
+
+        #[test]
+        fn rejects_mobiles() {
+            let mobile = WorldItem {
+                kind: WorldItemKind::Mobile,
+                ..gold_pile()
+            };
+
+            assert!(to_writer(
+                &mut vec![],
+                &Packet::<_>::from(&WorldItemLegacy::from(mobile))
+            )
+            .is_err());
+        }
+    }
+
+    mod lift_request {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let lift_request = LiftRequest {
+                serial: 0x40000010,
+                amount: 250,
+            };
+
+            let mut input: &[u8] = &[0x07u8, 0x40, 0x00, 0x00, 0x10, 0x00, 0xFA];
+
+            let parsed = LiftRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, lift_request);
+        }
+    }
+
+    mod drop_request {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let drop_request = DropRequest {
+                serial: 0x40000010,
+                x: 3668,
+                y: 2625,
+                z: -5,
+                grid_index: 0,
+                container: 0xFFFFFFFF,
+            };
+
+            let mut input: &[u8] = &[
+                0x08u8, 0x40, 0x00, 0x00, 0x10, 0x0E, 0x54, 0x0A, 0x41, 0xFB, 0x00, 0xFF, 0xFF,
+                0xFF, 0xFF,
+            ];
+
+            let parsed = DropRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, drop_request);
+        }
+    }
+
+    mod equip_request {
+        use super::*;
+
+        #[test]
+        fn deserialize() {
+            let equip_request = EquipRequest {
+                serial: 0x40000001,
+                layer: 0x05, // Shirt
+                mobile: 3833,
+            };
+
+            let mut input: &[u8] = &[0x13u8, 0x40, 0x00, 0x00, 0x01, 0x05, 0x00, 0x00, 0x0E, 0xF9];
+
+            let parsed =
+                EquipRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, equip_request);
+        }
+    }
+
+    mod drop_reject {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0x27u8, 0x01];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&DropReject {
+                    reason: DropRejectReason::OutOfRange,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+
+        #[test]
+        fn deserialize() {
+            let drop_reject = DropReject {
+                reason: DropRejectReason::AlreadyHolding,
+            };
+
+            let mut input: &[u8] = &[0x27u8, 0x04];
+
+            let parsed = DropReject::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, drop_reject);
+        }
+    }
+
+    mod corpse {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0x89u8, 0x00, 0x12, 0x40, 0x00, 0x03, 0x00, 0x05, 0x40, 0x00, 0x00, 0x01, 0x0B,
+                0x40, 0x00, 0x00, 0x05, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&Corpse {
+                    serial: 0x40000300,
+                    items: vec![
+                        CorpseItem {
+                            layer: 0x05, // Shirt
+                            serial: 0x40000001,
+                        },
+                        CorpseItem {
+                            layer: 0x0B, // Hair
+                            serial: 0x40000005,
+                        },
+                    ]
+                    .into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+}
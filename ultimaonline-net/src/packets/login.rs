@@ -1,10 +1,13 @@
-use crate::types::{FixedStr, List};
+use crate::error::Error;
+use crate::types::{FixedStr, Flags8, List, WireSize};
 use macros::packet;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use std::net::{Ipv4Addr, SocketAddrV4};
+use std::net::{IpAddr, Ipv4Addr, SocketAddrV4};
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+// Fields are in order of significance, so versions compare as expected
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, WireSize)]
 pub struct ClientVersion {
     major: u32,
     minor: u32,
@@ -22,6 +25,63 @@ impl std::fmt::Display for ClientVersion {
     }
 }
 
+impl ClientVersion {
+    pub const fn new(major: u32, minor: u32, revision: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            revision,
+            patch,
+        }
+    }
+}
+
+// Parses "major.minor.revision.patch", as well as the letter-suffixed forms
+// that some clients send. Older clients send "major.minor.revision" with the
+// patch as a letter (e.g. "4.0.11c" is 4.0.11.3). A letter after the patch
+// (e.g. "7.0.1.1a") has no equivalent in the numeric version, so it's dropped.
+impl FromStr for ClientVersion {
+    type Err = Error;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            Error::data(format!(
+                "client version {:?} is invalid: {}",
+                version, reason
+            ))
+        };
+        let number = |part: &str| {
+            part.parse::<u32>()
+                .map_err(|_| invalid(&format!("{:?} is not a number", part)))
+        };
+
+        let parts: Vec<&str> = version.trim().split('.').collect();
+        if parts.len() != 3 && parts.len() != 4 {
+            return Err(invalid("expected 3 or 4 parts separated by '.'"));
+        }
+
+        // Only the last part may have a letter suffix
+        let last = parts[parts.len() - 1];
+        let digits = last.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let suffix = match &last[digits.len()..] {
+            "" => None,
+            letter if letter.len() == 1 => letter.chars().next(),
+            letters => return Err(invalid(&format!("suffix {:?} is too long", letters))),
+        };
+
+        let major = number(parts[0])?;
+        let minor = number(parts[1])?;
+        let revision = number(if parts.len() == 3 { digits } else { parts[2] })?;
+        let patch = match (parts.len(), suffix) {
+            (3, Some(letter)) => letter.to_ascii_lowercase() as u32 - 'a' as u32 + 1,
+            (3, None) => 0,
+            _ => number(digits)?,
+        };
+
+        Ok(Self::new(major, minor, revision, patch))
+    }
+}
+
 #[packet(fixed(id = 0xEF, size = 20))]
 pub struct ClientHello {
     pub seed: u32,
@@ -36,7 +96,7 @@ pub struct AccountLogin {
 }
 
 #[allow(dead_code)]
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
 #[repr(u8)]
 pub enum LoginRejectionReason {
     Invalid = 0,
@@ -58,15 +118,35 @@ pub struct ServerInfo {
     pub name: FixedStr<32>,
     pub fullness: u8,
     pub timezone: u8,
+    #[serde(with = "crate::types::ipv4_reversed")]
     pub ip_address: Ipv4Addr,
 }
 
 #[packet(var(id = 0xA8))]
 pub struct ServerList {
-    pub flags: u8,
+    pub flags: Flags8,
     pub list: List<ServerInfo, u16>,
 }
 
+// The legacy server list only has room for IPv4 addresses, so servers which
+// are reachable over IPv6 are listed in a separate packet instead. Official
+// clients don't know it, so it's only for clients that opt in to it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfoV6 {
+    pub index: u16,
+    pub name: FixedStr<32>,
+    pub fullness: u8,
+    pub timezone: u8,
+    #[serde(with = "crate::types::ip_addr")]
+    pub ip_address: IpAddr,
+}
+
+#[packet(var(id = 0xFE))]
+pub struct ServerListV6 {
+    pub flags: Flags8,
+    pub list: List<ServerInfoV6, u16>,
+}
+
 #[packet(fixed(id = 0xA0, size = 2))]
 pub struct ServerSelection {
     pub index: u16,
@@ -74,6 +154,7 @@ pub struct ServerSelection {
 
 #[packet(fixed(id = 0x8C, size = 10))]
 pub struct GameServerHandoff {
+    #[serde(with = "crate::types::socket_addr_v4")]
     pub socket: SocketAddrV4,
     pub ticket: u32,
 }
@@ -83,6 +164,48 @@ mod tests {
     use super::*;
     use crate::packets::{FromPacketData, Packet};
     use crate::ser::to_writer;
+    mod client_version {
+        use super::*;
+
+        #[test]
+        fn parse() {
+            let version: ClientVersion = "7.0.15.1".parse().expect("Failed to parse version");
+            assert_eq!(version, ClientVersion::new(7, 0, 15, 1));
+            assert_eq!(version.to_string(), "7.0.15.1");
+
+            assert!(version > ClientVersion::new(7, 0, 9, 30));
+            assert!(version < ClientVersion::new(7, 0, 15, 2));
+        }
+
+        #[test]
+        fn parse_suffixed() {
+            let version: ClientVersion = "4.0.11c".parse().expect("Failed to parse version");
+            assert_eq!(version, ClientVersion::new(4, 0, 11, 3));
+
+            let version: ClientVersion = "7.0.1.1a".parse().expect("Failed to parse version");
+            assert_eq!(version, ClientVersion::new(7, 0, 1, 1));
+        }
+
+        #[test]
+        fn parse_invalid() {
+            for version in [
+                "",
+                "7.0",
+                "7.0.1.1.1",
+                "7.x.1.1",
+                "7.0a.1.1",
+                "7.0.1.1ab",
+                "7.0.1.-1",
+            ] {
+                assert!(
+                    matches!(version.parse::<ClientVersion>(), Err(Error::Data(_))),
+                    "{:?} should not parse",
+                    version
+                );
+            }
+        }
+    }
+
     mod login_rejection {
         use super::*;
 
@@ -102,6 +225,16 @@ mod tests {
             assert_eq!(packet.as_slice(), rej_invalid);
         }
 
+        #[test]
+        fn encode() {
+            let packet = crate::packets::encode(&LoginRejection {
+                reason: LoginRejectionReason::Invalid,
+            })
+            .expect("Failed to encode packet");
+
+            assert_eq!(packet, [0x82u8, 0]);
+        }
+
         #[test]
         fn deserialize() {
             let rej_blocked = LoginRejection {
@@ -120,6 +253,8 @@ mod tests {
     mod server_list {
         use super::*;
 
+        // The test packet has 7F 00 03 01 and 7F 00 03 02 for the addresses,
+        // which are read in reverse
         fn servers() -> Vec<ServerInfo> {
             vec![
                 ServerInfo {
@@ -127,18 +262,48 @@ mod tests {
                     name: "Server 1".into(),
                     fullness: 10,
                     timezone: 3,
-                    ip_address: "127.0.3.1".parse().unwrap(),
+                    ip_address: "1.3.0.127".parse().unwrap(),
                 },
                 ServerInfo {
                     index: 1,
                     name: "Another Server".into(),
                     fullness: 39,
                     timezone: 9,
-                    ip_address: "127.0.3.2".parse().unwrap(),
+                    ip_address: "2.3.0.127".parse().unwrap(),
                 },
             ]
         }
 
+        #[test]
+        fn reverses_address() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&ServerList {
+                    flags: Flags8(0x5D),
+                    list: vec![ServerInfo {
+                        index: 0,
+                        name: "Server 1".into(),
+                        fullness: 10,
+                        timezone: 3,
+                        ip_address: "127.0.3.1".parse().unwrap(),
+                    }]
+                    .into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet[..6], [0xA8, 0x00, 0x2E, 0x5D, 0x00, 0x01]);
+            assert_eq!(packet[40..], [0x0A, 0x03, 0x01, 0x03, 0x00, 0x7F]);
+
+            let parsed = ServerList::from_packet_data(&mut packet.as_slice())
+                .expect("Failed to parse packet");
+            assert_eq!(
+                Vec::from(parsed.list)[0].ip_address,
+                Ipv4Addr::new(127, 0, 3, 1)
+            );
+        }
+
         #[test]
         fn serialize() {
             let server_list = include_bytes!("../../test/resources/ServerList.pkt");
@@ -147,7 +312,7 @@ mod tests {
             to_writer(
                 &mut packet,
                 &Packet::<_>::from(&ServerList {
-                    flags: 0x5D,
+                    flags: Flags8(0x5D),
                     list: servers().into(),
                 }),
             )
@@ -159,7 +324,7 @@ mod tests {
         #[test]
         fn deserialize() {
             let server_list = ServerList {
-                flags: 0x5D,
+                flags: Flags8(0x5D),
                 list: servers().into(),
             };
 
@@ -171,9 +336,96 @@ mod tests {
         }
     }
 
+    mod server_list_v6 {
+        use super::*;
+
+        fn servers() -> Vec<ServerInfoV6> {
+            vec![
+                ServerInfoV6 {
+                    index: 0,
+                    name: "Server 1".into(),
+                    fullness: 10,
+                    timezone: 3,
+                    ip_address: "127.0.3.1".parse().unwrap(),
+                },
+                ServerInfoV6 {
+                    index: 1,
+                    name: "Another Server".into(),
+                    fullness: 39,
+                    timezone: 9,
+                    ip_address: "2001:db8::3:2".parse().unwrap(),
+                },
+            ]
+        }
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&ServerListV6 {
+                    flags: Flags8(0x5D),
+                    list: servers()[..1].to_vec().into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet[..6], [0xFE, 0x00, 0x2F, 0x5D, 0x00, 0x01]);
+            assert_eq!(packet[40..], [0x0A, 0x03, 0x04, 0x7F, 0x00, 0x03, 0x01]);
+        }
+
+        #[test]
+        fn round_trip() {
+            let server_list = ServerListV6 {
+                flags: Flags8(0x5D),
+                list: servers().into(),
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&server_list))
+                .expect("Failed to write packet");
+
+            // One address of each family, with the IPv6 one in order
+            assert_eq!(packet.len(), 6 + (36 + 5) + (36 + 17));
+            assert_eq!(packet[packet.len() - 17..][..3], [0x06, 0x20, 0x01]);
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed =
+                ServerListV6::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, server_list);
+        }
+
+        #[test]
+        fn rejects_unknown_family() {
+            let mut bytes = vec![0u8; 36];
+            bytes.extend_from_slice(&[0x05, 0x7F, 0x00, 0x03, 0x01]);
+
+            assert!(crate::de::from_slice::<ServerInfoV6>(&bytes).is_err());
+        }
+    }
+
     mod game_server_handoff {
         use super::*;
 
+        #[test]
+        fn serialize() {
+            let handoff = GameServerHandoff {
+                socket: "127.0.4.3:8734".parse().unwrap(),
+                ticket: 0x35701845,
+            };
+
+            let mut packet = Vec::<u8>::new();
+            Packet::<_>::from(&handoff)
+                .to_writer(&mut packet)
+                .expect("Failed to write packet");
+
+            assert_eq!(
+                packet,
+                [0x8C, 0x7F, 0x00, 0x04, 0x03, 0x22, 0x1E, 0x35, 0x70, 0x18, 0x45]
+            );
+        }
+
         #[test]
         fn round_trip() {
             let handoff = GameServerHandoff {
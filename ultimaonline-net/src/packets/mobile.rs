@@ -1,9 +1,15 @@
-use crate::types::{list::ListTerm, Direction, Graphic, Hue, Notoriety, Serial};
+use crate::packets::gump::GumpText;
+use crate::types::{
+    list::{ListNonTerm, ListTerm},
+    Direction, FixedStr, Graphic, Hue, List, Notoriety, Serial, UnicodeStr, WireSize,
+};
 use macros::packet;
-use serde::{Deserialize, Serialize};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use std::fmt;
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
 #[repr(u8)]
 pub enum EntityFlags {
     None = 0x00,
@@ -31,6 +37,22 @@ pub struct Item {
     pub hue: Hue,
 }
 
+// Moves the player's own character, unlike State which is for the other
+// mobiles that the player can see
+#[packet(fixed(id = 0x20, size = 18))]
+pub struct PlayerUpdate {
+    pub serial: Serial,
+    pub body: Graphic,
+    pub unknown_06: u8, // 0x00
+    pub hue: Hue,
+    pub flags: EntityFlags,
+    pub x: u16,
+    pub y: u16,
+    pub unknown_0e: u16, // 0x0000
+    pub direction: Direction,
+    pub z: i8,
+}
+
 #[packet(fixed(id = 0x77, size = 16))]
 pub struct State {
     pub serial: Serial,
@@ -50,7 +72,41 @@ pub struct Appearance {
     pub items: ListTerm<Item, u32>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+// A single item being worn, for when the rest of the mobile's appearance is
+// unchanged
+#[packet(fixed(id = 0x2E, size = 14))]
+pub struct WornItem {
+    pub serial: Serial,
+    pub type_id: u16,
+    pub unknown_06: u8, // 0x00
+    pub layer: u8,
+    pub mobile: Serial,
+    pub hue: Hue,
+}
+
+impl WornItem {
+    pub fn new(mobile: Serial, item: &Item) -> Self {
+        Self {
+            serial: item.serial,
+            type_id: item.type_id,
+            unknown_06: 0x00,
+            layer: item.layer,
+            mobile,
+            hue: item.hue,
+        }
+    }
+
+    pub fn item(&self) -> Item {
+        Item {
+            serial: self.serial,
+            type_id: self.type_id,
+            layer: self.layer,
+            hue: self.hue,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
 #[repr(u8)]
 pub enum QueryKind {
     Status = 0x4,
@@ -64,15 +120,534 @@ pub struct Query {
     pub serial: Serial,
 }
 
+#[packet(fixed(id = 0x72, size = 4))]
+pub struct WarMode {
+    pub war: bool,
+    pub unknown_01: [u8; 3], // 0x00, 0x32, 0x00
+}
+
+// The client opens a paperdoll by double-clicking the mobile (0x06),
+// with the high bit of the serial set when it is the player's own.
+#[packet(fixed(id = 0x88, size = 65))]
+pub struct Paperdoll {
+    pub serial: Serial,
+    pub text: FixedStr<60>, // Name and title
+    pub flags: u8,          // 0x01 war mode, 0x02 can lift items
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[repr(u16)]
+pub enum StatusBarColor {
+    Green = 0x0001,  // Poisoned
+    Yellow = 0x0002, // Invulnerable
+}
+
+#[packet(var(id = 0x17))]
+pub struct StatusBarFlag {
+    pub serial: Serial,
+    pub unknown_04: u16, // 0x0001
+    pub color: StatusBarColor,
+    pub enabled: bool,
+}
+
+#[packet(fixed(id = 0xA1, size = 8))]
+pub struct UpdateHealth {
+    pub serial: Serial,
+    pub max: u16,
+    pub current: u16,
+}
+
+#[packet(fixed(id = 0xA2, size = 8))]
+pub struct UpdateMana {
+    pub serial: Serial,
+    pub max: u16,
+    pub current: u16,
+}
+
+#[packet(fixed(id = 0xA3, size = 8))]
+pub struct UpdateStamina {
+    pub serial: Serial,
+    pub max: u16,
+    pub current: u16,
+}
+
+// Cliloc arguments for a buff, separated by tabs. Unlike most text these are
+// sent as null-terminated UTF-16LE, following a flag for whether any are present.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuffArgs(pub String);
+
+impl Serialize for BuffArgs {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::SerializeTuple;
+
+        let mut tuple = serializer.serialize_tuple(5)?;
+        if self.0.is_empty() {
+            tuple.serialize_element(&0u16)?;
+        } else {
+            // Swapping the bytes of each code unit makes them come out little-endian
+            let units: Vec<u16> = format!("\t{}", self.0)
+                .encode_utf16()
+                .map(u16::swap_bytes)
+                .collect();
+
+            tuple.serialize_element(&1u16)?;
+            tuple.serialize_element(&0u16)?;
+            tuple.serialize_element(&ListTerm::<u16, u16>::from(units))?;
+            tuple.serialize_element(&1u16)?;
+            tuple.serialize_element(&0u16)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for BuffArgs {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BuffArgsVisitor;
+
+        impl<'de> Visitor<'de> for BuffArgsVisitor {
+            type Value = BuffArgs;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a flag followed by optional buff arguments")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let next_u16 = |seq: &mut A, index| {
+                    seq.next_element::<u16>()?
+                        .ok_or_else(|| de::Error::invalid_length(index, &self))
+                };
+
+                if next_u16(&mut seq, 0)? == 0 {
+                    return Ok(BuffArgs::default());
+                }
+                next_u16(&mut seq, 1)?;
+
+                let units: Vec<u16> = seq
+                    .next_element::<ListTerm<u16, u16>>()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?
+                    .into();
+                let units: Vec<u16> = units.into_iter().map(u16::swap_bytes).collect();
+                let args = String::from_utf16(&units).map_err(de::Error::custom)?;
+
+                next_u16(&mut seq, 3)?;
+                next_u16(&mut seq, 4)?;
+
+                Ok(BuffArgs(
+                    args.strip_prefix('\t').unwrap_or(&args).to_string(),
+                ))
+            }
+        }
+
+        deserializer.deserialize_tuple(usize::MAX, BuffArgsVisitor)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BuffInfo {
+    pub unknown_00: u32, // 0x00000000
+    pub icon: u16,
+    pub unknown_06: u16, // 0x0001
+    pub unknown_08: u32, // 0x00000000
+    pub duration: u16,   // Seconds, or zero for no timer
+    pub unknown_0e: u16, // 0x0000
+    pub unknown_10: u8,  // 0x00
+    pub title: u32,
+    pub description: u32,
+    pub unknown_19: u32, // 0x00000000
+    pub args: BuffArgs,
+}
+
+// Adds a buff when there is an entry, otherwise removes it
+#[packet(var(id = 0xDF))]
+pub struct BuffBar {
+    pub serial: Serial,
+    pub icon: u16,
+    pub info: List<BuffInfo, u16>,
+}
+
+#[packet(fixed(id = 0xAF, size = 12))]
+pub struct DeathAction {
+    pub serial: Serial,
+    pub corpse: Serial,
+    pub running: u32, // Nonzero plays the animation for dying while running
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProfileUpdate {
+    pub unknown_00: u16, // 0x0001
+    pub text: GumpText,
+}
+
+#[packet(var(id = 0xB8))]
+pub struct ProfileRequest {
+    pub edit: bool,
+    pub serial: Serial,
+    pub update: ListNonTerm<ProfileUpdate>, // Only present when editing
+}
+
+impl ProfileRequest {
+    pub fn new_text(&self) -> Option<&str> {
+        let update: &[ProfileUpdate] = self.update.as_ref();
+        update.first().map(|update| update.text.as_str())
+    }
+}
+
+#[packet(var(id = 0xB8))]
+pub struct ProfileResponse {
+    pub serial: Serial,
+    pub header: String,
+    pub footer: UnicodeStr,
+    pub body: UnicodeStr,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::packets::{FromPacketData, Packet};
     use crate::ser::to_writer;
+    mod death_action {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0xAFu8, 0x00, 0x00, 0xDA, 0x32, 0x40, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&DeathAction {
+                    serial: 55858,
+                    corpse: 0x40000300,
+                    running: 0,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod paperdoll {
+        use super::*;
+
+        fn paperdoll() -> Paperdoll {
+            Paperdoll {
+                serial: 3833,
+                text: "Hamled, the Wanderer".into(),
+                flags: 0x02,
+            }
+        }
+
+        const BYTES: [u8; 66] = [
+            0x88u8, 0x00, 0x00, 0x0E, 0xF9, 0x48, 0x61, 0x6D, 0x6C, 0x65, 0x64, 0x2C, 0x20, 0x74,
+            0x68, 0x65, 0x20, 0x57, 0x61, 0x6E, 0x64, 0x65, 0x72, 0x65, 0x72, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&paperdoll()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &BYTES;
+
+            let parsed = Paperdoll::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, paperdoll());
+        }
+    }
+
+    mod buff_bar {
+        use super::*;
+
+        #[test]
+        fn serialize_add() {
+            let expected_bytes = [
+                0xDFu8, 0x00, 0x38, 0x00, 0x00, 0x0E, 0xF9, 0x03, 0xF2, 0x00, 0x01, 0x00, 0x00,
+                0x00, 0x00, 0x03, 0xF2, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1E, 0x00, 0x00,
+                0x00, 0x00, 0x10, 0x6A, 0x87, 0x00, 0x10, 0x6A, 0x88, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x01, 0x00, 0x00, 0x09, 0x00, 0x31, 0x00, 0x30, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+                0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&BuffBar {
+                    serial: 3833,
+                    icon: 1010, // Bless
+                    info: vec![BuffInfo {
+                        unknown_00: 0,
+                        icon: 1010,
+                        unknown_06: 0x0001,
+                        unknown_08: 0,
+                        duration: 30,
+                        unknown_0e: 0,
+                        unknown_10: 0,
+                        title: 1075847,       // Bless
+                        description: 1075848, // +~1_val~ to Strength, Dexterity, and Intelligence
+                        unknown_19: 0,
+                        args: BuffArgs("10".to_string()),
+                    }]
+                    .into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+
+        #[test]
+        fn serialize_remove() {
+            let expected_bytes = [
+                0xDFu8, 0x00, 0x0B, 0x00, 0x00, 0x0E, 0xF9, 0x03, 0xF2, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&BuffBar {
+                    serial: 3833,
+                    icon: 1010, // Bless
+                    info: vec![].into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod player_update {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0x20u8, 0x00, 0x00, 0x0E, 0xF9, 0x01, 0x90, 0x00, 0x03, 0xEA, 0x00, 0x0E, 0x53,
+                0x0A, 0x41, 0x00, 0x00, 0x04, 0xFB,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&PlayerUpdate {
+                    serial: 3833,
+                    body: 400,
+                    unknown_06: 0,
+                    hue: 1002,
+                    flags: EntityFlags::None,
+                    x: 3667,
+                    y: 2625,
+                    unknown_0e: 0,
+                    direction: Direction::South,
+                    z: -5,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod profile_request {
+        use super::*;
+
+        #[test]
+        fn deserialize_view() {
+            let profile_request = ProfileRequest {
+                edit: false,
+                serial: 3833,
+                update: vec![].into(),
+            };
+
+            let mut input: &[u8] = &[0xB8u8, 0x00, 0x08, 0x00, 0x00, 0x00, 0x0E, 0xF9];
+
+            let parsed =
+                ProfileRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, profile_request);
+            assert_eq!(parsed.new_text(), None);
+        }
+
+        #[test]
+        fn deserialize_edit() {
+            let mut input: &[u8] = &[
+                0xB8u8, 0x00, 0x12, 0x01, 0x00, 0x00, 0x0E, 0xF9, 0x00, 0x01, 0x00, 0x03, 0x00,
+                0x48, 0x00, 0x69, 0x00, 0x21,
+            ];
+
+            let parsed =
+                ProfileRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert!(parsed.edit);
+            assert_eq!(parsed.serial, 3833);
+            assert_eq!(parsed.new_text(), Some("Hi!"));
+        }
+    }
+
+    mod profile_response {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0xB8u8, 0x00, 0x18, 0x00, 0x00, 0x0E, 0xF9, 0x48, 0x61, 0x6D, 0x6C, 0x65, 0x64,
+                0x00, 0x00, 0x00, 0x00, 0x48, 0x00, 0x69, 0x00, 0x21, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&ProfileResponse {
+                    serial: 3833,
+                    header: "Hamled".to_string(),
+                    footer: "".into(),
+                    body: "Hi!".into(),
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod status_bar_flag {
+        use super::*;
+
+        #[test]
+        fn serialize_poisoned() {
+            let expected_bytes = [
+                0x17u8, 0x00, 0x0C, 0x00, 0x00, 0xDA, 0x32, 0x00, 0x01, 0x00, 0x01, 0x01,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&StatusBarFlag {
+                    serial: 55858,
+                    unknown_04: 0x0001,
+                    color: StatusBarColor::Green,
+                    enabled: true,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod update_health {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0xA1u8, 0x00, 0x00, 0xDA, 0x32, 0x00, 0x64, 0x00, 0x2A];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&UpdateHealth {
+                    serial: 55858,
+                    max: 100,
+                    current: 42,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod update_mana {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0xA2u8, 0x00, 0x00, 0x0E, 0xF9, 0x00, 0x32, 0x00, 0x19];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&UpdateMana {
+                    serial: 3833,
+                    max: 50,
+                    current: 25,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod update_stamina {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0xA3u8, 0x00, 0x00, 0x0E, 0xF9, 0x00, 0x50, 0x00, 0x50];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&UpdateStamina {
+                    serial: 3833,
+                    max: 80,
+                    current: 80,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod war_mode {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let war_mode = WarMode {
+                war: true,
+                unknown_01: [0x00, 0x32, 0x00],
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&war_mode)).expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), [0x72u8, 0x01, 0x00, 0x32, 0x00]);
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed = WarMode::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, war_mode);
+        }
+    }
+
     mod appearance {
         use super::*;
 
-        fn appearance() -> Appearance {
+        pub(super) fn appearance() -> Appearance {
             Appearance {
                 state: State {
                     serial: 0x12345678,
@@ -158,6 +733,48 @@ mod tests {
             assert_eq!(packet.as_slice(), expected_bytes);
         }
 
+        #[test]
+        fn serialize_one_pass() {
+            let mut two_pass = Vec::<u8>::new();
+            to_writer(&mut two_pass, &Packet::<_>::from(&appearance()))
+                .expect("Failed to write packet");
+
+            let mut one_pass = Vec::<u8>::new();
+            let size = Packet::<_>::from(&appearance())
+                .to_writer(&mut one_pass)
+                .expect("Failed to write packet");
+
+            assert_eq!(one_pass, two_pass);
+            assert_eq!(size, one_pass.len());
+        }
+
+        #[test]
+        fn json_round_trip() {
+            let json = serde_json::to_value(appearance()).expect("Failed to write JSON");
+
+            // The items are a plain array, without the list terminator
+            assert_eq!(json["state"]["serial"], 0x12345678);
+            assert_eq!(json["items"].as_array().map(Vec::len), Some(8));
+            assert_eq!(json["items"][7]["type_id"], 0x8A8A);
+
+            let parsed: Appearance = serde_json::from_value(json).expect("Failed to parse JSON");
+            assert_eq!(parsed, appearance());
+        }
+
+        #[test]
+        #[cfg(feature = "json")]
+        fn json_helpers_round_trip() {
+            use crate::json::{from_json, to_json};
+
+            let json = to_json(&appearance()).expect("Failed to write JSON");
+            assert!(json.contains("\"type_id\": 35466"));
+
+            let parsed: Appearance = from_json(&json).expect("Failed to parse JSON");
+            assert_eq!(parsed, appearance());
+
+            assert!(from_json::<Appearance>(r#"{"state": {}}"#).is_err());
+        }
+
         #[test]
         fn deserialize() {
             let appearance = appearance();
@@ -177,4 +794,64 @@ mod tests {
             assert_eq!(parsed, appearance);
         }
     }
+
+    mod worn_item {
+        use super::*;
+
+        fn worn_item() -> WornItem {
+            WornItem {
+                serial: 0x40000001,
+                type_id: 0x1A1A,
+                unknown_06: 0x00,
+                layer: 0x1B,
+                mobile: 0x12345678,
+                hue: 0x1C1C,
+            }
+        }
+
+        const WORN_ITEM_BYTES: [u8; 15] = [
+            0x2Eu8, 0x40, 0x00, 0x00, 0x01, 0x1A, 0x1A, 0x00, 0x1B, 0x12, 0x34, 0x56, 0x78, 0x1C,
+            0x1C,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&worn_item()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), WORN_ITEM_BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &WORN_ITEM_BYTES;
+
+            let parsed = WornItem::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, worn_item());
+        }
+
+        #[test]
+        fn matches_appearance() {
+            let appearance = super::appearance::appearance();
+            let items: Vec<Item> = appearance.items.clone().into();
+            let item = items[0];
+
+            let worn = WornItem::new(appearance.state.serial, &item);
+            assert_eq!(worn, worn_item());
+            assert_eq!(worn.item(), item);
+
+            // The same fields are laid out the same way, apart from the layer
+            // being preceded by a byte and the mobile serial being in between
+            let mut appearance_bytes = Vec::<u8>::new();
+            to_writer(&mut appearance_bytes, &Packet::<_>::from(&appearance))
+                .expect("Failed to write packet");
+            let item_bytes = &appearance_bytes[19..28];
+
+            assert_eq!(WORN_ITEM_BYTES[1..7], item_bytes[..6]);
+            assert_eq!(WORN_ITEM_BYTES[8], item_bytes[6]);
+            assert_eq!(WORN_ITEM_BYTES[13..], item_bytes[7..]);
+        }
+    }
 }
@@ -15,6 +15,13 @@ pub struct Success {
     pub notoriety: Notoriety,
 }
 
+// Sent by the client when it thinks its position has drifted from the
+// server's, which answers with the player's position
+#[packet(fixed(id = 0x22, size = 2))]
+pub struct Resync {
+    pub unknown_00: u16, // 0x0000
+}
+
 #[packet(fixed(id = 0x21, size = 7))]
 pub struct Reject {
     pub sequence: u8,
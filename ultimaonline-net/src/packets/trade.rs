@@ -0,0 +1,238 @@
+use crate::types::{Name, Serial};
+use macros::packet;
+
+// The partner's name is preceded by a flag saying whether it is present,
+// which is always set when sending since clients expect the name.
+mod partner_name {
+    use super::*;
+    use serde::de::{self, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(name: &Name, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&true)?; // Has name
+        tuple.serialize_element(name)?;
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Name, D::Error> {
+        struct PartnerNameVisitor;
+
+        impl<'de> Visitor<'de> for PartnerNameVisitor {
+            type Value = Name;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a flag followed by an optional name")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let has_name: bool = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+                if !has_name {
+                    return Ok(Default::default());
+                }
+
+                seq.next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))
+            }
+        }
+
+        deserializer.deserialize_tuple(2, PartnerNameVisitor)
+    }
+}
+
+// Every secure trade packet shares the same id, with the layout
+// of the rest of the packet determined by the leading action byte.
+#[packet(var(id = 0x6F))]
+pub enum SecureTrade {
+    #[packet_tag(0x00)]
+    Open {
+        partner: Serial,
+        container: Serial,
+        partner_container: Serial,
+        #[serde(with = "partner_name")]
+        partner_name: Name,
+    },
+    #[packet_tag(0x01)]
+    Close { container: Serial },
+    #[packet_tag(0x02)]
+    Update {
+        container: Serial,
+        #[serde(with = "crate::types::bool_u32")]
+        accepted: bool,
+        #[serde(with = "crate::types::bool_u32")]
+        partner_accepted: bool,
+    },
+    #[packet_tag(0x03)]
+    UpdateGold {
+        container: Serial,
+        gold: u32,
+        platinum: u32,
+    },
+}
+
+// The client only reports its own acceptance, so its
+// responses are shorter than the updates sent by the server.
+#[packet(var(id = 0x6F))]
+pub enum TradeResponse {
+    #[packet_tag(0x01)]
+    Cancel { container: Serial },
+    #[packet_tag(0x02)]
+    Accept {
+        container: Serial,
+        #[serde(with = "crate::types::bool_u32")]
+        accepted: bool,
+    },
+    #[packet_tag(0x03)]
+    UpdateGold {
+        container: Serial,
+        gold: u32,
+        platinum: u32,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::{FromPacketData, Packet};
+    use crate::ser::to_writer;
+    mod secure_trade {
+        use super::*;
+
+        const OPEN_BYTES: [u8; 47] = [
+            0x6Fu8, 0x00, 0x2F, 0x00, 0x00, 0x00, 0xDA, 0x32, 0x40, 0x00, 0x01, 0x00, 0x40, 0x00,
+            0x01, 0x01, 0x01, 0x48, 0x61, 0x6D, 0x6C, 0x65, 0x64, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        fn open() -> SecureTrade {
+            SecureTrade::Open {
+                partner: 55858,
+                container: 0x40000100,
+                partner_container: 0x40000101,
+                partner_name: "Hamled".into(),
+            }
+        }
+
+        #[test]
+        fn serialize_open() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&open())).expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), OPEN_BYTES);
+        }
+
+        #[test]
+        fn deserialize_open() {
+            let mut input: &[u8] = &OPEN_BYTES;
+
+            let parsed = SecureTrade::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, open());
+        }
+
+        #[test]
+        fn deserialize_update() {
+            let secure_trade = SecureTrade::Update {
+                container: 0x40000100,
+                accepted: true,
+                partner_accepted: false,
+            };
+
+            let mut input: &[u8] = &[
+                0x6Fu8, 0x00, 0x10, 0x02, 0x40, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+                0x00, 0x00, 0x00,
+            ];
+
+            let parsed = SecureTrade::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, secure_trade);
+        }
+
+        #[test]
+        fn serialize_update() {
+            let secure_trade = SecureTrade::Update {
+                container: 0x40000100,
+                accepted: false,
+                partner_accepted: true,
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&secure_trade))
+                .expect("Failed to write packet");
+
+            assert_eq!(
+                packet.as_slice(),
+                [
+                    0x6Fu8, 0x00, 0x10, 0x02, 0x40, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                    0x00, 0x00, 0x01,
+                ]
+            );
+        }
+
+        #[test]
+        fn round_trip_update_gold() {
+            let secure_trade = SecureTrade::UpdateGold {
+                container: 0x40000100,
+                gold: 1500,
+                platinum: 2,
+            };
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&secure_trade))
+                .expect("Failed to write packet");
+
+            let mut input: &[u8] = &packet;
+            let parsed = SecureTrade::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, secure_trade);
+        }
+
+        #[test]
+        fn deserialize_unknown_action() {
+            let mut input: &[u8] = &[0x6Fu8, 0x00, 0x08, 0x07, 0x40, 0x00, 0x01, 0x00];
+
+            assert!(SecureTrade::from_packet_data(&mut input).is_err());
+        }
+    }
+
+    mod trade_response {
+        use super::*;
+
+        #[test]
+        fn deserialize_accept() {
+            let trade_response = TradeResponse::Accept {
+                container: 0x40000100,
+                accepted: true,
+            };
+
+            let mut input: &[u8] = &[
+                0x6Fu8, 0x00, 0x0C, 0x02, 0x40, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01,
+            ];
+
+            let parsed =
+                TradeResponse::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, trade_response);
+        }
+
+        #[test]
+        fn deserialize_cancel() {
+            let trade_response = TradeResponse::Cancel {
+                container: 0x40000100,
+            };
+
+            let mut input: &[u8] = &[0x6Fu8, 0x00, 0x08, 0x01, 0x40, 0x00, 0x01, 0x00];
+
+            let parsed =
+                TradeResponse::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, trade_response);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::types::{list::ListNonTerm, Graphic, Hue, List, PascalStr, Serial};
+use macros::packet;
+use serde::{Deserialize, Serialize};
+
+// Unlike other length-prefixed names, the name of an item for sale is null
+// terminated, with the terminator counted by the length
+mod buy_item_name {
+    use super::*;
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        name: &PascalStr<u8>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return name.serialize(serializer);
+        }
+
+        let mut bytes = name.as_str().as_bytes().to_vec();
+        bytes.push(0);
+
+        List::<u8, u8>::from(bytes).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PascalStr<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            return PascalStr::deserialize(deserializer);
+        }
+
+        let mut bytes = Vec::from(List::<u8, u8>::deserialize(deserializer)?);
+        if bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        if !bytes.is_ascii() {
+            return Err(de::Error::custom("item name must only contain ASCII"));
+        }
+
+        let name = String::from_utf8(bytes).map_err(de::Error::custom)?;
+        Ok(name.as_str().into())
+    }
+}
+
+// Prices for the contents of the vendor's buy container,
+// in the same order as the items were sent to the client.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BuyItem {
+    pub price: u32,
+    #[serde(with = "buy_item_name")]
+    pub name: PascalStr<u8>,
+}
+
+#[packet(var(id = 0x74))]
+pub struct BuyList {
+    pub container: Serial,
+    pub items: List<BuyItem, u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BuyRequestItem {
+    pub layer: u8, // 0x1A
+    pub serial: Serial,
+    pub amount: u16,
+}
+
+#[packet(var(id = 0x3B))]
+pub struct BuyRequest {
+    pub vendor: Serial,
+    pub flag: u8, // 0x00 when there are no items, otherwise 0x02
+    pub items: ListNonTerm<BuyRequestItem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SellItem {
+    pub serial: Serial,
+    pub graphic: Graphic,
+    pub hue: Hue,
+    pub amount: u16,
+    pub price: u16,
+    pub name: PascalStr<u16>,
+}
+
+#[packet(var(id = 0x9E))]
+pub struct SellList {
+    pub vendor: Serial,
+    pub items: List<SellItem, u16>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::{FromPacketData, Packet};
+    use crate::ser::to_writer;
+    mod buy_list {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let buy_list = BuyList {
+                container: 0x40000200,
+                items: vec![
+                    BuyItem {
+                        price: 5,
+                        name: "Apple".into(),
+                    },
+                    BuyItem {
+                        price: 21,
+                        name: "Dagger".into(),
+                    },
+                ]
+                .into(),
+            };
+
+            let expected_bytes = [
+                0x74u8, 0x00, 0x1F, 0x40, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, 0x05, 0x06,
+                0x41, 0x70, 0x70, 0x6C, 0x65, 0x00, 0x00, 0x00, 0x00, 0x15, 0x07, 0x44, 0x61, 0x67,
+                0x67, 0x65, 0x72, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&buy_list)).expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed = BuyList::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, buy_list);
+        }
+    }
+
+    mod buy_request {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let buy_request = BuyRequest {
+                vendor: 0x00001234,
+                flag: 0x02,
+                items: vec![
+                    BuyRequestItem {
+                        layer: 0x1A,
+                        serial: 0x40000201,
+                        amount: 10,
+                    },
+                    BuyRequestItem {
+                        layer: 0x1A,
+                        serial: 0x40000202,
+                        amount: 1,
+                    },
+                ]
+                .into(),
+            };
+
+            let expected_bytes = [
+                0x3Bu8, 0x00, 0x16, 0x00, 0x00, 0x12, 0x34, 0x02, 0x1A, 0x40, 0x00, 0x02, 0x01,
+                0x00, 0x0A, 0x1A, 0x40, 0x00, 0x02, 0x02, 0x00, 0x01,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&buy_request))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed = BuyRequest::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, buy_request);
+        }
+    }
+
+    mod sell_list {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let sell_list = SellList {
+                vendor: 0x00001234,
+                items: vec![SellItem {
+                    serial: 0x40000010,
+                    graphic: 0x0F0E, // Empty bottle
+                    hue: 0,
+                    amount: 3,
+                    price: 2,
+                    name: "Empty Bottle".into(),
+                }]
+                .into(),
+            };
+
+            let expected_bytes = [
+                0x9Eu8, 0x00, 0x23, 0x00, 0x00, 0x12, 0x34, 0x00, 0x01, 0x40, 0x00, 0x00, 0x10,
+                0x0F, 0x0E, 0x00, 0x00, 0x00, 0x03, 0x00, 0x02, 0x00, 0x0C, 0x45, 0x6D, 0x70, 0x74,
+                0x79, 0x20, 0x42, 0x6F, 0x74, 0x74, 0x6C, 0x65,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&sell_list)).expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+
+            let mut input: &[u8] = packet.as_slice();
+            let parsed = SellList::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, sell_list);
+        }
+    }
+}
@@ -1,6 +1,519 @@
+use crate::types::{Graphic, Serial, WireSize};
 use macros::packet;
+use serde_repr::{Deserialize_repr, Serialize_repr};
 
 #[packet(fixed(id = 0x4F, size = 1))]
 pub struct WorldLightLevel {
     pub level: u8,
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum SoundMode {
+    Quiet = 0x00,
+    Repeat = 0x01,
+}
+
+#[packet(fixed(id = 0x54, size = 11))]
+pub struct SoundEffect {
+    pub mode: SoundMode,
+    pub sound: u16,
+    pub unknown_03: u16, // 0x0000
+    pub x: u16,
+    pub y: u16,
+    pub z: i16,
+}
+
+#[packet(fixed(id = 0x6D, size = 2))]
+pub struct PlayMusic {
+    pub music: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum WeatherType {
+    Rain = 0x00,
+    Storm = 0x01,
+    Snow = 0x02,
+    Brewing = 0x03, // Storm is brewing
+    None = 0xFF,
+}
+
+#[packet(fixed(id = 0x65, size = 3))]
+pub struct Weather {
+    pub kind: WeatherType,
+    pub intensity: u8, // Number of effects on screen, up to 0x46
+    pub temperature: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum SeasonType {
+    Spring = 0x00,
+    Summer = 0x01,
+    Fall = 0x02,
+    Winter = 0x03,
+    Desolation = 0x04,
+}
+
+#[packet(fixed(id = 0xBC, size = 2))]
+pub struct Season {
+    pub season: SeasonType,
+    pub play_sound: bool,
+}
+
+#[packet(fixed(id = 0x5B, size = 3))]
+pub struct GameTime {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum EffectType {
+    Moving = 0x00, // From source to target
+    Lightning = 0x01,
+    FixedLocation = 0x02,
+    FixedSource = 0x03, // Follows the source, such as an effect on oneself
+}
+
+#[packet(fixed(id = 0x70, size = 27))]
+pub struct GraphicalEffect {
+    pub kind: EffectType,
+    pub source: Serial,
+    pub target: Serial,
+    pub graphic: Graphic,
+    pub source_x: u16,
+    pub source_y: u16,
+    pub source_z: i8,
+    pub target_x: u16,
+    pub target_y: u16,
+    pub target_z: i8,
+    pub speed: u8,
+    pub duration: u8,
+    pub unknown_18: u16, // 0x0000
+    pub fixed_direction: bool,
+    pub explodes: bool,
+}
+
+// Colored effect for clients before the particle effects of 0xC7
+#[packet(fixed(id = 0xC0, size = 35))]
+pub struct HuedEffectLegacy {
+    #[packet_field(nested)]
+    pub effect: GraphicalEffect,
+    pub hue: u32,
+    pub render_mode: u32,
+}
+
+#[packet(fixed(id = 0xC7, size = 48))]
+pub struct HuedEffect {
+    #[packet_field(nested)]
+    pub effect: GraphicalEffect,
+    pub hue: u32,
+    pub render_mode: u32,
+    pub particle_effect: u16,
+    pub explode_effect: u16,
+    pub explode_sound: u16,
+    pub serial: Serial, // Serial of the effect itself
+    pub layer: u8,
+    pub unknown_2e: u16, // 0x0000
+}
+
+// Opens the map gump for a map item, showing the area within the bounds
+// scaled to the width and height
+#[packet(fixed(id = 0x90, size = 18))]
+pub struct OpenMap {
+    pub serial: Serial,
+    pub gump: Graphic, // 0x139D
+    pub x1: u16,       // Upper left
+    pub y1: u16,
+    pub x2: u16, // Lower right
+    pub y2: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
+#[repr(u8)]
+pub enum MapPinCommand {
+    Add = 0x01,
+    Insert = 0x02,
+    Change = 0x03,
+    Remove = 0x04,
+    Clear = 0x05,
+    ToggleEdit = 0x06,   // From the client
+    EditResponse = 0x07, // From the server, with pin as whether it's editable
+}
+
+// Edits the course plotted on a map, sent by the client when the player
+// edits it and by the server to show it. The pin position is relative to
+// the map gump, rather than in world coordinates.
+#[packet(fixed(id = 0x56, size = 10))]
+pub struct MapPins {
+    pub serial: Serial,
+    pub command: MapPinCommand,
+    pub pin: u8,
+    pub x: u16,
+    pub y: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::{FromPacketData, Packet};
+    use crate::ser::to_writer;
+    mod sound_effect {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0x54u8, 0x01, 0x00, 0xEA, 0x00, 0x00, 0x0E, 0x54, 0x0A, 0x41, 0x00, 0x05,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&SoundEffect {
+                    mode: SoundMode::Repeat,
+                    sound: 0x00EA, // Wooden door opening
+                    unknown_03: 0,
+                    x: 3668,
+                    y: 2625,
+                    z: 5,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+
+        #[test]
+        fn deserialize() {
+            let sound_effect = SoundEffect {
+                mode: SoundMode::Quiet,
+                sound: 0x00F1, // Wooden door closing
+                unknown_03: 0,
+                x: 1602,
+                y: 1591,
+                z: -2,
+            };
+
+            let mut input: &[u8] = &[
+                0x54u8, 0x00, 0x00, 0xF1, 0x00, 0x00, 0x06, 0x42, 0x06, 0x37, 0xFF, 0xFE,
+            ];
+
+            let parsed = SoundEffect::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, sound_effect);
+        }
+    }
+
+    mod play_music {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0x6Du8, 0x00, 0x09];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&PlayMusic {
+                    music: 0x0009, // Britain
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod weather {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0x65u8, 0x00, 0x46, 0x0A];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&Weather {
+                    kind: WeatherType::Rain,
+                    intensity: 0x46,
+                    temperature: 10,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod season {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0xBCu8, 0x03, 0x01];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&Season {
+                    season: SeasonType::Winter,
+                    play_sound: true,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod game_time {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [0x5Bu8, 0x0C, 0x00, 0x00];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&GameTime {
+                    hour: 12,
+                    minute: 0,
+                    second: 0,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod graphical_effect {
+        use super::*;
+
+        // Lightning bolt striking a mobile
+        pub fn lightning_bolt() -> GraphicalEffect {
+            GraphicalEffect {
+                kind: EffectType::Lightning,
+                source: 55858,
+                target: 0,
+                graphic: 0,
+                source_x: 3668,
+                source_y: 2625,
+                source_z: 0,
+                target_x: 3668,
+                target_y: 2625,
+                target_z: 0,
+                speed: 0,
+                duration: 0,
+                unknown_18: 0,
+                fixed_direction: false,
+                explodes: false,
+            }
+        }
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0x70u8, 0x01, 0x00, 0x00, 0xDA, 0x32, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0E,
+                0x54, 0x0A, 0x41, 0x00, 0x0E, 0x54, 0x0A, 0x41, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&lightning_bolt()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+
+        // Fireball flying from one mobile to another
+        pub fn fireball() -> GraphicalEffect {
+            GraphicalEffect {
+                kind: EffectType::Moving,
+                source: 3833,
+                target: 55858,
+                graphic: 0x36D4,
+                source_x: 3667,
+                source_y: 2626,
+                source_z: 5,
+                target_x: 3668,
+                target_y: 2625,
+                target_z: -2,
+                speed: 7,
+                duration: 0x10,
+                unknown_18: 0,
+                fixed_direction: true,
+                explodes: true,
+            }
+        }
+
+        #[test]
+        fn serialize_moving() {
+            let expected_bytes = [
+                0x70u8, 0x00, 0x00, 0x00, 0x0E, 0xF9, 0x00, 0x00, 0xDA, 0x32, 0x36, 0xD4, 0x0E,
+                0x53, 0x0A, 0x42, 0x05, 0x0E, 0x54, 0x0A, 0x41, 0xFE, 0x07, 0x10, 0x00, 0x00, 0x01,
+                0x01,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&fireball()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod hued_effect_legacy {
+        use super::*;
+
+        fn hued_fireball() -> HuedEffectLegacy {
+            HuedEffectLegacy {
+                effect: super::graphical_effect::fireball(),
+                hue: 0x0481,
+                render_mode: 2,
+            }
+        }
+
+        // The same as the graphical effect, with the hue and render mode after
+        const HUED_FIREBALL_BYTES: [u8; 36] = [
+            0xC0u8, 0x00, 0x00, 0x00, 0x0E, 0xF9, 0x00, 0x00, 0xDA, 0x32, 0x36, 0xD4, 0x0E, 0x53,
+            0x0A, 0x42, 0x05, 0x0E, 0x54, 0x0A, 0x41, 0xFE, 0x07, 0x10, 0x00, 0x00, 0x01, 0x01,
+            0x00, 0x00, 0x04, 0x81, 0x00, 0x00, 0x00, 0x02,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&hued_fireball()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), HUED_FIREBALL_BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &HUED_FIREBALL_BYTES;
+
+            let parsed =
+                HuedEffectLegacy::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, hued_fireball());
+        }
+    }
+
+    mod hued_effect {
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let expected_bytes = [
+                0xC7u8, 0x01, 0x00, 0x00, 0xDA, 0x32, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0E,
+                0x54, 0x0A, 0x41, 0x00, 0x0E, 0x54, 0x0A, 0x41, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x04, 0x81, 0x00, 0x00, 0x00, 0x03, 0x13, 0x88, 0x00, 0x00, 0x00,
+                0x29, 0x00, 0x00, 0xDA, 0x32, 0xFF, 0x00, 0x00,
+            ];
+
+            let mut packet = Vec::<u8>::new();
+            to_writer(
+                &mut packet,
+                &Packet::<_>::from(&HuedEffect {
+                    effect: super::graphical_effect::lightning_bolt(),
+                    hue: 0x0481,
+                    render_mode: 3,
+                    particle_effect: 5000,
+                    explode_effect: 0,
+                    explode_sound: 0x0029, // Lightning
+                    serial: 55858,
+                    layer: 0xFF,
+                    unknown_2e: 0,
+                }),
+            )
+            .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), expected_bytes);
+        }
+    }
+
+    mod open_map {
+        use super::*;
+
+        fn open_map() -> OpenMap {
+            OpenMap {
+                serial: 0x40001234,
+                gump: 0x139D,
+                x1: 1400,
+                y1: 1500,
+                x2: 1800,
+                y2: 1900,
+                width: 200,
+                height: 200,
+            }
+        }
+
+        const OPEN_MAP_BYTES: [u8; 19] = [
+            0x90u8, 0x40, 0x00, 0x12, 0x34, 0x13, 0x9D, 0x05, 0x78, 0x05, 0xDC, 0x07, 0x08, 0x07,
+            0x6C, 0x00, 0xC8, 0x00, 0xC8,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&open_map()))
+                .expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), OPEN_MAP_BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &OPEN_MAP_BYTES;
+
+            let parsed = OpenMap::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, open_map());
+        }
+    }
+
+    mod map_pins {
+        use super::*;
+
+        fn add_pin() -> MapPins {
+            MapPins {
+                serial: 0x40001234,
+                command: MapPinCommand::Add,
+                pin: 0,
+                x: 0x0041,
+                y: 0x0073,
+            }
+        }
+
+        const ADD_PIN_BYTES: [u8; 11] = [
+            0x56u8, 0x40, 0x00, 0x12, 0x34, 0x01, 0x00, 0x00, 0x41, 0x00, 0x73,
+        ];
+
+        #[test]
+        fn serialize() {
+            let mut packet = Vec::<u8>::new();
+            to_writer(&mut packet, &Packet::<_>::from(&add_pin())).expect("Failed to write packet");
+
+            assert_eq!(packet.as_slice(), ADD_PIN_BYTES);
+        }
+
+        #[test]
+        fn deserialize() {
+            let mut input: &[u8] = &ADD_PIN_BYTES;
+
+            let parsed = MapPins::from_packet_data(&mut input).expect("Failed to parse packet");
+
+            assert_eq!(parsed, add_pin());
+        }
+    }
+}
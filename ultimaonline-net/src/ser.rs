@@ -25,8 +25,9 @@ where
     Ok(serializer.size)
 }
 
+// Returns the number of bytes written
 #[inline]
-pub fn to_writer<'a, W, T>(writer: &'a mut W, value: &'a T) -> Result<()>
+pub fn to_writer<'a, W, T>(writer: &'a mut W, value: &'a T) -> Result<usize>
 where
     W: io::Write,
     T: Serialize,
@@ -37,7 +38,7 @@ where
     };
     value.serialize(&mut serializer)?;
 
-    Ok(())
+    Ok(serializer.size)
 }
 
 impl<'a, 'b, W> ser::Serializer for &'a mut Serializer<'b, W>
@@ -443,3 +444,25 @@ where
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FixedStr, List};
+
+    #[test]
+    fn counts_bytes_written() {
+        let value = (
+            0x1234u16,
+            FixedStr::<8>::from("name"),
+            List::<u32, u8>::from(vec![1, 2, 3]),
+            Some(0x7Fu8),
+        );
+
+        let mut buffer = vec![0xFFu8]; // Existing contents aren't counted
+        let size = to_writer(&mut buffer, &value).unwrap();
+        assert_eq!(size, buffer.len() - 1);
+        assert_eq!(size, to_size(&value).unwrap());
+        assert_eq!(size, 2 + 8 + 1 + 3 * 4 + 1);
+    }
+}
@@ -1,20 +1,86 @@
-use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use crate::error::Error;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
 use serde::ser::{self, Serialize, Serializer};
-use serde_repr::{Deserialize_repr, Serialize_repr};
+use serde::Deserialize;
+use serde_repr::Serialize_repr;
 use std::convert::TryFrom;
 use std::fmt;
+use std::marker::PhantomData;
 
 pub mod list;
-pub use list::List;
+pub use list::{ByteLenList, List, ListLen, ListTerm};
 
 pub mod movement;
-pub use movement::{Movement, MovementRaw};
+pub use movement::{Movement, MovementRaw, MovementSequence};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+// Number of bytes a type takes up once serialized, which fixed packets
+// use to check that their fields add up to their declared size. This can
+// differ from the in-memory size, which may include padding.
+pub trait WireSize {
+    const WIRE_SIZE: usize;
+}
+
+// Derived for structs as the sum of their fields, and for fieldless enums
+// as the size of their primitive repr
+pub use macros::WireSize;
+
+macro_rules! impl_wire_size {
+    ($($ty:ty),*) => {
+        $(impl WireSize for $ty {
+            const WIRE_SIZE: usize = std::mem::size_of::<$ty>();
+        })*
+    };
+}
+
+impl_wire_size!(u8, u16, u32, u64, i8, i16, i32, i64, bool);
+
+impl<T: WireSize, const LEN: usize> WireSize for [T; LEN] {
+    const WIRE_SIZE: usize = T::WIRE_SIZE * LEN;
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct FixedStr<const LEN: usize> {
     str: [u8; LEN],
 }
 
+impl<const LEN: usize> FixedStr<LEN> {
+    // Unlike From, fails rather than cutting the string short when it
+    // doesn't fit with a null after it
+    pub fn from_checked(string: &str) -> Result<Self, Error> {
+        if string.len() >= LEN {
+            return Err(Error::data(format!(
+                "{:?} is longer than {} bytes",
+                string,
+                LEN.saturating_sub(1)
+            )));
+        }
+
+        Ok(string.into())
+    }
+
+    // The string without the null padding
+    fn content(&self) -> &[u8] {
+        let len = self.str.iter().position(|&by| by == 0).unwrap_or(LEN);
+        &self.str[..len]
+    }
+}
+
+// Strings which only differ in what follows the first null are equal, since
+// clients don't always zero the rest of the buffer
+impl<const LEN: usize> PartialEq for FixedStr<LEN> {
+    fn eq(&self, other: &Self) -> bool {
+        self.content() == other.content()
+    }
+}
+
+impl<const LEN: usize> Eq for FixedStr<LEN> {}
+
+impl<const LEN: usize> fmt::Display for FixedStr<LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(self.content()))
+    }
+}
+
 impl<const LEN: usize> Serialize for FixedStr<LEN> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -24,12 +90,22 @@ impl<const LEN: usize> Serialize for FixedStr<LEN> {
             Err(ser::Error::custom(
                 "FixedStr must have a length <= u16::MAX",
             ))
+        } else if serializer.is_human_readable() {
+            // Without the null padding
+            match std::str::from_utf8(self.content()) {
+                Ok(string) => serializer.serialize_str(string),
+                Err(_) => serializer.serialize_bytes(self.content()),
+            }
         } else {
             serializer.serialize_bytes(&self.str)
         }
     }
 }
 
+impl<const LEN: usize> WireSize for FixedStr<LEN> {
+    const WIRE_SIZE: usize = LEN;
+}
+
 impl<const LEN: usize> Default for FixedStr<LEN> {
     fn default() -> Self {
         Self { str: [0u8; LEN] }
@@ -70,19 +146,24 @@ impl<'de, const LEN: usize> Visitor<'de> for FixedStrVisitor<LEN> {
         let mut val: FixedStr<LEN> = Default::default();
 
         for i in 0..seq.size_hint().unwrap_or(LEN) {
-            let by = seq.next_element::<u8>()?;
-            match by {
+            // Clients may leave off the padding of a string at the end of a
+            // packet, so the rest of the string is null
+            match seq.next_element::<u8>()? {
                 Some(by) => val.str[i] = by,
-                None => {
-                    return Err(de::Error::custom(
-                        "Missing 1 or more elements from FixedStr",
-                    ))
-                }
+                None => break,
             }
         }
 
         Ok(val)
     }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<Self::Value, E> {
+        if string.len() > LEN {
+            return Err(de::Error::invalid_length(string.len(), &self));
+        }
+
+        Ok(string.into())
+    }
 }
 
 impl<'de, const LEN: usize> Deserialize<'de> for FixedStr<LEN> {
@@ -90,10 +171,467 @@ impl<'de, const LEN: usize> Deserialize<'de> for FixedStr<LEN> {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_tuple(LEN, FixedStrVisitor)
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(FixedStrVisitor)
+        } else {
+            deserializer.deserialize_tuple_struct("FixedStr", LEN, FixedStrVisitor)
+        }
+    }
+}
+
+// Null-terminated string of big-endian UTF-16 code units
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnicodeStr(String);
+
+impl UnicodeStr {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
     }
 }
 
+impl Serialize for UnicodeStr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&self.0);
+        }
+
+        let units: Vec<u16> = self.0.encode_utf16().collect();
+        if units.contains(&0) {
+            return Err(ser::Error::custom(
+                "UnicodeStr cannot contain a null character",
+            ));
+        }
+
+        ListTerm::<u16, u16>::from(units).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for UnicodeStr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            return String::deserialize(deserializer).map(Self);
+        }
+
+        let units: Vec<u16> = ListTerm::<u16, u16>::deserialize(deserializer)?.into();
+
+        Ok(Self(String::from_utf16(&units).map_err(de::Error::custom)?))
+    }
+}
+
+impl From<&str> for UnicodeStr {
+    fn from(string: &str) -> Self {
+        Self(string.to_string())
+    }
+}
+
+impl From<String> for UnicodeStr {
+    fn from(string: String) -> Self {
+        Self(string)
+    }
+}
+
+impl From<UnicodeStr> for String {
+    fn from(val: UnicodeStr) -> Self {
+        val.0
+    }
+}
+
+impl fmt::Display for UnicodeStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// ASCII string prefixed by its length, without a null terminator
+#[derive(Clone, Debug, PartialEq)]
+pub struct PascalStr<L: ListLen = u8>(String, PhantomData<L>);
+
+impl<L: ListLen> PascalStr<L> {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl<L: ListLen> Default for PascalStr<L> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<L: ListLen + Serialize> Serialize for PascalStr<L> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !self.0.is_ascii() {
+            return Err(ser::Error::custom("PascalStr must only contain ASCII"));
+        }
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&self.0);
+        }
+
+        List::<u8, L>::from(self.0.as_bytes().to_vec()).serialize(serializer)
+    }
+}
+
+impl<'de, L: 'de + ListLen + Deserialize<'de>> Deserialize<'de> for PascalStr<L> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes: Vec<u8> = if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?.into_bytes()
+        } else {
+            List::<u8, L>::deserialize(deserializer)?.into()
+        };
+        if !bytes.is_ascii() {
+            return Err(de::Error::custom("PascalStr must only contain ASCII"));
+        }
+
+        Ok(Self(
+            String::from_utf8(bytes).map_err(de::Error::custom)?,
+            PhantomData,
+        ))
+    }
+}
+
+impl<L: ListLen> From<&str> for PascalStr<L> {
+    fn from(string: &str) -> Self {
+        Self(string.to_string(), PhantomData)
+    }
+}
+
+impl<L: ListLen> fmt::Display for PascalStr<L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// The server list gives the address of each server with its octets in
+// reverse order, i.e. 127.0.0.1 is sent as 01 00 00 7F
+pub mod ipv4_reversed {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::net::Ipv4Addr;
+
+    pub const WIRE_SIZE: usize = 4;
+
+    pub fn serialize<S: Serializer>(addr: &Ipv4Addr, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return addr.serialize(serializer);
+        }
+
+        let [a, b, c, d] = addr.octets();
+        (d, c, b, a).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Ipv4Addr, D::Error> {
+        if deserializer.is_human_readable() {
+            return Ipv4Addr::deserialize(deserializer);
+        }
+
+        let (d, c, b, a) = <(u8, u8, u8, u8)>::deserialize(deserializer)?;
+        Ok(Ipv4Addr::new(a, b, c, d))
+    }
+}
+
+// The game server handoff gives the address with its octets in order,
+// followed by the port as a big-endian u16, i.e. 127.0.0.1:2593 is sent
+// as 7F 00 00 01 0A 21
+pub mod socket_addr_v4 {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    pub const WIRE_SIZE: usize = 6;
+
+    pub fn serialize<S: Serializer>(
+        socket: &SocketAddrV4,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return socket.serialize(serializer);
+        }
+
+        let [a, b, c, d] = socket.ip().octets();
+        (a, b, c, d, socket.port()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SocketAddrV4, D::Error> {
+        if deserializer.is_human_readable() {
+            return SocketAddrV4::deserialize(deserializer);
+        }
+
+        let (a, b, c, d, port) = <(u8, u8, u8, u8, u16)>::deserialize(deserializer)?;
+        Ok(SocketAddrV4::new(Ipv4Addr::new(a, b, c, d), port))
+    }
+}
+
+// Addresses which may be either IPv4 or IPv6 are sent as their family (4 or
+// 6) followed by their octets in order, i.e. ::1 is sent as 06 00 .. 00 01
+pub mod ip_addr {
+    use serde::de::{self, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::fmt;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    const V4: u8 = 4;
+    const V6: u8 = 6;
+
+    pub fn serialize<S: Serializer>(addr: &IpAddr, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return addr.serialize(serializer);
+        }
+
+        match addr {
+            IpAddr::V4(addr) => (V4, addr.octets()).serialize(serializer),
+            IpAddr::V6(addr) => {
+                let mut tuple = serializer.serialize_tuple(17)?;
+                tuple.serialize_element(&V6)?;
+                for octet in addr.octets().iter() {
+                    tuple.serialize_element(octet)?;
+                }
+                tuple.end()
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<IpAddr, D::Error> {
+        struct IpAddrVisitor;
+
+        impl<'de> Visitor<'de> for IpAddrVisitor {
+            type Value = IpAddr;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an address family followed by its octets")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let family = seq
+                    .next_element::<u8>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+                let mut octets = [0u8; 16];
+                let len = match family {
+                    V4 => 4,
+                    V6 => 16,
+                    _ => {
+                        return Err(de::Error::custom(format!(
+                            "{} is not a valid address family",
+                            family
+                        )))
+                    }
+                };
+                for (i, octet) in octets[..len].iter_mut().enumerate() {
+                    *octet = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i + 1, &self))?;
+                }
+
+                Ok(match family {
+                    V4 => IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3])),
+                    _ => IpAddr::V6(Ipv6Addr::from(octets)),
+                })
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            return IpAddr::deserialize(deserializer);
+        }
+
+        // Only as many octets as the family needs are read
+        deserializer.deserialize_tuple(17, IpAddrVisitor)
+    }
+}
+
+pub trait SwapBytes: Copy {
+    fn swap_bytes(self) -> Self;
+}
+
+macro_rules! impl_swap_bytes {
+    ($($ty:ty),*) => {
+        $(impl SwapBytes for $ty {
+            fn swap_bytes(self) -> Self {
+                <$ty>::swap_bytes(self)
+            }
+        })*
+    };
+}
+
+impl_swap_bytes!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+// Integer which is sent in little-endian order, for the few structures
+// that don't use network byte order. Since the serializer always writes
+// big-endian, swapping the bytes beforehand produces little-endian output.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LittleEndian<T>(pub T);
+
+impl<T: SwapBytes + Serialize> Serialize for LittleEndian<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            self.0.serialize(serializer)
+        } else {
+            self.0.swap_bytes().serialize(serializer)
+        }
+    }
+}
+
+impl<'de, T: SwapBytes + Deserialize<'de>> Deserialize<'de> for LittleEndian<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            T::deserialize(deserializer).map(Self)
+        } else {
+            Ok(Self(T::deserialize(deserializer)?.swap_bytes()))
+        }
+    }
+}
+
+impl<T: WireSize> WireSize for LittleEndian<T> {
+    const WIRE_SIZE: usize = T::WIRE_SIZE;
+}
+
+impl<T> From<T> for LittleEndian<T> {
+    fn from(val: T) -> Self {
+        Self(val)
+    }
+}
+
+// Used by #[packet_field(le)] to serialize a plain field as LittleEndian
+pub mod little_endian {
+    use super::{LittleEndian, SwapBytes};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: SwapBytes + Serialize,
+        S: Serializer,
+    {
+        LittleEndian(*val).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: SwapBytes + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(LittleEndian::<T>::deserialize(deserializer)?.0)
+    }
+}
+
+// Flag which is sent as a full 32-bit integer rather than a single byte.
+// Any non-zero value is read as set.
+pub mod bool_u32 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(val: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*val as u32)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        Ok(u32::deserialize(deserializer)? != 0)
+    }
+}
+
+// Set of bit flags which is sent as the raw integer. The meaning of each
+// bit depends upon the packet, which defines the named flags it uses.
+macro_rules! flags_type {
+    ($name:ident, $int:ty) => {
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub $int);
+
+        impl $name {
+            pub const NONE: Self = Self(0);
+            pub const ALL: Self = Self(<$int>::MAX);
+
+            pub const fn bits(self) -> $int {
+                self.0
+            }
+
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            // Const versions of | and & !, for defining flag constants
+            pub const fn union(self, other: Self) -> Self {
+                Self(self.0 | other.0)
+            }
+
+            pub const fn difference(self, other: Self) -> Self {
+                Self(self.0 & !other.0)
+            }
+        }
+
+        impl std::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl std::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl std::ops::BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        impl std::ops::BitAndAssign for $name {
+            fn bitand_assign(&mut self, rhs: Self) {
+                self.0 &= rhs.0;
+            }
+        }
+
+        impl std::ops::Not for $name {
+            type Output = Self;
+
+            fn not(self) -> Self {
+                Self(!self.0)
+            }
+        }
+
+        impl WireSize for $name {
+            const WIRE_SIZE: usize = <$int as WireSize>::WIRE_SIZE;
+        }
+
+        impl From<$int> for $name {
+            fn from(val: $int) -> Self {
+                Self(val)
+            }
+        }
+
+        impl fmt::Binary for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Binary::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+flags_type!(Flags8, u8);
+flags_type!(Flags32, u32);
+
 pub type Serial = u32;
 
 pub type Name = FixedStr<30>;
@@ -102,7 +640,28 @@ pub type Name = FixedStr<30>;
 pub type Hue = u16;
 pub type Graphic = u16;
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+// Converts a byte into one of the enum's variants, so that values from a
+// client which aren't any of them fail with the offending value
+macro_rules! impl_try_from_u8 {
+    ($name:ident { $($variant:ident),+ }) => {
+        impl TryFrom<u8> for $name {
+            type Error = Error;
+
+            fn try_from(val: u8) -> Result<Self, Self::Error> {
+                $(
+                    if val == $name::$variant as u8 {
+                        return Ok($name::$variant);
+                    }
+                )+
+
+                Err(Error::data(format!("{} is not a valid {}", val, stringify!($name))))
+            }
+        }
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize, WireSize)]
+#[serde(try_from = "u8")]
 #[repr(u8)]
 pub enum Direction {
     North = 0,
@@ -115,7 +674,84 @@ pub enum Direction {
     Up,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+impl_try_from_u8!(Direction {
+    North,
+    Right,
+    East,
+    Down,
+    South,
+    Left,
+    West,
+    Up
+});
+
+impl Direction {
+    // In clockwise order, starting from North
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::Right,
+        Direction::East,
+        Direction::Down,
+        Direction::South,
+        Direction::Left,
+        Direction::West,
+        Direction::Up,
+    ];
+
+    fn rotate(self, steps: u8) -> Self {
+        Self::ALL[((self as u8 + steps) % 8) as usize]
+    }
+
+    // Each turn is by 45 degrees
+    pub fn turn_left(self) -> Self {
+        self.rotate(7)
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.rotate(1)
+    }
+
+    pub fn opposite(self) -> Self {
+        self.rotate(4)
+    }
+
+    // The (dx, dy) step of moving one tile in this direction. North is
+    // towards decreasing y, East towards increasing x.
+    pub fn offset(self) -> (i8, i8) {
+        use Direction::*;
+
+        match self {
+            North => (0, -1),
+            Right => (1, -1),
+            East => (1, 0),
+            Down => (1, 1),
+            South => (0, 1),
+            Left => (-1, 1),
+            West => (-1, 0),
+            Up => (-1, -1),
+        }
+    }
+
+    // The direction to face from one (x, y) point to look at another, or
+    // None if they are the same point. Points which are much further away
+    // along one axis than the other are treated as lying along that axis.
+    pub fn towards(from: (u16, u16), to: (u16, u16)) -> Option<Self> {
+        let dx = to.0 as i32 - from.0 as i32;
+        let dy = to.1 as i32 - from.1 as i32;
+
+        let (adx, ady) = (dx.abs(), dy.abs());
+
+        let step = (
+            if ady > 2 * adx { 0 } else { dx.signum() as i8 },
+            if adx > 2 * ady { 0 } else { dy.signum() as i8 },
+        );
+
+        Self::ALL.iter().copied().find(|dir| dir.offset() == step)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize, WireSize)]
+#[serde(try_from = "u8")]
 #[repr(u8)]
 pub enum Notoriety {
     Innocent = 1,
@@ -127,7 +763,18 @@ pub enum Notoriety {
     Invulnerable,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+impl_try_from_u8!(Notoriety {
+    Innocent,
+    Ally,
+    CanBeAttacked,
+    Criminal,
+    Enemy,
+    Murderer,
+    Invulnerable
+});
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize, WireSize)]
+#[serde(try_from = "u8")]
 #[repr(u8)]
 pub enum CharIdentity {
     HumanMale = 2,
@@ -138,10 +785,193 @@ pub enum CharIdentity {
     GargoyleFemale,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+impl_try_from_u8!(CharIdentity {
+    HumanMale,
+    HumanFemale,
+    ElfMale,
+    ElfFemale,
+    GargoyleMale,
+    GargoyleFemale
+});
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize)]
+#[serde(try_from = "u8")]
 #[repr(u8)]
 pub enum Race {
     Human = 1,
     Elf,
     Gargoyle,
 }
+
+impl_try_from_u8!(Race {
+    Human,
+    Elf,
+    Gargoyle
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enums_from_u8() {
+        assert_eq!(Direction::try_from(6).unwrap(), Direction::West);
+        assert_eq!(Notoriety::try_from(1).unwrap(), Notoriety::Innocent);
+        assert_eq!(
+            CharIdentity::try_from(7).unwrap(),
+            CharIdentity::GargoyleFemale
+        );
+        assert_eq!(Race::try_from(2).unwrap(), Race::Elf);
+
+        assert!(matches!(Direction::try_from(8), Err(Error::Data(_))));
+        assert!(matches!(Notoriety::try_from(0), Err(Error::Data(_))));
+        assert!(matches!(CharIdentity::try_from(1), Err(Error::Data(_))));
+        assert!(matches!(
+            Race::try_from(4),
+            Err(Error::Data(msg)) if msg == "4 is not a valid Race"
+        ));
+    }
+
+    #[test]
+    fn deserializes_enums_from_u8() {
+        use crate::de::from_slice;
+
+        assert_eq!(from_slice::<Race>(&[0x03]).unwrap(), Race::Gargoyle);
+
+        // The conversion's error is reported rather than serde's
+        let err = from_slice::<Race>(&[0x09]).unwrap_err();
+        assert!(err.to_string().contains("9 is not a valid Race"));
+    }
+
+    #[test]
+    fn fixed_str_from_checked() {
+        let city = FixedStr::<32>::from_checked("Skara Brae").unwrap();
+        assert_eq!(city, "Skara Brae".into());
+
+        // Room is left for the null
+        assert!(FixedStr::<32>::from_checked(&"a".repeat(31)).is_ok());
+        assert!(matches!(
+            FixedStr::<32>::from_checked(&"a".repeat(32)),
+            Err(Error::Data(_))
+        ));
+        assert!(matches!(
+            FixedStr::<32>::from_checked("The Most Extraordinarily Long City Name"),
+            Err(Error::Data(msg)) if msg.contains("longer than 31 bytes")
+        ));
+    }
+
+    #[test]
+    fn flags() {
+        use crate::de::from_slice;
+        use crate::ser::to_writer;
+
+        let mut flags = Flags32(0x01) | Flags32(0x04);
+        assert!(flags.contains(Flags32(0x04)));
+        assert!(!flags.contains(Flags32(0x06)));
+
+        flags &= !Flags32(0x01);
+        assert_eq!(flags, Flags32::ALL.difference(Flags32(!0x04)));
+        assert_eq!(Flags8::NONE.union(Flags8(0x80)).bits(), 0x80);
+
+        let mut bytes = Vec::new();
+        to_writer(&mut bytes, &Flags32(0x0000_11A8)).unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x11, 0xA8]);
+        assert_eq!(from_slice::<Flags32>(&bytes).unwrap(), Flags32(0x0000_11A8));
+        assert_eq!(from_slice::<Flags8>(&[0x5D]).unwrap(), Flags8(0x5D));
+    }
+
+    #[test]
+    fn direction_offsets() {
+        use Direction::*;
+
+        let offsets: Vec<_> = Direction::ALL.iter().map(|dir| dir.offset()).collect();
+        assert_eq!(
+            offsets,
+            [
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1)
+            ]
+        );
+
+        for dir in Direction::ALL.iter().copied() {
+            let (dx, dy) = dir.offset();
+            assert_eq!(dir.opposite().offset(), (-dx, -dy));
+            let to = ((100 + dx as i16) as u16, (100 + dy as i16) as u16);
+            assert_eq!(Direction::towards((100, 100), to), Some(dir));
+        }
+
+        assert_eq!(Direction::towards((100, 100), (110, 96)), Some(East));
+        assert_eq!(Direction::towards((100, 100), (110, 94)), Some(Right));
+        assert_eq!(Direction::towards((100, 100), (97, 80)), Some(North));
+        assert_eq!(Direction::towards((100, 100), (100, 100)), None);
+    }
+
+    #[test]
+    fn human_readable() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Strings {
+            fixed: FixedStr<8>,
+            unicode: UnicodeStr,
+            pascal: PascalStr,
+            list: List<u16, u8>,
+            little: LittleEndian<u16>,
+        }
+
+        let strings = Strings {
+            fixed: "name".into(),
+            unicode: "Hello, Britannia!".into(),
+            pascal: "pascal".into(),
+            list: vec![1, 2, 3].into(),
+            little: 0x1234.into(),
+        };
+
+        let json = serde_json::to_string(&strings).unwrap();
+        assert_eq!(
+            json,
+            r#"{"fixed":"name","unicode":"Hello, Britannia!","pascal":"pascal","list":[1,2,3],"little":4660}"#
+        );
+        assert_eq!(serde_json::from_str::<Strings>(&json).unwrap(), strings);
+
+        assert!(serde_json::from_str::<FixedStr<2>>(r#""name""#).is_err());
+    }
+
+    #[test]
+    fn name_display_and_equality() {
+        let name: Name = "Hamled".into();
+        assert_eq!(name.to_string(), "Hamled");
+
+        // Anything after the first null is padding
+        let mut bytes = [0u8; 30];
+        bytes[..6].copy_from_slice(b"Hamled");
+        bytes[7..10].copy_from_slice(b"xyz");
+        let padded = Name { str: bytes };
+        assert_eq!(padded, name);
+        assert_eq!(padded.to_string(), "Hamled");
+
+        assert_ne!(Name::from("Hamle"), name);
+        assert_ne!(Name::from("Hamled2"), name);
+        assert_eq!(Name::default().to_string(), "");
+    }
+
+    #[test]
+    fn direction_turns() {
+        use Direction::*;
+
+        assert_eq!(North.turn_left(), Up);
+        assert_eq!(Up.turn_right(), North);
+        assert_eq!(East.opposite(), West);
+        assert_eq!(Left.opposite(), Right);
+
+        for dir in Direction::ALL.iter().copied() {
+            assert_eq!(dir.turn_left().turn_right(), dir);
+            assert_eq!((0..8).fold(dir, |dir, _| dir.turn_right()), dir);
+            assert_eq!((0..4).fold(dir, |dir, _| dir.turn_left()), dir.opposite());
+        }
+    }
+}
@@ -32,6 +32,11 @@ impl<T: Serialize, L: ListLen + Serialize> Serialize for List<T, L> {
     where
         S: Serializer,
     {
+        // Only the elements, since the length is implied
+        if serializer.is_human_readable() {
+            return self.0.serialize(serializer);
+        }
+
         let mut struct_ser = serializer.serialize_struct("List", 2)?;
 
         struct_ser.serialize_field::<L>(
@@ -114,6 +119,10 @@ where
     where
         D: Deserializer<'de>,
     {
+        if deserializer.is_human_readable() {
+            return Vec::<T>::deserialize(deserializer).map(Into::into);
+        }
+
         const FIELDS: &'static [&'static str] = &["length", "elements"];
         deserializer.deserialize_struct(
             "List",
@@ -200,6 +209,11 @@ impl<T: Serialize, Term: ListTerminator + Serialize> Serialize for ListTerm<T, T
             Value(&'a T),
         }
 
+        // Only the elements, without the terminator
+        if serializer.is_human_readable() {
+            return self.0.serialize(serializer);
+        }
+
         let mut seq_ser = serializer.serialize_seq(Some(self.0.len()))?;
         for element in &self.0 {
             seq_ser.serialize_element(&Element::<T, Term>::Value(element))?;
@@ -274,6 +288,10 @@ where
     where
         D: Deserializer<'de>,
     {
+        if deserializer.is_human_readable() {
+            return Vec::<T>::deserialize(deserializer).map(Into::into);
+        }
+
         deserializer.deserialize_seq(ListTermVisitor {
             element_type: PhantomData,
             terminator_type: PhantomData,
@@ -369,3 +387,182 @@ impl<T: Serialize> From<ListNonTerm<T>> for Vec<T> {
         val.0
     }
 }
+
+impl<T: Serialize> AsRef<[T]> for ListNonTerm<T> {
+    fn as_ref(&self) -> &[T] {
+        self.0.as_slice()
+    }
+}
+
+// A list prefixed with its size in bytes rather than its number of
+// elements. When INCLUSIVE, the size also counts the prefix itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ByteLenList<T, L: ListLen, const INCLUSIVE: bool = false>(Vec<T>, PhantomData<L>);
+
+impl<T, L: ListLen, const INCLUSIVE: bool> ByteLenList<T, L, INCLUSIVE> {
+    const PREFIX_SIZE: u64 = (L::BITS / 8) as u64;
+}
+
+impl<T: Serialize, L: ListLen + Serialize, const INCLUSIVE: bool> Serialize
+    for ByteLenList<T, L, INCLUSIVE>
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            return self.0.serialize(serializer);
+        }
+
+        let mut size = crate::ser::to_size(&self.0).map_err(ser::Error::custom)? as u64;
+        if INCLUSIVE {
+            size += Self::PREFIX_SIZE;
+        }
+
+        let mut struct_ser = serializer.serialize_struct("ByteLenList", 2)?;
+
+        struct_ser.serialize_field::<L>(
+            "length",
+            &size.try_into().or(Err(ser::Error::custom(format!(
+                "List size cannot fit into {} bits",
+                L::BITS
+            ))))?,
+        )?;
+        struct_ser.serialize_field("elements", &self.0)?;
+
+        struct_ser.end()
+    }
+}
+
+impl<T, L: ListLen, const INCLUSIVE: bool> Default for ByteLenList<T, L, INCLUSIVE> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<T, L: ListLen, const INCLUSIVE: bool> From<Vec<T>> for ByteLenList<T, L, INCLUSIVE> {
+    fn from(val: Vec<T>) -> Self {
+        Self(val, PhantomData)
+    }
+}
+
+impl<T, L: ListLen, const INCLUSIVE: bool> From<ByteLenList<T, L, INCLUSIVE>> for Vec<T> {
+    fn from(val: ByteLenList<T, L, INCLUSIVE>) -> Self {
+        val.0
+    }
+}
+
+impl<T, L: ListLen, const INCLUSIVE: bool> AsRef<[T]> for ByteLenList<T, L, INCLUSIVE> {
+    fn as_ref(&self) -> &[T] {
+        self.0.as_slice()
+    }
+}
+
+impl<'de, T, L, const INCLUSIVE: bool> Deserialize<'de> for ByteLenList<T, L, INCLUSIVE>
+where
+    T: de::DeserializeOwned,
+    L: ListLen + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ByteLenListVisitor<T, L, const INCLUSIVE: bool>(PhantomData<(T, L)>);
+
+        impl<'de, T, L, const INCLUSIVE: bool> Visitor<'de> for ByteLenListVisitor<T, L, INCLUSIVE>
+        where
+            T: de::DeserializeOwned,
+            L: ListLen + Deserialize<'de>,
+        {
+            type Value = ByteLenList<T, L, INCLUSIVE>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_fmt(format_args!(
+                    "a list prefixed with its {}-bit size in bytes",
+                    L::BITS
+                ))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut size: u64 = seq
+                    .next_element::<L>()?
+                    .map(|size| size.into())
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                if INCLUSIVE {
+                    size = size
+                        .checked_sub(ByteLenList::<T, L, INCLUSIVE>::PREFIX_SIZE)
+                        .ok_or_else(|| de::Error::custom("list size is smaller than its prefix"))?;
+                }
+
+                let mut bytes = Vec::with_capacity(size as usize);
+                for _ in 0..size {
+                    bytes.push(
+                        seq.next_element::<u8>()?
+                            .ok_or_else(|| de::Error::invalid_length(1, &self))?,
+                    );
+                }
+
+                crate::de::from_slice::<Vec<T>>(&bytes)
+                    .map(Into::into)
+                    .map_err(de::Error::custom)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            return Vec::<T>::deserialize(deserializer).map(Into::into);
+        }
+
+        deserializer.deserialize_tuple(usize::MAX, ByteLenListVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{de::from_slice, ser::to_writer};
+
+    #[test]
+    fn byte_len_list_exclusive() {
+        let list = ByteLenList::<u16, u8>::from(vec![0x1234, 0x5678, 0x9ABC]);
+
+        let mut bytes = vec![];
+        to_writer(&mut bytes, &list).unwrap();
+        assert_eq!(bytes, [0x06, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC]);
+
+        assert_eq!(from_slice::<ByteLenList<u16, u8>>(&bytes).unwrap(), list);
+    }
+
+    #[test]
+    fn byte_len_list_inclusive() {
+        let list = ByteLenList::<u32, u16, true>::from(vec![0x12345678]);
+
+        let mut bytes = vec![];
+        to_writer(&mut bytes, &list).unwrap();
+        assert_eq!(bytes, [0x00, 0x06, 0x12, 0x34, 0x56, 0x78]);
+
+        assert_eq!(
+            from_slice::<ByteLenList<u32, u16, true>>(&bytes).unwrap(),
+            list
+        );
+
+        let empty = ByteLenList::<u32, u16, true>::default();
+        let mut bytes = vec![];
+        to_writer(&mut bytes, &empty).unwrap();
+        assert_eq!(bytes, [0x00, 0x02]);
+        assert_eq!(
+            from_slice::<ByteLenList<u32, u16, true>>(&bytes).unwrap(),
+            empty
+        );
+
+        assert!(from_slice::<ByteLenList<u32, u16, true>>(&[0x00, 0x01]).is_err());
+    }
+
+    #[test]
+    fn byte_len_list_partial_element() {
+        // A size of 3 bytes can't hold whole u16 elements
+        assert!(from_slice::<ByteLenList<u16, u8>>(&[0x03, 0x12, 0x34, 0x56]).is_err());
+    }
+}
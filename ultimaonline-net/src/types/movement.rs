@@ -1,8 +1,8 @@
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
-use super::Direction;
+use super::{Direction, WireSize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize_repr, Deserialize_repr, WireSize)]
 #[repr(u8)]
 pub enum MovementRaw {
     North = 0,
@@ -127,3 +127,91 @@ impl From<Movement> for Direction {
         val.dir
     }
 }
+
+// Tracks the sequence numbers of a client's movement requests. The client
+// numbers its requests from 0, wrapping from 255 back around to 1 since 0
+// is only used for the first request after a (re)start. When a request is
+// rejected the client starts again from 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MovementSequence {
+    expected: u8,
+}
+
+impl MovementSequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn expected(&self) -> u8 {
+        self.expected
+    }
+
+    // Checks the sequence number of a request, advancing to the next one if
+    // it was expected. Otherwise the request must be answered with a Reject
+    // and the sequence is reset to expect 0.
+    pub fn accept(&mut self, sequence: u8) -> bool {
+        if sequence != self.expected {
+            self.reset();
+            return false;
+        }
+
+        self.expected = match sequence {
+            u8::MAX => 1,
+            _ => sequence + 1,
+        };
+
+        true
+    }
+
+    // For when a request is rejected for another reason, such as the move
+    // being blocked
+    pub fn reset(&mut self) {
+        self.expected = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence_advances() {
+        let mut seq = MovementSequence::new();
+        assert_eq!(seq.expected(), 0);
+
+        for sequence in 0..10 {
+            assert!(seq.accept(sequence));
+        }
+        assert_eq!(seq.expected(), 10);
+    }
+
+    #[test]
+    fn sequence_wraps_past_zero() {
+        let mut seq = MovementSequence::new();
+        for sequence in 0..=255 {
+            assert!(seq.accept(sequence));
+        }
+
+        assert_eq!(seq.expected(), 1);
+        let mut restarted = seq;
+        assert!(!restarted.accept(0));
+        assert!(seq.accept(1));
+        assert!(seq.accept(2));
+    }
+
+    #[test]
+    fn sequence_resets_after_reject() {
+        let mut seq = MovementSequence::new();
+        assert!(seq.accept(0));
+        assert!(seq.accept(1));
+
+        assert!(!seq.accept(5));
+        assert_eq!(seq.expected(), 0);
+        assert!(!seq.accept(2));
+        assert!(seq.accept(0));
+        assert!(seq.accept(1));
+
+        seq.reset();
+        assert!(seq.accept(0));
+    }
+}
@@ -0,0 +1,8 @@
+// Fixed packets check their declared size when compiled, so the mismatched
+// case can only be shown by a build which fails
+#[test]
+fn fixed_packet_size() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/fixed_size_matches.rs");
+    cases.compile_fail("tests/ui/fixed_size_mismatch.rs");
+}
@@ -0,0 +1,39 @@
+// The packet macro refers to the library by crate paths, which are
+// re-exported here as it would see them. Packet can only be built from
+// within the library, so it is stood in for.
+mod de {
+    pub use ultimaonline_net::de::*;
+}
+mod error {
+    pub use ultimaonline_net::error::*;
+}
+mod packets {
+    pub use ultimaonline_net::packets::{FromPacketData, IntoPacket};
+
+    pub struct Packet<T> {
+        pub id: u8,
+        pub var_size: bool,
+        pub contents: T,
+    }
+}
+mod types {
+    pub use ultimaonline_net::types::*;
+}
+
+use serde::{Deserialize, Serialize};
+use types::WireSize;
+use ultimaonline_net_macros::packet;
+
+// Padded to 8 bytes in memory, but only 5 are sent
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, WireSize)]
+pub struct Padded {
+    flag: u8,
+    value: u32,
+}
+
+#[packet(fixed(id = 0x01, size = 8))]
+pub struct PaddedPacket {
+    padded: Padded,
+}
+
+fn main() {}
@@ -9,6 +9,7 @@ use syn::{
 };
 
 mod kw {
+    syn::custom_keyword!(include);
     syn::custom_keyword!(send);
     syn::custom_keyword!(recv);
 }
@@ -16,57 +17,239 @@ mod kw {
 struct CodecDef {
     visibility: Visibility,
     name: Ident,
+    includes: Vec<Ident>,
     send_pkts: Vec<Path>,
     recv_pkts: Vec<Path>,
 }
 
+fn parse_pkts(input: ParseStream) -> Result<Vec<Path>> {
+    let contents;
+    bracketed!(contents in input);
+    let paths: Punctuated<Path, Token![,]> = contents.parse_terminated(Path::parse)?;
+    let pkts = paths.into_iter().collect::<Vec<_>>();
+    check_unique(&pkts)?;
+    Ok(pkts)
+}
+
+// Adds the packets from an included codec, skipping the ones which are
+// already listed. Packets with the same name from different modules are
+// still caught by check_unique.
+fn merge_pkts(pkts: &mut Vec<Path>, included: Vec<Path>) -> Result<()> {
+    for pkt in included {
+        if !pkts
+            .iter()
+            .any(|prev| quote!(#prev).to_string() == quote!(#pkt).to_string())
+        {
+            pkts.push(pkt);
+        }
+    }
+
+    check_unique(pkts)
+}
+
 impl Parse for CodecDef {
     fn parse(input: ParseStream) -> Result<Self> {
         let visibility: Visibility = input.parse()?;
         let name: Ident = input.parse()?;
         input.parse::<Token![,]>()?;
 
-        input.parse::<kw::send>()?;
-        let send_pkts = {
+        let includes = if input.peek(kw::include) {
+            input.parse::<kw::include>()?;
             let contents;
             bracketed!(contents in input);
-            let paths: Punctuated<Path, Token![,]> = contents.parse_terminated(Path::parse)?;
-            let mut pkts: Vec<Path> = Vec::new();
-            for path in paths {
-                pkts.push(path.to_owned());
-            }
-            pkts
+            let names: Punctuated<Ident, Token![,]> = contents.parse_terminated(Ident::parse)?;
+            input.parse::<Token![,]>()?;
+            names.into_iter().collect()
+        } else {
+            vec![]
         };
+
+        input.parse::<kw::send>()?;
+        let mut send_pkts = parse_pkts(input)?;
         input.parse::<Token![,]>()?;
 
         input.parse::<kw::recv>()?;
-        let recv_pkts = {
-            let contents;
-            bracketed!(contents in input);
-            let paths: Punctuated<Path, Token![,]> = contents.parse_terminated(Path::parse)?;
-            let mut pkts: Vec<Path> = Vec::new();
-            for path in paths {
-                pkts.push(path.to_owned());
-            }
-            pkts
-        };
+        let mut recv_pkts = parse_pkts(input)?;
+
+        // The lists of each included codec follow, once it has been expanded
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            input.parse::<kw::send>()?;
+            let included = parse_pkts(input)?;
+            merge_pkts(&mut send_pkts, included)?;
+            input.parse::<Token![,]>()?;
+
+            input.parse::<kw::recv>()?;
+            let included = parse_pkts(input)?;
+            merge_pkts(&mut recv_pkts, included)?;
+        }
 
         Ok(CodecDef {
             visibility,
             name,
+            includes,
             send_pkts,
             recv_pkts,
         })
     }
 }
 
+fn packets_macro_name(codec_name: &Ident) -> Ident {
+    Ident::new(&format!("__{}_packets", codec_name), codec_name.span())
+}
+
+// Each direction gets its own frame enum with a variant named after each
+// packet, so a packet may be listed in both send and recv (e.g. symmetric
+// packets like WarMode). Within one direction though, a packet listed twice
+// would produce conflicting trait impls, and two packets with the same name
+// from different modules would produce conflicting variants. Packets with
+// the same id are caught by check_unique_ids instead.
+fn check_unique(pkts: &[Path]) -> Result<()> {
+    for (i, pkt) in pkts.iter().enumerate() {
+        let name = &pkt.segments.last().unwrap().ident;
+        if let Some(prev) = pkts[..i]
+            .iter()
+            .find(|prev| &prev.segments.last().unwrap().ident == name)
+        {
+            let msg = if quote!(#prev).to_string() == quote!(#pkt).to_string() {
+                format!("packet {} is listed more than once", name)
+            } else {
+                format!("packet name {} is used by more than one packet", name)
+            };
+            return Err(syn::Error::new_spanned(pkt, msg));
+        }
+    }
+
+    Ok(())
+}
+
+// Packet ids aren't known until the packet types are, so two packets with
+// the same id in one direction can only be caught once the codec is compiled.
+// The decoder could never tell them apart, so the later one would never be
+// received.
+fn check_unique_ids(
+    codec_name: &Ident,
+    direction: &str,
+    pkts: &[Path],
+) -> proc_macro2::TokenStream {
+    if pkts.is_empty() {
+        return quote! {};
+    }
+
+    let checks = pkts.iter().enumerate().map(|(index, pkt)| {
+        let msg = format!(
+            "packet {} has the same id as another packet {} by {}",
+            quote!(#pkt).to_string().replace(' ', ""),
+            direction,
+            codec_name
+        );
+        quote! { assert!(DUPLICATE != #index, #msg); }
+    });
+
+    quote! {
+        const _: () = {
+            const IDS: &[(u8, Option<u16>)] = &[ #( (#pkts::PACKET_ID, #pkts::EXTENDED_ID) ),* ];
+
+            // Index of the first packet whose id was already used
+            const DUPLICATE: usize = {
+                let mut duplicate = IDS.len();
+                let mut i = 0;
+                while i < IDS.len() && duplicate == IDS.len() {
+                    let mut j = 0;
+                    while j < i {
+                        let same_extended = match (IDS[i].1, IDS[j].1) {
+                            (Some(a), Some(b)) => a == b,
+                            (None, None) => true,
+                            _ => false,
+                        };
+                        if IDS[i].0 == IDS[j].0 && same_extended {
+                            duplicate = i;
+                        }
+                        j += 1;
+                    }
+                    i += 1;
+                }
+                duplicate
+            };
+
+            #( #checks )*
+        };
+    }
+}
+
+// Lets frames be counted or logged by the type of packet they contain
+fn frame_methods(frame_name: &Ident, pkts: &[Path]) -> proc_macro2::TokenStream {
+    let variants = pkts
+        .iter()
+        .map(|p| &p.segments.last().unwrap().ident)
+        .collect::<Vec<_>>();
+    let names = pkts.iter().map(|p| {
+        p.segments
+            .iter()
+            .map(|seg| seg.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::")
+    });
+
+    quote! {
+        impl #frame_name {
+            pub fn packet_id(&self) -> (u8, Option<u16>) {
+                match *self {
+                    #( Self::#variants(_) => (#pkts::PACKET_ID, #pkts::EXTENDED_ID) ),*
+                }
+            }
+
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    #( Self::#variants(_) => #names ),*
+                }
+            }
+        }
+    }
+}
+
 #[proc_macro]
 pub fn define_codec(item: TokenStream) -> TokenStream {
     let codec_def = parse_macro_input!(item as CodecDef);
 
     let vis = codec_def.visibility;
     let codec_name = codec_def.name;
+    let send_pkts = &codec_def.send_pkts;
+    let recv_pkts = &codec_def.recv_pkts;
+
+    // A proc macro can't see what another codec lists, so each codec also
+    // defines a macro which passes its lists on to the codecs that include
+    // it. Those are expanded one at a time, until every list is merged in.
+    if let Some((include, rest)) = codec_def.includes.split_first() {
+        let packets_macro = packets_macro_name(include);
+        let output = quote! {
+            #packets_macro! {
+                #vis #codec_name,
+                include [ #(#rest),* ],
+                send [ #(#send_pkts),* ],
+                recv [ #(#recv_pkts),* ]
+            }
+        };
+
+        return output.into();
+    }
+
+    let packets_macro = {
+        let name = packets_macro_name(&codec_name);
+        quote! {
+            #[allow(unused_macros)]
+            macro_rules! #name {
+                ($($codec:tt)*) => {
+                    define_codec! {
+                        $($codec)*,
+                        send [ #(#send_pkts),* ],
+                        recv [ #(#recv_pkts),* ]
+                    }
+                };
+            }
+        }
+    };
     let frame_name = Ident::new(&format!("{}FrameRecv", codec_name), codec_name.span());
+    let codec_str = codec_name.to_string();
 
     let decoder = {
         let frame = {
@@ -75,10 +258,14 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
                 .recv_pkts
                 .iter()
                 .map(|p| &p.segments.last().unwrap().ident);
+            let methods = frame_methods(&frame_name, &codec_def.recv_pkts);
             quote! {
+                #[derive(Debug)]
                 #vis enum #frame_name {
                     #( #variants(#pkts) ),*
                 }
+
+                #methods
             }
         };
 
@@ -91,14 +278,28 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
             quote! {
                #( (#pkts::PACKET_ID, #pkts::EXTENDED_ID) => {
                    let ready = match #pkts::SIZE {
-                       Some(size) if size <= src.remaining() => true,
-                       None => match chunk.len() {
-                           3.. => (unsafe {
-                                    u16::from_be_bytes(chunk[1..3].try_into().unwrap_unchecked())
-                                }) as usize <= src.remaining(),
+                       // Fixed sizes don't include the packet id
+                       Some(size) => size < src.remaining(),
+                       // Var and extended packets declare their whole size,
+                       // including the id, size and any extended id
+                       None => match src.len() {
+                           3.. => {
+                               let size = u16::from_be_bytes([src[1], src[2]]) as usize;
+                               let header_size = match #pkts::EXTENDED_ID {
+                                   Some(_) => 5,
+                                   None => 3,
+                               };
+                               if size < header_size {
+                                   return Err(Self::Error::data(format!(
+                                       "packet size {} is smaller than its header",
+                                       size
+                                   )));
+                               }
+
+                               size <= src.remaining()
+                           }
                            _ => false,
                        },
-                       _ => false,
                    };
 
                    Ok(if ready {
@@ -121,22 +322,22 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
                 type Error = ::ultimaonline_net::error::Error;
 
                 fn decode(&mut self, src: &mut ::bytes::BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-                    use ::std::convert::TryInto;
                     use ::bytes::Buf;
                     use ::ultimaonline_net::packets::{self, FromPacketData};
                     use #frame_name::*;
 
-                    // Peek at the first byte
+                    // Peek at the first byte. The buffer is indexed directly
+                    // rather than through chunk(), which may not cover all of
+                    // the bytes that have been read so far.
                     if !src.has_remaining() { return Ok(None); }
 
-                    let chunk = src.chunk();
-                    let packet_id = chunk[0];
+                    let packet_id = src[0];
 
                     // Peek for extended packet id
                     let extended_id = match packet_id {
                         packets::EXTENDED_PACKET_ID => {
-                            if chunk.len() < 5 { return Ok(None); }
-                            Some(u16::from_be_bytes(chunk[3..5].try_into().unwrap()))
+                            if src.len() < 5 { return Ok(None); }
+                            Some(u16::from_be_bytes([src[3], src[4]]))
                         },
                         _ => None
                     };
@@ -144,13 +345,10 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
                     // match that to the appropriate packet, or error if none matches
                     match (packet_id, extended_id) {
                         #id_match_arms
-                        _ => Err(Self::Error::data(format!(
-                            "packet id {} is unhandled",
-                            match extended_id {
-                                Some(ei) => format!("{:#0X}({:#0X})", packet_id, ei),
-                                _ => format!("{:#0X}", packet_id)
-                            }
-                        ))),
+                        got => Err(Self::Error::UnexpectedPacket {
+                            expected: #codec_str,
+                            got,
+                        }),
                     }
                 }
             }
@@ -188,9 +386,11 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
                 )*
             };
 
+            let methods = frame_methods(&frame_name, &codec_def.send_pkts);
             quote! {
                 #enum_def
                 #impls
+                #methods
             }
         };
 
@@ -200,7 +400,7 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
                 .iter()
                 .map(|p| &p.segments.last().unwrap().ident);
             quote! {
-               #( #names(content) => ::ultimaonline_net::packets::write_packet(content, &mut dst.writer()) ),*,
+               #( #names(content) => ::tokio_util::codec::Encoder::encode(self, content, dst) ),*,
             }
         } else {
             quote! {}
@@ -226,7 +426,13 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
                 fn encode(&mut self, pkt: P, dst: &mut ::bytes::BytesMut) -> Result<(), Self::Error> {
                     use ::bytes::BufMut;
 
-                    ::ultimaonline_net::packets::write_packet(pkt, &mut dst.writer())
+                    let start = dst.len();
+                    ::ultimaonline_net::packets::write_packet(pkt, &mut dst.writer())?;
+
+                    #[cfg(debug_assertions)]
+                    ::ultimaonline_net::packets::check_written_size::<P>(&dst[start..]);
+
+                    Ok(())
                 }
             }
 
@@ -236,7 +442,6 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
                 type Error = ::ultimaonline_net::error::Error;
 
                 fn encode(&mut self, pkt: &'a #frame_name, dst: &mut ::bytes::BytesMut) -> Result<(), Self::Error> {
-                    use ::bytes::BufMut;
                     use #frame_name::*;
 
                     match pkt {
@@ -249,10 +454,16 @@ pub fn define_codec(item: TokenStream) -> TokenStream {
         }
     };
 
+    let send_ids = check_unique_ids(&codec_name, "sent", &codec_def.send_pkts);
+    let recv_ids = check_unique_ids(&codec_name, "received", &codec_def.recv_pkts);
+
     let output = quote! {
         #vis struct #codec_name;
         #decoder
         #encoder
+        #send_ids
+        #recv_ids
+        #packets_macro
     };
 
     output.into()
@@ -1,45 +1,64 @@
+use clap::Parser;
 use eyre::{eyre, Context, Result};
-use std::{
-    convert::TryInto,
-    env,
-    net::{Ipv4Addr, SocketAddrV4},
-    sync::Arc,
-};
+use std::{convert::TryInto, net::Ipv4Addr, sync::Arc, time::Duration};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
-    sync::Notify,
+    sync::{mpsc, Notify},
+    time,
 };
 use tokio::{net::TcpListener, task::JoinHandle};
-use tracing::{debug, debug_span, error, info, info_span};
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
-use ultimaonline_net::types::Serial;
+use tracing::{debug, debug_span, error, info, info_span, warn};
+use tracing_subscriber::{fmt, prelude::*};
+use ultimaonline_net::{
+    packets::{action, char_select, chat, login},
+    types::{FixedStr, Serial},
+};
+use uoverse_server::config::ConfigArgs;
+use uoverse_server::game::capabilities::Capabilities;
 use uoverse_server::game::client::{self, *};
 use uoverse_server::game::server;
+use uoverse_server::tickets::TicketRegistry;
+
+/// Game server, which clients connect to after logging in
+#[derive(Parser)]
+struct Args {
+    /// Address to listen on
+    listen_addr: Option<Ipv4Addr>,
+
+    /// Port to listen on
+    listen_port: Option<u16>,
+
+    /// Length of a server tick in milliseconds
+    #[arg(value_parser = clap::value_parser!(u64).range(1..))]
+    tick_ms: Option<u64>,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
 
-const DEFAULT_LISTEN_ADDR: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
-const DEFAULT_LISTEN_PORT: u16 = 2594;
+// How long clients have to finish sending their packets when shutting down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[tokio::main]
 pub async fn main() -> Result<()> {
-    let mut listen_addr = DEFAULT_LISTEN_ADDR;
-    let mut listen_port = DEFAULT_LISTEN_PORT;
-
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        listen_addr = args[1]
-            .parse()
-            .expect(format!("Invalid listen address: {}", &args[1]).as_str());
+    let args = Args::parse();
+    let mut config = args.config.load()?;
+
+    if let Some(addr) = args.listen_addr {
+        config.game.listen.set_ip(addr);
+    }
+    if let Some(port) = args.listen_port {
+        config.game.listen.set_port(port);
     }
-    if args.len() > 2 {
-        listen_port = u16::from_str_radix(&args[2], 10)
-            .expect(format!("Invalid listen port: {}", &args[2]).as_str());
+    if let Some(tick_ms) = args.tick_ms {
+        config.game.tick_ms = tick_ms;
     }
 
-    let listen_socket = SocketAddrV4::new(listen_addr, listen_port);
+    let listen_socket = config.game.listen;
 
     tracing_subscriber::registry()
         .with(fmt::layer())
-        .with(EnvFilter::from_default_env())
+        .with(config.env_filter())
         .init();
 
     let span = info_span!("server");
@@ -48,7 +67,19 @@ pub async fn main() -> Result<()> {
     let listener = TcpListener::bind(listen_socket).await.unwrap();
     info!(socket = %listen_socket, "Game server listening on {}", listen_socket);
 
-    let server = Arc::new(server::Server::new());
+    let server = Arc::new(server::Server::new(config.game.server_config()?));
+
+    // Without tickets shared with the login server, seeds can't be checked
+    let tickets = config
+        .tickets
+        .as_ref()
+        .map(|path| Arc::new(TicketRegistry::open(path)));
+    if tickets.is_none() {
+        info!("No ticket file is configured, so login seeds will not be checked");
+    }
+    if !config.game.compression {
+        warn!("Compression is turned off, so real clients will not be able to play");
+    }
     let shutdown_notice = Arc::new(Notify::new());
     {
         let server = server.clone();
@@ -65,15 +96,23 @@ pub async fn main() -> Result<()> {
         tokio::spawn(async move { Ok(server.run_loop().await?) })
     };
 
+    // Every client task holds a sender, so once they have all finished the
+    // receiver is closed
+    let (clients_send, mut clients_done) = mpsc::channel::<()>(1);
+
     loop {
         tokio::select! {
             Ok((mut socket, _)) = listener.accept() => {
                 let server = server.clone();
+                let tickets = tickets.clone();
+                let compression = config.game.compression;
+                let client_done = clients_send.clone();
                 tokio::spawn(async move {
-                    match process(&mut socket, server).await {
+                    match process(&mut socket, server, tickets.as_deref(), compression).await {
                         Err(err) => error!("{:#}", err),
                         Ok(()) => {}
                     }
+                    drop(client_done);
                 });
             }
 
@@ -89,17 +128,33 @@ pub async fn main() -> Result<()> {
         .expect("Error joining server task")
         .wrap_err("Server error")?;
 
+    drop(clients_send);
+    if time::timeout(SHUTDOWN_TIMEOUT, clients_done.recv())
+        .await
+        .is_err()
+    {
+        warn!("Timed out waiting for clients to disconnect.");
+    }
+
     info!("Shutdown complete.");
     Ok(())
 }
 
-async fn process<Io: AsyncIo>(mut socket: Io, server: Arc<server::Server>) -> Result<()> {
+async fn process<Io: AsyncIo>(
+    mut socket: Io,
+    server: Arc<server::Server>,
+    tickets: Option<&TicketRegistry>,
+    compression: bool,
+) -> Result<()> {
     let span = debug_span!("client");
     let _ = span.enter();
 
     let preworld_span = debug_span!(parent: &span, "preworld");
     let span_guard = preworld_span.enter();
-    let state = preworld(&mut socket)
+    // Allocated up front, since the client is told it when logging in
+    let serial = server.new_player_serial();
+
+    let (state, caps) = preworld(&mut socket, tickets, compression, serial)
         .await
         .wrap_err("Client did not complete pre-world")?;
 
@@ -108,27 +163,41 @@ async fn process<Io: AsyncIo>(mut socket: Io, server: Arc<server::Server>) -> Re
 
     let inworld_span = debug_span!(parent: &span, "in-world");
     let span_guard = inworld_span.enter();
-    in_world(server, state)
+    in_world(server, state, caps, serial)
         .await
         .wrap_err("Client had error during in-world")?;
     drop(span_guard);
 
     debug!("Client disconnected.");
-    socket.shutdown().await?;
+    match socket.shutdown().await {
+        Err(err) if is_disconnect(&err) => {}
+        res => res?,
+    }
 
     Ok(())
 }
 
-async fn preworld<Io: AsyncIo>(socket: Io) -> Result<InWorld<Io>> {
-    let state = handshake(socket).await?;
-    let state = char_login(state).await?;
-
-    Ok(state)
+async fn preworld<Io: AsyncIo>(
+    socket: Io,
+    tickets: Option<&TicketRegistry>,
+    compression: bool,
+    serial: Serial,
+) -> Result<(InWorld<Io>, Capabilities)> {
+    let (state, version) = handshake(socket, tickets, compression).await?;
+    let caps = Capabilities::for_version(&version);
+    debug!(version = %version, ?caps, "Client version is {}", version);
+    let state = char_login(state, &caps, serial).await?;
+
+    Ok((state, caps))
 }
 
-const PLAYER_SERIAL: Serial = 3833;
+const PLAYER_NAME: &str = "Hamled";
 
-async fn handshake<Io: AsyncIo>(mut socket: Io) -> Result<CharSelect<Io>> {
+async fn handshake<Io: AsyncIo>(
+    mut socket: Io,
+    tickets: Option<&TicketRegistry>,
+    compression: bool,
+) -> Result<(CharSelect<Io>, login::ClientVersion)> {
     use ultimaonline_net::packets::char_select as packets;
 
     // Client sends a 4 byte seed value, followed by the initial login packet.
@@ -137,9 +206,12 @@ async fn handshake<Io: AsyncIo>(mut socket: Io) -> Result<CharSelect<Io>> {
     let _ = socket.read_u32().await;
 
     let mut state = Connected::new(socket);
+    if !compression {
+        state = state.without_compression();
+    }
     let login = match state.recv().await? {
         Some(codecs::ConnectedFrameRecv::GameLogin(login)) => login,
-        _ => return Err(eyre!("Did not get GameLogin packet")),
+        None => return Err(eyre!("Client disconnected before sending GameLogin packet")),
     };
 
     let username = TryInto::<&str>::try_into(&login.username).expect("Invalid UTF-8 in username");
@@ -150,6 +222,13 @@ async fn handshake<Io: AsyncIo>(mut socket: Io) -> Result<CharSelect<Io>> {
         username, password, login.seed
     );
 
+    // The seed is the ticket which the login server handed the client off with
+    if let Some(tickets) = tickets {
+        tickets
+            .redeem(username, login.seed)
+            .wrap_err("Client did not log in with its ticket")?;
+    }
+
     let mut state = CharList::<Io>::from(state);
     state
         .send(&packets::Features {
@@ -160,126 +239,7 @@ async fn handshake<Io: AsyncIo>(mut socket: Io) -> Result<CharSelect<Io>> {
     state
         .send(&packets::CharList {
             chars: vec![Default::default(); 7].into(),
-            cities: vec![
-                packets::CityInfo {
-                    index: 0,
-                    city: "Name Haven".into(),
-                    building: "New Haven Bank".into(),
-                    location: packets::MapLocation {
-                        x: 3667,
-                        y: 2625,
-                        z: 0,
-                        id: 1,
-                    },
-                    description: 1150168,
-                    unknown_15: 0,
-                },
-                packets::CityInfo {
-                    index: 1,
-                    city: "Yew".into(),
-                    building: "The Empath Abbey".into(),
-                    location: packets::MapLocation {
-                        x: 633,
-                        y: 858,
-                        z: 0,
-                        id: 1,
-                    },
-                    description: 1075072,
-                    unknown_15: 0,
-                },
-                packets::CityInfo {
-                    index: 2,
-                    city: "Minoc".into(),
-                    building: "The Barnacle".into(),
-                    location: packets::MapLocation {
-                        x: 2476,
-                        y: 413,
-                        z: 15,
-                        id: 1,
-                    },
-                    description: 1075073,
-                    unknown_15: 0,
-                },
-                packets::CityInfo {
-                    index: 3,
-                    city: "Britain".into(),
-                    building: "The Wayfarer's Inn".into(),
-                    location: packets::MapLocation {
-                        x: 1602,
-                        y: 1591,
-                        z: 20,
-                        id: 1,
-                    },
-                    description: 1075074,
-                    unknown_15: 0,
-                },
-                packets::CityInfo {
-                    index: 4,
-                    city: "Moonglow".into(),
-                    building: "The Scholar's Inn".into(),
-                    location: packets::MapLocation {
-                        x: 4408,
-                        y: 1168,
-                        z: 0,
-                        id: 1,
-                    },
-                    description: 1075075,
-                    unknown_15: 0,
-                },
-                packets::CityInfo {
-                    index: 5,
-                    city: "Trinsic".into(),
-                    building: "The Traveler's Inn".into(),
-                    location: packets::MapLocation {
-                        x: 1845,
-                        y: 2745,
-                        z: 0,
-                        id: 1,
-                    },
-                    description: 1075076,
-                    unknown_15: 0,
-                },
-                packets::CityInfo {
-                    index: 6,
-                    city: "Jhelom".into(),
-                    building: "The Mercenary Inn".into(),
-                    location: packets::MapLocation {
-                        x: 1374,
-                        y: 3826,
-                        z: 0,
-                        id: 1,
-                    },
-                    description: 1075078,
-                    unknown_15: 0,
-                },
-                packets::CityInfo {
-                    index: 7,
-                    city: "Skara Brae".into(),
-                    building: "The Falconer's Inn".into(),
-                    location: packets::MapLocation {
-                        x: 618,
-                        y: 2234,
-                        z: 0,
-                        id: 1,
-                    },
-                    description: 1075079,
-                    unknown_15: 0,
-                },
-                packets::CityInfo {
-                    index: 8,
-                    city: "Vesper".into(),
-                    building: "The Ironwood Inn".into(),
-                    location: packets::MapLocation {
-                        x: 2771,
-                        y: 976,
-                        z: 0,
-                        id: 1,
-                    },
-                    description: 1075080,
-                    unknown_15: 0,
-                },
-            ]
-            .into(),
+            cities: starting_cities()?.into(),
             flags: client::FLAGS,
             unknown_var1: -1,
         })
@@ -294,118 +254,231 @@ async fn handshake<Io: AsyncIo>(mut socket: Io) -> Result<CharSelect<Io>> {
         Some(codecs::ClientVersionFrameRecv::VersionResp(packets::VersionResp { version })) => {
             version
         }
-        _ => return Err(eyre!("Did not get VersionResp packet")),
+        None => {
+            return Err(eyre!(
+                "Client disconnected before sending VersionResp packet"
+            ))
+        }
     };
 
     debug!(version = %version, "Got client version: {}", version);
+    let version: login::ClientVersion =
+        version.parse().wrap_err("Client sent an invalid version")?;
 
-    Ok(CharSelect::<Io>::from(state))
+    Ok((CharSelect::<Io>::from(state), version))
 }
 
-async fn char_login<Io: AsyncIo>(mut state: CharSelect<Io>) -> Result<InWorld<Io>> {
+// Names which don't fit are an error, rather than being cut short
+fn starting_cities() -> Result<Vec<char_select::CityInfo>> {
+    Ok(vec![
+        char_select::CityInfo {
+            index: 0,
+            city: FixedStr::from_checked("Name Haven")?,
+            building: FixedStr::from_checked("New Haven Bank")?,
+            location: char_select::MapLocation {
+                x: 3667,
+                y: 2625,
+                z: 0,
+                id: 1,
+            },
+            description: 1150168,
+            unknown_15: 0,
+        },
+        char_select::CityInfo {
+            index: 1,
+            city: FixedStr::from_checked("Yew")?,
+            building: FixedStr::from_checked("The Empath Abbey")?,
+            location: char_select::MapLocation {
+                x: 633,
+                y: 858,
+                z: 0,
+                id: 1,
+            },
+            description: 1075072,
+            unknown_15: 0,
+        },
+        char_select::CityInfo {
+            index: 2,
+            city: FixedStr::from_checked("Minoc")?,
+            building: FixedStr::from_checked("The Barnacle")?,
+            location: char_select::MapLocation {
+                x: 2476,
+                y: 413,
+                z: 15,
+                id: 1,
+            },
+            description: 1075073,
+            unknown_15: 0,
+        },
+        char_select::CityInfo {
+            index: 3,
+            city: FixedStr::from_checked("Britain")?,
+            building: FixedStr::from_checked("The Wayfarer's Inn")?,
+            location: char_select::MapLocation {
+                x: 1602,
+                y: 1591,
+                z: 20,
+                id: 1,
+            },
+            description: 1075074,
+            unknown_15: 0,
+        },
+        char_select::CityInfo {
+            index: 4,
+            city: FixedStr::from_checked("Moonglow")?,
+            building: FixedStr::from_checked("The Scholar's Inn")?,
+            location: char_select::MapLocation {
+                x: 4408,
+                y: 1168,
+                z: 0,
+                id: 1,
+            },
+            description: 1075075,
+            unknown_15: 0,
+        },
+        char_select::CityInfo {
+            index: 5,
+            city: FixedStr::from_checked("Trinsic")?,
+            building: FixedStr::from_checked("The Traveler's Inn")?,
+            location: char_select::MapLocation {
+                x: 1845,
+                y: 2745,
+                z: 0,
+                id: 1,
+            },
+            description: 1075076,
+            unknown_15: 0,
+        },
+        char_select::CityInfo {
+            index: 6,
+            city: FixedStr::from_checked("Jhelom")?,
+            building: FixedStr::from_checked("The Mercenary Inn")?,
+            location: char_select::MapLocation {
+                x: 1374,
+                y: 3826,
+                z: 0,
+                id: 1,
+            },
+            description: 1075078,
+            unknown_15: 0,
+        },
+        char_select::CityInfo {
+            index: 7,
+            city: FixedStr::from_checked("Skara Brae")?,
+            building: FixedStr::from_checked("The Falconer's Inn")?,
+            location: char_select::MapLocation {
+                x: 618,
+                y: 2234,
+                z: 0,
+                id: 1,
+            },
+            description: 1075079,
+            unknown_15: 0,
+        },
+        char_select::CityInfo {
+            index: 8,
+            city: FixedStr::from_checked("Vesper")?,
+            building: FixedStr::from_checked("The Ironwood Inn")?,
+            location: char_select::MapLocation {
+                x: 2771,
+                y: 976,
+                z: 0,
+                id: 1,
+            },
+            description: 1075080,
+            unknown_15: 0,
+        },
+    ])
+}
+
+async fn char_login<Io: AsyncIo>(
+    mut state: CharSelect<Io>,
+    caps: &Capabilities,
+    serial: Serial,
+) -> Result<InWorld<Io>> {
     use ultimaonline_net::{packets::*, types};
     let create_info = match state.recv().await? {
         Some(codecs::CharSelectFrameRecv::CreateCharacter(info)) => info,
-        _ => return Err(eyre!("Did not get CreateCharacter packet")),
+        None => {
+            return Err(eyre!(
+                "Client disconnected before sending CreateCharacter packet"
+            ))
+        }
     };
 
-    let name: &str = (&create_info.name).try_into()?;
+    let name = &create_info.name;
     debug!(
         char_name = %name,
         "Create character named: {}", name
     );
 
-    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-
     debug!(char_name = %name, "Sending character into world");
 
     let mut state = CharLogin::<Io>::from(state);
 
-    // Set the map first
-    state
-        .send(&map::MapChange {
-            map_id: 0x0, // Britannia
-        })
-        .await?;
-
+    // Set the map first. The packets are flushed to the client all at once.
     state
-        .send(&char_login::LoginConfirmation {
-            serial: PLAYER_SERIAL,
-            unknown_04: 0,
-            body: 401, // Human male?
-            x: 3667,
-            y: 2625,
-            z: 0,
-            direction: types::Direction::South,
-            unknown_10: 0,
-            unknown_11: 0xFFFFFFFF,
-            unknown_15: [0u8; 14],
-        })
-        .await?;
-
-    // Character status
-    state
-        .send(&char_login::CharStatus {
-            serial: PLAYER_SERIAL,
-            name: "Hamled".into(),
-            hitpoints: char_login::Attribute {
-                current: 100,
-                maximum: 100,
-            },
-            renamable: false,
-            version: 6,    // Latest version for character status
-            gender: false, // Male
-            strength: 20,
-            dexterity: 20,
-            intelligence: 20,
-            stamina: char_login::Attribute {
-                current: 100,
-                maximum: 100,
-            },
-            mana: char_login::Attribute {
-                current: 100,
-                maximum: 100,
-            },
-            gold: 0,
-            phys_resist: 50,
-            weight: char_login::Attribute {
-                current: 0,
-                maximum: 100,
-            },
-            race: types::Race::Human,
-            stat_cap: 300,
-            follower_count: 0,
-            follower_max: 0,
-            fire_resist: 50,
-            cold_resist: 50,
-            poison_resist: 50,
-            energy_resist: 50,
-            luck: 20,
-            damage_min: 0,
-            damage_max: 0,
-            tithing_points: 0,
-            aos_stats: [Default::default(); 15],
-        })
+        .send_all(vec![
+            map::MapChange {
+                map_id: 0x0, // Britannia
+            }
+            .into(),
+            char_login::LoginConfirmation {
+                serial,
+                unknown_04: 0,
+                body: 401, // Human male?
+                x: 3667,
+                y: 2625,
+                z: 0,
+                direction: types::Direction::South,
+                unknown_10: 0,
+                unknown_11: 0xFFFFFFFF,
+                unknown_15: [0u8; 14],
+            }
+            .into(),
+            // Character status
+            server::player_status(serial, PLAYER_NAME, caps.status_version).into(),
+            char_login::LoginComplete {}.into(),
+        ])
         .await?;
 
-    state.send(&char_login::LoginComplete {}).await?;
-
     Ok(InWorld::<Io>::from(state))
 }
 
-async fn in_world<Io: AsyncIo>(server: Arc<server::Server>, mut state: InWorld<Io>) -> Result<()> {
+async fn in_world<Io: AsyncIo>(
+    server: Arc<server::Server>,
+    mut state: InWorld<Io>,
+    caps: Capabilities,
+    serial: Serial,
+) -> Result<()> {
     use codecs::InWorldFrameRecv;
     use ultimaonline_net::packets::network::{PingAck, PingReq};
 
-    let mut client = server.new_client()?;
+    let mut client = server.new_client(server::player_status(
+        serial,
+        PLAYER_NAME,
+        caps.status_version,
+    ))?;
+
+    // Clients ping regularly, so one which sends nothing at all for this
+    // long is assumed to be gone
+    let idle_timeout = server.config().idle_timeout();
+    let mut last_activity = time::Instant::now();
 
     loop {
         tokio::select! {
             res = state.recv() => {
+                last_activity = time::Instant::now();
                 match res? {
                     Some(InWorldFrameRecv::PingReq(PingReq {val})) => {
                         state.send(&PingAck{val}).await?
                     },
+                    Some(InWorldFrameRecv::TalkRequest(req)) => echo_speech(&mut state, &req, &caps, serial).await?,
+                    // TODO: Track war mode on the player once combat exists
+                    Some(InWorldFrameRecv::WarMode(req)) => state.send(&req).await?,
+                    Some(InWorldFrameRecv::AttackRequest(req)) => {
+                        state.send(&action::AttackOk { serial: req.serial }).await?
+                    },
                     Some(packet) => client.send(packet)?,
                     None => {
                         debug!("Client connection closed.");
@@ -417,15 +490,231 @@ async fn in_world<Io: AsyncIo>(server: Arc<server::Server>, mut state: InWorld<I
             packet = client.receiver.recv() => {
                 match packet {
                     Some(packet) => state.send_frame(&packet).await?,
+                    // The server has stopped sending to the client, after
+                    // all of the packets already sent to it
+                    None if server.is_shutdown() => {
+                        debug!("Server shutting down.");
+                        leave_world(&mut state, serial, "The server is shutting down.").await?;
+                        break;
+                    }
                     None => {
-                        // TODO: Send packets that inform the client of removal
                         debug!("Client removed from world.");
+                        leave_world(&mut state, serial, "You have been removed from the world.").await?;
                         break;
                     }
                 }
             }
+
+            // Dropping the client on the way out removes the player from
+            // the world on the next tick
+            _ = time::sleep_until(last_activity + idle_timeout) => {
+                debug!("Client was idle for {:?}, disconnecting.", idle_timeout);
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Inform the client that the player was removed from the world, ignoring
+// errors from a connection which the client already closed
+async fn leave_world<Io: AsyncIo>(
+    state: &mut InWorld<Io>,
+    serial: Serial,
+    reason: &str,
+) -> Result<()> {
+    use ultimaonline_net::{error::Error, packets::entity};
+
+    let res = async {
+        state
+            .send(&chat::AsciiMessage {
+                serial: 0xFFFFFFFF,
+                graphic: 0xFFFF,
+                kind: chat::MessageType::System,
+                hue: 0x0026,
+                font: 3,
+                name: "System".into(),
+                text: reason.to_string(),
+            })
+            .await?;
+        state.send(&entity::DeleteObject { serial }).await
+    }
+    .await;
+
+    match res {
+        Err(Error::Io(err)) if is_disconnect(&err) => {
+            debug!("Client connection already closed.");
+            Ok(())
         }
+        res => Ok(res?),
+    }
+}
+
+fn is_disconnect(err: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    matches!(
+        err.kind(),
+        ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::NotConnected
+    )
+}
+
+// Repeat what the player said back to them, so that speech can be verified end-to-end
+async fn echo_speech<Io: AsyncIo>(
+    state: &mut InWorld<Io>,
+    req: &chat::TalkRequest,
+    caps: &Capabilities,
+    serial: Serial,
+) -> Result<()> {
+    let text = req.text()?;
+    debug!(%text, "Got speech: {}", text);
+
+    if !caps.unicode_speech || (req.is_encoded() && text.is_ascii()) {
+        state
+            .send(&chat::AsciiMessage {
+                serial,
+                graphic: 401,
+                kind: chat::MessageType::Regular,
+                hue: req.hue,
+                font: req.font,
+                name: PLAYER_NAME.into(),
+                text,
+            })
+            .await?;
+    } else {
+        state
+            .send(&chat::UnicodeMessage {
+                serial,
+                graphic: 401,
+                kind: chat::MessageType::Regular,
+                hue: req.hue,
+                font: req.font,
+                language: req.language,
+                name: PLAYER_NAME.into(),
+                text: text.into(),
+            })
+            .await?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{self, AsyncRead};
+
+    #[test]
+    fn starting_city_names_fit() {
+        let cities = starting_cities().unwrap();
+        assert_eq!(cities.len(), 9);
+    }
+
+    #[test]
+    fn rejects_zero_tick() {
+        assert!(Args::try_parse_from(["game", "127.0.0.1", "2594", "0"]).is_err());
+        assert!(Args::try_parse_from(["game", "127.0.0.1", "2594", "1"]).is_ok());
+    }
+
+    // Reads everything sent to the client, so that the server never waits
+    // on a full buffer
+    fn discard(mut reader: impl AsyncRead + Unpin + Send + 'static) {
+        tokio::spawn(async move { io::copy(&mut reader, &mut io::sink()).await });
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn disconnects_idle_clients() {
+        let idle_timeout = Duration::from_secs(60);
+        let tick = server::ServerConfig::default().tick();
+        let server = Arc::new(server::Server::new(
+            server::ServerConfig::new(tick, idle_timeout).unwrap(),
+        ));
+        let server_task = {
+            let server = server.clone();
+            tokio::spawn(async move { server.run_loop().await })
+        };
+
+        let (io, peer) = io::duplex(1024);
+        let (peer_reader, mut peer_writer) = io::split(peer);
+        discard(peer_reader);
+
+        let state = ClientVersion::from(CharList::from(Connected::new(io)));
+        let state = InWorld::from(CharLogin::from(CharSelect::from(state)));
+        let caps = Capabilities::for_version(&"7.0.15.1".parse().unwrap());
+
+        // Someone else in the world sees the player leave
+        let mut other = server
+            .new_client(server::player_status(1002, "Other", caps.status_version))
+            .unwrap();
+
+        let serial = server.new_player_serial();
+        let start = time::Instant::now();
+        let player = tokio::spawn(in_world(server.clone(), state, caps, serial));
+
+        // Pings keep the client connected
+        time::sleep(idle_timeout / 2).await;
+        peer_writer.write_all(&[0x73, 0x00]).await.unwrap();
+        time::sleep(idle_timeout * 3 / 4).await;
+        assert!(!player.is_finished());
+
+        player.await.unwrap().unwrap();
+        assert_eq!(start.elapsed(), idle_timeout * 3 / 2);
+
+        // The player is removed on the next tick
+        time::sleep(server.config().tick() * 3 / 2).await;
+        let mut deleted = false;
+        while let Ok(frame) = other.receiver.try_recv() {
+            deleted |= matches!(
+                frame,
+                codecs::InWorldFrameSend::DeleteObject(pkt) if pkt.serial == serial
+            );
+        }
+        assert!(deleted);
+
+        server.shutdown();
+        server_task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn closes_clients_on_shutdown() {
+        let server = Arc::new(server::Server::new(Default::default()));
+        let server_task = {
+            let server = server.clone();
+            tokio::spawn(async move { server.run_loop().await })
+        };
+
+        // Uncompressed, so the packets can be found in what was sent
+        let (io, mut peer) = io::duplex(1024);
+        let state = Connected::new(io).without_compression();
+        let state = ClientVersion::from(CharList::from(state));
+        let state = InWorld::from(CharLogin::from(CharSelect::from(state)));
+        let caps = Capabilities::for_version(&"7.0.15.1".parse().unwrap());
+
+        let serial = server.new_player_serial();
+        let player = tokio::spawn(in_world(server.clone(), state, caps, serial));
+        let received = tokio::spawn(async move {
+            let mut bytes = vec![];
+            peer.read_to_end(&mut bytes).await.map(|_| bytes)
+        });
+
+        time::sleep(server.config().tick() * 3 / 2).await;
+        server.shutdown();
+        server_task.await.unwrap().unwrap();
+
+        // The connection is closed once the player has left the world
+        let bytes = time::timeout(SHUTDOWN_TIMEOUT, received)
+            .await
+            .expect("Client was not disconnected in time")
+            .unwrap()
+            .unwrap();
+        player.await.unwrap().unwrap();
+
+        let reason = b"The server is shutting down.";
+        assert!(bytes.windows(reason.len()).any(|window| window == reason));
+
+        let mut delete = vec![0x1D];
+        delete.extend_from_slice(&serial.to_be_bytes());
+        assert!(bytes.ends_with(&delete));
+    }
+}
@@ -1,53 +1,71 @@
+use clap::Parser;
 use eyre::{eyre, Context, Result};
 use std::{
     convert::TryInto,
-    env,
     net::{Ipv4Addr, SocketAddrV4},
+    path::PathBuf,
+    sync::Arc,
 };
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpListener;
-use tracing::{debug_span, debug, info_span, info};
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+use tracing::{debug, debug_span, info, info_span};
+use tracing_subscriber::{fmt, prelude::*};
+use ultimaonline_net::types::Flags8;
+use uoverse_server::config::ConfigArgs;
 use uoverse_server::login::client::*;
+use uoverse_server::tickets::TicketRegistry;
 
-const DEFAULT_LISTEN_ADDR: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
-const DEFAULT_LISTEN_PORT: u16 = 2593;
+/// Login server, handing clients off to the game server
+#[derive(Parser)]
+struct Args {
+    /// Address to listen on
+    listen_addr: Option<Ipv4Addr>,
 
-const DEFAULT_GAME_ADDR: Ipv4Addr = DEFAULT_LISTEN_ADDR;
-const DEFAULT_GAME_PORT: u16 = DEFAULT_LISTEN_PORT + 1;
+    /// Port to listen on
+    listen_port: Option<u16>,
+
+    /// Address of the game server
+    game_addr: Option<Ipv4Addr>,
+
+    /// Port of the game server
+    game_port: Option<u16>,
+
+    /// Account store
+    #[arg(long)]
+    accounts: Option<PathBuf>,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
 
 #[tokio::main]
 pub async fn main() -> Result<()> {
-    let mut listen_addr = DEFAULT_LISTEN_ADDR;
-    let mut listen_port = DEFAULT_LISTEN_PORT;
-
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        listen_addr = args[1]
-            .parse()
-            .expect(format!("Invalid listen address: {}", &args[1]).as_str());
+    let args = Args::parse();
+    let mut config = args.config.load()?;
+
+    if let Some(addr) = args.listen_addr {
+        config.login.listen.set_ip(addr);
     }
-    if args.len() > 2 {
-        listen_port = u16::from_str_radix(&args[2], 10)
-            .expect(format!("Invalid listen port: {}", &args[2]).as_str());
+    if let Some(port) = args.listen_port {
+        config.login.listen.set_port(port);
     }
-
-    let listen_socket = SocketAddrV4::new(listen_addr, listen_port);
-
-    let mut game_addr = DEFAULT_GAME_ADDR;
-    let mut game_port = DEFAULT_GAME_PORT;
-    if args.len() > 3 {
-        game_addr = args[3]
-            .parse()
-            .expect(format!("Invalid game server address: {}", &args[3]).as_str())
+    if let Some(addr) = args.game_addr {
+        config.login.game_server.set_ip(addr);
+    }
+    if let Some(port) = args.game_port {
+        config.login.game_server.set_port(port);
     }
-    if args.len() > 4 {
-        game_port = u16::from_str_radix(&args[4], 10)
-            .expect(format!("Invalid game server port: {}", &args[4]).as_str());
+    if let Some(accounts) = args.accounts {
+        config.login.accounts = Some(accounts);
     }
-    let game_socket = SocketAddrV4::new(game_addr, game_port);
 
-    tracing_subscriber::registry().with(fmt::layer()).with(EnvFilter::from_default_env()).init();
+    let listen_socket = config.login.listen;
+    let game_socket = config.login.game_server;
+
+    tracing_subscriber::registry()
+        .with(fmt::layer())
+        .with(config.env_filter())
+        .init();
 
     let span = info_span!("server");
     let _ = span.enter();
@@ -55,11 +73,21 @@ pub async fn main() -> Result<()> {
     let listener = TcpListener::bind(listen_socket).await.unwrap();
     info!(socket = %listen_socket, "Login server listening on {}", listen_socket);
     info!(socket = %game_socket, "Using game server socket {}", game_socket);
+    if let Some(accounts) = &config.login.accounts {
+        // Any account is accepted until there is an account store
+        info!(path = %accounts.display(), "Account store {} is not used yet", accounts.display());
+    }
+
+    let tickets = Arc::new(match &config.tickets {
+        Some(path) => TicketRegistry::open(path),
+        None => TicketRegistry::new(),
+    });
 
     loop {
         let (mut socket, _) = listener.accept().await.unwrap();
+        let tickets = tickets.clone();
         tokio::spawn(async move {
-            process(&mut socket, game_socket)
+            process(&mut socket, game_socket, &tickets)
                 .await
                 .wrap_err("Client had error during login")?;
 
@@ -70,7 +98,11 @@ pub async fn main() -> Result<()> {
     }
 }
 
-async fn process<Io: AsyncIo>(socket: Io, game_socket: SocketAddrV4) -> Result<()> {
+async fn process<Io: AsyncIo>(
+    socket: Io,
+    game_socket: SocketAddrV4,
+    tickets: &TicketRegistry,
+) -> Result<()> {
     use ultimaonline_net::packets::login as packets;
 
     let span = debug_span!("client_process");
@@ -79,7 +111,11 @@ async fn process<Io: AsyncIo>(socket: Io, game_socket: SocketAddrV4) -> Result<(
     let mut state = Connected::new(socket);
     let hello = match state.recv().await? {
         Some(codecs::ConnectedFrameRecv::ClientHello(hello)) => hello,
-        _ => return Err(eyre!("Did not get ClientHello packet")),
+        None => {
+            return Err(eyre!(
+                "Client disconnected before sending ClientHello packet"
+            ))
+        }
     };
 
     debug!(
@@ -91,7 +127,11 @@ async fn process<Io: AsyncIo>(socket: Io, game_socket: SocketAddrV4) -> Result<(
     let mut state = Hello::<Io>::from(state);
     let login = match state.recv().await? {
         Some(codecs::HelloFrameRecv::AccountLogin(login)) => login,
-        _ => return Err(eyre!("Did not get AccountLogin packet")),
+        None => {
+            return Err(eyre!(
+                "Client disconnected before sending AccountLogin packet"
+            ))
+        }
     };
 
     let username = TryInto::<&str>::try_into(&login.username).expect("Invalid UTF-8 in username");
@@ -119,7 +159,7 @@ async fn process<Io: AsyncIo>(socket: Io, game_socket: SocketAddrV4) -> Result<(
     // Send server list
     state
         .send(&packets::ServerList {
-            flags: 0x5D,
+            flags: Flags8(0x5D),
             list: vec![packets::ServerInfo {
                 index: 0,
                 name: "Test Server".into(),
@@ -138,18 +178,23 @@ async fn process<Io: AsyncIo>(socket: Io, game_socket: SocketAddrV4) -> Result<(
         Some(codecs::ServerSelectFrameRecv::ServerSelection(packets::ServerSelection {
             index,
         })) => index,
-        _ => return Err(eyre!("Did not get ServerSelection packet")),
+        None => {
+            return Err(eyre!(
+                "Client disconnected before sending ServerSelection packet"
+            ))
+        }
     };
 
     debug!("Got server selection: {}", selection);
 
     let mut state = Handoff::<Io>::from(state);
 
-    // Send the information to hand-off to the game server
+    // Send the information to hand-off to the game server. The client logs
+    // in to the game server with the ticket as its seed.
     state
         .send(&packets::GameServerHandoff {
             socket: game_socket,
-            ticket: rand::random::<u32>(),
+            ticket: tickets.issue(username)?,
         })
         .await?;
 
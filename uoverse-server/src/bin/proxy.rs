@@ -0,0 +1,100 @@
+use clap::Parser;
+use eyre::{Context, Result};
+use std::net::SocketAddrV4;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{error, info, info_span, Instrument};
+use tracing_subscriber::{fmt, prelude::*};
+use uoverse_server::config::ConfigArgs;
+use uoverse_server::game::client::codecs::CompressionCodec;
+use uoverse_server::proxy::{codecs, forward, Tap};
+use uoverse_server::trace::TraceCodec;
+
+/// Proxy between clients and a real server, logging the packets sent each
+/// way. Frames are logged at debug level, and their bytes at trace level.
+///
+/// Clients are only proxied into the game if the login server hands them
+/// off to the proxy's game listener, rather than to the real game server.
+#[derive(Parser)]
+struct Args {
+    /// Login server to forward login connections to
+    login_server: SocketAddrV4,
+
+    /// Game server to forward game connections to
+    game_server: SocketAddrV4,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+#[tokio::main]
+pub async fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = args.config.load()?;
+
+    tracing_subscriber::registry()
+        .with(fmt::layer())
+        .with(config.env_filter())
+        .init();
+
+    let login_listener = TcpListener::bind(config.login.listen).await?;
+    let game_listener = TcpListener::bind(config.game.listen).await?;
+    info!(
+        "Proxying logins from {} to {}, and games from {} to {}",
+        config.login.listen, args.login_server, config.game.listen, args.game_server
+    );
+
+    loop {
+        tokio::select! {
+            Ok((client, addr)) = login_listener.accept() => {
+                let server = args.login_server;
+                tokio::spawn(async move {
+                    let res = async {
+                        let server = TcpStream::connect(server)
+                            .await
+                            .wrap_err("Unable to connect to login server")?;
+                        forward(
+                            client,
+                            server,
+                            Tap::new("client", TraceCodec::new(codecs::LoginClient)).with_seed(),
+                            Tap::new("server", TraceCodec::new(codecs::LoginServer)),
+                        )
+                        .await
+                        .wrap_err("Login connection failed")
+                    };
+
+                    if let Err(err) = res.instrument(info_span!("login", %addr)).await {
+                        error!("{:#}", err);
+                    }
+                });
+            }
+
+            Ok((client, addr)) = game_listener.accept() => {
+                let server = args.game_server;
+                tokio::spawn(async move {
+                    let res = async {
+                        let server = TcpStream::connect(server)
+                            .await
+                            .wrap_err("Unable to connect to game server")?;
+
+                        // Everything the game server sends is compressed
+                        forward(
+                            client,
+                            server,
+                            Tap::new("client", TraceCodec::new(codecs::GameClient)).with_seed(),
+                            Tap::new(
+                                "server",
+                                CompressionCodec::decompressing(TraceCodec::new(codecs::GameServer)),
+                            ),
+                        )
+                        .await
+                        .wrap_err("Game connection failed")
+                    };
+
+                    if let Err(err) = res.instrument(info_span!("game", %addr)).await {
+                        error!("{:#}", err);
+                    }
+                });
+            }
+        }
+    }
+}
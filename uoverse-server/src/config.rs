@@ -0,0 +1,220 @@
+use eyre::{bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    fs,
+    net::{Ipv4Addr, SocketAddrV4},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing_subscriber::EnvFilter;
+
+use crate::game::server::ServerConfig;
+
+const DEFAULT_ADDR: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+const DEFAULT_LOGIN_PORT: u16 = 2593;
+const DEFAULT_GAME_PORT: u16 = 2594;
+
+// Configuration of both the login and game servers, read from a TOML file.
+// Anything missing from the file takes its default value.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub log: Option<String>,      // Tracing filter, instead of RUST_LOG
+    pub tickets: Option<PathBuf>, // Handoff tickets shared by both servers
+    pub login: LoginConfig,
+    pub game: GameConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginConfig {
+    pub listen: SocketAddrV4,
+    pub game_server: SocketAddrV4, // Where clients are handed off to
+    pub accounts: Option<PathBuf>, // Account store
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddrV4::new(DEFAULT_ADDR, DEFAULT_LOGIN_PORT),
+            game_server: SocketAddrV4::new(DEFAULT_ADDR, DEFAULT_GAME_PORT),
+            accounts: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub listen: SocketAddrV4,
+    pub tick_ms: u64,
+    pub idle_timeout_secs: u64,
+    pub compression: bool, // Only turned off to debug, clients need it
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddrV4::new(DEFAULT_ADDR, DEFAULT_GAME_PORT),
+            tick_ms: ServerConfig::default().tick().as_millis() as u64,
+            idle_timeout_secs: ServerConfig::default().idle_timeout().as_secs(),
+            compression: true,
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn server_config(&self) -> Result<ServerConfig> {
+        ServerConfig::new(
+            Duration::from_millis(self.tick_ms),
+            Duration::from_secs(self.idle_timeout_secs),
+        )
+        .wrap_err("Invalid game server config")
+    }
+}
+
+impl Config {
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        toml::from_str(toml).wrap_err("Invalid config")
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let toml = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read config file {}", path.display()))?;
+
+        Self::from_toml(&toml).wrap_err_with(|| format!("In config file {}", path.display()))
+    }
+
+    // Zero lengths of time from the file would stop the game server from
+    // running at all, or disconnect every client straight away
+    fn check(&self) -> Result<()> {
+        if self.game.tick_ms == 0 {
+            bail!("game.tick_ms must be more than zero");
+        }
+        if self.game.idle_timeout_secs == 0 {
+            bail!("game.idle_timeout_secs must be more than zero");
+        }
+
+        Ok(())
+    }
+
+    pub fn env_filter(&self) -> EnvFilter {
+        match &self.log {
+            Some(filter) => EnvFilter::new(filter),
+            None => EnvFilter::from_default_env(),
+        }
+    }
+}
+
+// Options shared by the server binaries, to be flattened into their args
+#[derive(Clone, Debug, clap::Args)]
+pub struct ConfigArgs {
+    /// TOML config file, defaults are used for anything it leaves out
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// Tracing filter, overriding the config file and RUST_LOG
+    #[arg(long)]
+    pub log: Option<String>,
+}
+
+impl ConfigArgs {
+    pub fn load(&self) -> Result<Config> {
+        let mut config = match &self.config {
+            Some(path) => {
+                let config = Config::open(path)?;
+                config
+                    .check()
+                    .wrap_err_with(|| format!("In config file {}", path.display()))?;
+                config
+            }
+            None => Config::default(),
+        };
+
+        if let Some(log) = &self.log {
+            config.log = Some(log.clone());
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_config() {
+        let config = Config::from_toml(
+            r#"
+            log = "uoverse_server=debug"
+            tickets = "data/tickets.txt"
+
+            [login]
+            listen = "0.0.0.0:2593"
+            game_server = "192.168.1.10:2594"
+            accounts = "data/accounts.toml"
+
+            [game]
+            listen = "0.0.0.0:2594"
+            tick_ms = 250
+            idle_timeout_secs = 60
+            compression = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                log: Some("uoverse_server=debug".to_string()),
+                tickets: Some("data/tickets.txt".into()),
+                login: LoginConfig {
+                    listen: "0.0.0.0:2593".parse().unwrap(),
+                    game_server: "192.168.1.10:2594".parse().unwrap(),
+                    accounts: Some("data/accounts.toml".into()),
+                },
+                game: GameConfig {
+                    listen: "0.0.0.0:2594".parse().unwrap(),
+                    tick_ms: 250,
+                    idle_timeout_secs: 60,
+                    compression: false,
+                },
+            }
+        );
+        let server_config = config.game.server_config().unwrap();
+        assert_eq!(server_config.tick(), Duration::from_millis(250));
+        assert_eq!(server_config.idle_timeout(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn defaults() {
+        let config = Config::from_toml("[game]\ntick_ms = 100\n").unwrap();
+        assert_eq!(config.login, LoginConfig::default());
+        assert_eq!(config.game.listen, LoginConfig::default().game_server);
+        assert_eq!(config.game.tick_ms, 100);
+        assert!(config.game.compression);
+
+        assert!(Config::from_toml("[game]\ntick = 100\n").is_err());
+    }
+
+    #[test]
+    fn rejects_zero_durations() {
+        let path = std::env::temp_dir().join(format!("uoverse_config_{}.toml", std::process::id()));
+        let load = |toml: &str| {
+            fs::write(&path, toml).unwrap();
+            ConfigArgs {
+                config: Some(path.clone()),
+                log: None,
+            }
+            .load()
+        };
+
+        assert!(load("[game]\ntick_ms = 0\n").is_err());
+        assert!(load("[game]\nidle_timeout_secs = 0\n").is_err());
+        assert!(load("[game]\ntick_ms = 1\nidle_timeout_secs = 1\n").is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,59 @@
+use ultimaonline_net::packets::login::ClientVersion;
+
+// Which variants of packets a client understands, based on its version
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    pub status_version: u8, // Layout of CharStatus
+    pub unicode_speech: bool,
+    pub world_item_v2: bool, // WorldItem rather than WorldItemLegacy
+}
+
+// Clients from this version onwards read the Age of Shadows stats at the
+// end of CharStatus
+const STATUS_V6: ClientVersion = ClientVersion::new(7, 0, 0, 0);
+
+// Clients before this version can only display ASCII speech
+const UNICODE_SPEECH: ClientVersion = ClientVersion::new(2, 0, 0, 0);
+
+// High Seas clients read WorldItem, with the field it added at the end
+const WORLD_ITEM_V2: ClientVersion = ClientVersion::new(7, 0, 9, 0);
+
+impl Capabilities {
+    pub fn for_version(version: &ClientVersion) -> Self {
+        Self {
+            // Earlier layouts aren't supported, so older clients get the
+            // oldest one that is
+            status_version: if *version >= STATUS_V6 { 6 } else { 5 },
+            unicode_speech: *version >= UNICODE_SPEECH,
+            world_item_v2: *version >= WORLD_ITEM_V2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_version() {
+        let caps = Capabilities::for_version(&"7.0.15.1".parse().unwrap());
+        assert_eq!(caps.status_version, 6);
+        assert!(caps.unicode_speech);
+
+        let caps = Capabilities::for_version(&"6.0.14.2".parse().unwrap());
+        assert_eq!(caps.status_version, 5);
+        assert!(caps.unicode_speech);
+    }
+
+    #[test]
+    fn ascii_speech() {
+        let caps = Capabilities::for_version(&"1.26.4".parse().unwrap());
+        assert!(!caps.unicode_speech);
+    }
+
+    #[test]
+    fn world_item_version() {
+        assert!(Capabilities::for_version(&"7.0.9.0".parse().unwrap()).world_item_v2);
+        assert!(!Capabilities::for_version(&"7.0.8.2".parse().unwrap()).world_item_v2);
+    }
+}
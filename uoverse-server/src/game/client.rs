@@ -1,4 +1,5 @@
 use futures::sink::SinkExt;
+use std::collections::HashSet;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     sync::mpsc::{self, error::TryRecvError},
@@ -7,20 +8,38 @@ use tokio_stream::StreamExt;
 use tokio_util::codec::Framed;
 use ultimaonline_net::{
     error::{Error, Result},
-    packets::Packet,
+    packets::{
+        char_login::{self, SkillLock, StatLocks},
+        char_select, Packet,
+    },
+    types::{Flags32, MovementSequence, Serial},
 };
 
+use crate::trace::TraceCodec;
+
 pub mod codecs;
 use codecs::CompressionCodec;
 
 pub trait AsyncIo = AsyncRead + AsyncWrite + Unpin + Send + Sync;
 
-// Ensures that the FSM must start with the initial state (Connected)
-struct GameSequencer;
+// Ensures that the FSM must start with the initial state (Connected), and
+// carries the connection's settings from each state to the next
+struct GameSequencer {
+    compress: bool,
+}
+
+impl GameSequencer {
+    fn compression<C>(&self, codec: C) -> CompressionCodec<C> {
+        match self.compress {
+            true => CompressionCodec::new(codec),
+            false => CompressionCodec::uncompressed(codec),
+        }
+    }
+}
 
 pub struct Connected<Io: AsyncIo> {
     sequencer: GameSequencer,
-    framer: Framed<Io, codecs::Connected>,
+    framer: Framed<Io, TraceCodec<codecs::Connected>>,
 }
 
 impl<Io: AsyncIo> Connected<Io> {
@@ -30,15 +49,21 @@ impl<Io: AsyncIo> Connected<Io> {
 
     pub fn new(io: Io) -> Self {
         Self {
-            sequencer: GameSequencer {},
-            framer: Framed::new(io, codecs::Connected),
+            sequencer: GameSequencer { compress: true },
+            framer: Framed::new(io, TraceCodec::new(codecs::Connected)),
         }
     }
+
+    // Sends the packets without compressing them, for debugging
+    pub fn without_compression(mut self) -> Self {
+        self.sequencer.compress = false;
+        self
+    }
 }
 
 pub struct CharList<Io: AsyncIo> {
     sequencer: GameSequencer,
-    framer: Framed<Io, CompressionCodec<codecs::CharList>>,
+    framer: Framed<Io, CompressionCodec<TraceCodec<codecs::CharList>>>,
 }
 
 impl<Io: AsyncIo> CharList<Io> {
@@ -54,18 +79,17 @@ impl<Io: AsyncIo> CharList<Io> {
 
 impl<Io: AsyncIo> From<Connected<Io>> for CharList<Io> {
     fn from(val: Connected<Io>) -> Self {
+        let codec = val.sequencer.compression(TraceCodec::new(codecs::CharList));
         Self {
             sequencer: val.sequencer,
-            framer: val
-                .framer
-                .map_codec(|_| CompressionCodec::new(codecs::CharList {})),
+            framer: val.framer.map_codec(|_| codec),
         }
     }
 }
 
 pub struct ClientVersion<Io: AsyncIo> {
     sequencer: GameSequencer,
-    framer: Framed<Io, codecs::ClientVersion>,
+    framer: Framed<Io, TraceCodec<codecs::ClientVersion>>,
 }
 
 impl<Io: AsyncIo> ClientVersion<Io> {
@@ -78,14 +102,16 @@ impl<Io: AsyncIo> From<CharList<Io>> for ClientVersion<Io> {
     fn from(val: CharList<Io>) -> Self {
         Self {
             sequencer: val.sequencer,
-            framer: val.framer.map_codec(|_| codecs::ClientVersion),
+            framer: val
+                .framer
+                .map_codec(|_| TraceCodec::new(codecs::ClientVersion)),
         }
     }
 }
 
 pub struct CharSelect<Io: AsyncIo> {
     sequencer: GameSequencer,
-    framer: Framed<Io, codecs::CharSelect>,
+    framer: Framed<Io, TraceCodec<codecs::CharSelect>>,
 }
 
 impl<Io: AsyncIo> CharSelect<Io> {
@@ -98,14 +124,16 @@ impl<Io: AsyncIo> From<ClientVersion<Io>> for CharSelect<Io> {
     fn from(val: ClientVersion<Io>) -> Self {
         Self {
             sequencer: val.sequencer,
-            framer: val.framer.map_codec(|_| codecs::CharSelect),
+            framer: val
+                .framer
+                .map_codec(|_| TraceCodec::new(codecs::CharSelect)),
         }
     }
 }
 
 pub struct CharLogin<Io: AsyncIo> {
     sequencer: GameSequencer,
-    framer: Framed<Io, CompressionCodec<codecs::CharLogin>>,
+    framer: Framed<Io, CompressionCodec<TraceCodec<codecs::CharLogin>>>,
 }
 
 impl<Io: AsyncIo> CharLogin<Io> {
@@ -117,15 +145,30 @@ impl<Io: AsyncIo> CharLogin<Io> {
     {
         self.framer.send(pkt).await
     }
+
+    // Sends the packets with only one flush at the end, rather than one for
+    // each packet. Every packet is compressed on its own, so the client reads
+    // them the same as if they were sent separately.
+    pub async fn send_all<I>(&mut self, frames: I) -> Result<()>
+    where
+        I: IntoIterator<Item = codecs::CharLoginFrameSend>,
+    {
+        for frame in frames {
+            self.framer.feed(&frame).await?;
+        }
+
+        SinkExt::<&codecs::CharLoginFrameSend>::flush(&mut self.framer).await
+    }
 }
 
 impl<Io: AsyncIo> From<CharSelect<Io>> for CharLogin<Io> {
     fn from(val: CharSelect<Io>) -> Self {
+        let codec = val
+            .sequencer
+            .compression(TraceCodec::new(codecs::CharLogin));
         Self {
             sequencer: val.sequencer,
-            framer: val
-                .framer
-                .map_codec(|_| CompressionCodec::new(codecs::CharLogin)),
+            framer: val.framer.map_codec(|_| codec),
         }
     }
 }
@@ -133,7 +176,7 @@ impl<Io: AsyncIo> From<CharSelect<Io>> for CharLogin<Io> {
 pub struct InWorld<Io: AsyncIo> {
     #[allow(dead_code)]
     sequencer: GameSequencer,
-    framer: Framed<Io, CompressionCodec<codecs::InWorld>>,
+    framer: Framed<Io, CompressionCodec<TraceCodec<codecs::InWorld>>>,
 }
 
 impl<Io: AsyncIo> InWorld<Io> {
@@ -146,6 +189,18 @@ impl<Io: AsyncIo> InWorld<Io> {
         self.framer.send(pkt).await
     }
 
+    // Sends the packets with only one flush at the end, like CharLogin
+    pub async fn send_all<I>(&mut self, frames: I) -> Result<()>
+    where
+        I: IntoIterator<Item = codecs::InWorldFrameSend>,
+    {
+        for frame in frames {
+            self.framer.feed(&frame).await?;
+        }
+
+        SinkExt::<&codecs::InWorldFrameSend>::flush(&mut self.framer).await
+    }
+
     pub async fn send_frame<'a>(&mut self, pkt: &'a codecs::InWorldFrameSend) -> Result<()> {
         self.framer.send(pkt).await
     }
@@ -157,11 +212,10 @@ impl<Io: AsyncIo> InWorld<Io> {
 
 impl<Io: AsyncIo> From<CharLogin<Io>> for InWorld<Io> {
     fn from(val: CharLogin<Io>) -> Self {
+        let codec = val.sequencer.compression(TraceCodec::new(codecs::InWorld));
         Self {
             sequencer: val.sequencer,
-            framer: val
-                .framer
-                .map_codec(|_| CompressionCodec::new(codecs::InWorld {})),
+            framer: val.framer.map_codec(|_| codec),
         }
     }
 }
@@ -208,8 +262,62 @@ impl ClientReceiver for Client {
 }
 
 pub struct WorldClient {
-    pub receiver: mpsc::UnboundedReceiver<codecs::InWorldFrameRecv>,
+    pub id: u64,
+    pub serial: Serial, // Serial of the player's character
     pub sender: mpsc::UnboundedSender<codecs::InWorldFrameSend>,
+    pub in_range: HashSet<Serial>,
+    pub movement: MovementSequence,
+    pub status: char_login::CharStatus, // As last sent to the client
+    pub stat_locks: StatLocks,
+    closed: bool,
+}
+
+impl WorldClient {
+    pub fn new(
+        id: u64,
+        status: char_login::CharStatus,
+        sender: mpsc::UnboundedSender<codecs::InWorldFrameSend>,
+    ) -> Self {
+        Self {
+            id,
+            serial: status.serial,
+            sender,
+            in_range: HashSet::new(),
+            movement: MovementSequence::new(),
+            status,
+            stat_locks: StatLocks {
+                strength: SkillLock::Up,
+                dexterity: SkillLock::Up,
+                intelligence: SkillLock::Up,
+            },
+            closed: false,
+        }
+    }
+
+    // Changes the player's status, sending it to the client if it changed
+    pub fn update_status(
+        &mut self,
+        change: impl FnOnce(&mut char_login::CharStatus),
+    ) -> Result<()> {
+        let before = self.status.clone();
+        change(&mut self.status);
+        if self.status != before {
+            self.send(self.status.clone().into())?;
+        }
+
+        Ok(())
+    }
+
+    // Closed either by the world, or by the client side dropping its receiver
+    pub fn is_closed(&self) -> bool {
+        self.closed || self.sender.is_closed()
+    }
+
+    // The client's packets are received by the server loop, which stops
+    // receiving them once the client is removed from the world
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
 }
 
 impl ClientSender for WorldClient {
@@ -221,35 +329,114 @@ impl ClientSender for WorldClient {
     }
 }
 
-impl ClientReceiver for WorldClient {
-    type RecvItem = codecs::InWorldFrameRecv;
-    fn recv(&mut self) -> Result<Option<Self::RecvItem>> {
-        match self.receiver.try_recv() {
-            Ok(item) => Ok(Some(item)),
-            Err(TryRecvError::Empty) => Ok(None),
-            _ => Err(Error::Message("TODO: MPSC recv error".to_string())),
-        }
+// Enable all features except:
+pub const FEATURES: Flags32 = char_select::Features::ALL
+    .difference(char_select::Features::THIRD_DAWN)
+    .difference(char_select::Features::SIXTH_CHAR_SLOT)
+    .difference(char_select::Features::EIGHTH_AGE)
+    .difference(char_select::Features::TENTH_AGE)
+    .difference(char_select::Features::INCREASED_STORAGE)
+    .difference(char_select::Features::ROLEPLAY_FACES)
+    .difference(char_select::Features::TRIAL_ACCOUNT);
+
+// Disable all flags except:
+pub const FLAGS: Flags32 = char_select::CharList::CONTEXT_MENUS
+    .union(char_select::CharList::AOS_EXPANSION)
+    .union(char_select::CharList::SE_EXPANSION)
+    .union(char_select::CharList::ML_EXPANSION)
+    .union(char_select::CharList::SEVENTH_CHAR_SLOT);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+    use ultimaonline_net::packets::{char_login, map};
+
+    fn char_login(io: io::DuplexStream) -> CharLogin<io::DuplexStream> {
+        let state = ClientVersion::from(CharList::from(Connected::new(io)));
+        CharLogin::from(CharSelect::from(state))
     }
 
-    fn close(&mut self) {
-        self.receiver.close();
+    #[test]
+    fn feature_flags() {
+        assert_eq!(FEATURES, Flags32(0b0000_0000_1111_1111_1001_0010_1101_1011));
+        assert!(!FEATURES.contains(char_select::Features::THIRD_DAWN));
+        assert!(!FEATURES.contains(char_select::Features::TRIAL_ACCOUNT));
+
+        assert_eq!(FLAGS, Flags32(0b0000_0000_0000_0000_0001_0001_1010_1000));
+        assert!(FLAGS.contains(
+            char_select::CharList::CONTEXT_MENUS | char_select::CharList::SEVENTH_CHAR_SLOT
+        ));
     }
-}
 
-// Enable all flags except:
-// Third Dawn =                                                0100
-// 6th Char Slot =                                        0010 0000
-// 8th Age =                                         0001 0000 0000
-// 10th Age =                                        0100 0000 0000
-// Increased Storage =                               1000 0000 0000
-// Roleplay Faces =                             0010 0000 0000 0000
-// Trial Account =                              0100 0000 0000 0000
-pub const FEATURES: u32 = 0b0000_0000_1111_1111_1001_0010_1101_1011;
+    #[tokio::test]
+    async fn batches_sends() {
+        let (io, mut peer) = io::duplex(1024);
+        let mut state = char_login(io);
+        state.send(&map::MapChange { map_id: 0x1 }).await.unwrap();
+        state.send(&char_login::LoginComplete {}).await.unwrap();
+        drop(state);
+
+        let mut sequential = vec![];
+        peer.read_to_end(&mut sequential).await.unwrap();
+
+        let (io, mut peer) = io::duplex(1024);
+        let mut state = char_login(io);
+        state
+            .send_all(vec![
+                map::MapChange { map_id: 0x1 }.into(),
+                char_login::LoginComplete {}.into(),
+            ])
+            .await
+            .unwrap();
+        drop(state);
+
+        let mut batched = vec![];
+        peer.read_to_end(&mut batched).await.unwrap();
+
+        assert!(!batched.is_empty());
+        assert_eq!(batched, sequential);
+    }
 
-// Disable all flags except:
-// Context Menus =                                          1000
-// AOS Expansion =                                     0010 0000
-// SE Expansion =                                      1000 0000
-// ML Expansion =                                 0001 0000 0000
-// Seventh Char Slot =                       0001 0000 0000 0000
-pub const FLAGS: u32 = 0b0000_0000_0000_0000_0001_0001_1010_1000;
+    #[tokio::test]
+    async fn sends_uncompressed() {
+        use crate::proxy::codecs::{GameServer, GameServerFrameRecv};
+        use tokio_util::codec::Decoder;
+
+        let (io, mut peer) = io::duplex(1024);
+        let state = Connected::new(io).without_compression();
+        let mut state =
+            CharLogin::from(CharSelect::from(ClientVersion::from(CharList::from(state))));
+        state.send(&map::MapChange { map_id: 0x1 }).await.unwrap();
+        drop(state);
+
+        let mut bytes = vec![];
+        peer.read_to_end(&mut bytes).await.unwrap();
+        assert_eq!(bytes, [0xBF, 0x00, 0x06, 0x00, 0x08, 0x01]);
+
+        // Read the same way as a client would, just without decompressing
+        match GameServer.decode(&mut bytes.as_slice().into()) {
+            Ok(Some(GameServerFrameRecv::MapChange(pkt))) => assert_eq!(pkt.map_id, 0x1),
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_unexpected_packet() {
+        let (io, mut peer) = io::duplex(1024);
+        let mut state = CharSelect::from(ClientVersion::from(CharList::from(Connected::new(io))));
+
+        // A request to go into war mode, before a character is chosen
+        peer.write_all(&[0x72, 0x01, 0x00, 0x32, 0x00])
+            .await
+            .unwrap();
+
+        match state.recv().await {
+            Err(Error::UnexpectedPacket { expected, got }) => {
+                assert_eq!(expected, "CharSelect");
+                assert_eq!(got, (0x72, None));
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+}
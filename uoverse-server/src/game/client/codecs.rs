@@ -52,39 +52,137 @@ define_codec! {
 define_codec! {
     pub InWorld,
     send [
+        action::AttackOk,
+        action::TargetRequest,
+        char_login::CharStatus,
+        char_login::ExtendedStats,
+        char_login::ResurrectMenu,
+        char_login::Skills,
+        chat::AsciiMessage,
+        chat::ClilocAffixMessage,
+        chat::ClilocMessage,
+        chat::UnicodeMessage,
+        client_info::ContextMenuDisplay,
+        entity::DeleteObject,
+        entity::MegaCliloc,
+        gump::CloseGeneric,
+        gump::CompressedGump,
+        gump::DisplayGump,
+        housing::CustomHouse,
+        item::ContainerContents,
+        item::Corpse,
+        item::DropReject,
+        item::OpenContainer,
+        item::WorldItem,
+        item::WorldItemLegacy,
         mobile::Appearance,
+        mobile::BuffBar,
+        mobile::DeathAction,
         mobile::MobLightLevel,
+        mobile::Paperdoll,
+        mobile::PlayerUpdate,
+        mobile::ProfileResponse,
         mobile::State,
+        mobile::StatusBarFlag,
+        mobile::UpdateHealth,
+        mobile::UpdateMana,
+        mobile::UpdateStamina,
+        mobile::WarMode,
+        mobile::WornItem,
         movement::Success,
         movement::Reject,
         network::PingAck,
+        trade::SecureTrade,
+        vendor::BuyList,
+        vendor::SellList,
+        world::GameTime,
+        world::GraphicalEffect,
+        world::HuedEffect,
+        world::HuedEffectLegacy,
+        world::MapPins,
+        world::OpenMap,
+        world::PlayMusic,
+        world::Season,
+        world::SoundEffect,
+        world::Weather,
         world::WorldLightLevel,
     ],
     recv [
+        action::AttackRequest,
         action::ClickUse,
         action::ClickLook,
+        action::TargetResponse,
+        char_login::ResurrectMenu,
+        char_login::SkillLockChange,
+        char_login::StatLockChange,
         char_select::VersionResp,
         chat::OpenWindow,
+        chat::TalkRequest,
+        client_info::ContextMenuRequest,
+        client_info::ContextMenuResponse,
         client_info::Flags,
         client_info::Language,
         client_info::WindowSize,
         client_info::ViewRange,
         entity::EntityBatchQuery,
         gump::CloseStatus,
+        gump::GumpResponse,
+        housing::DesignRequest,
         housing::ShowPublicContent,
+        item::DropRequest,
+        item::EquipRequest,
+        item::LiftRequest,
+        mobile::ProfileRequest,
         mobile::Query,
+        mobile::WarMode,
         movement::Request,
-        network::PingReq
+        movement::Resync,
+        network::PingReq,
+        trade::TradeResponse,
+        vendor::BuyRequest,
+        world::MapPins,
     ]
 }
 
+// Compresses the packets sent to the client. Packets from the client aren't
+// compressed, so they are only decompressed when this is decoding what a
+// server sent instead, such as when proxying. Compression can also be turned
+// off, to see the packets' bytes on the wire when debugging, though real
+// clients can't read them.
+//
+// Each packet is compressed on its own, ending with the terminator and
+// padded to a whole byte, as the client expects. No state is carried from
+// one packet to the next.
 pub struct CompressionCodec<C> {
     codec: C,
+    compress: bool,
+    decompressed: Option<BytesMut>,
 }
 
 impl<C> CompressionCodec<C> {
     pub fn new(codec: C) -> Self {
-        Self { codec }
+        Self {
+            codec,
+            compress: true,
+            decompressed: None,
+        }
+    }
+
+    pub fn decompressing(codec: C) -> Self {
+        Self {
+            codec,
+            compress: true,
+            decompressed: Some(BytesMut::new()),
+        }
+    }
+
+    // Passes the packets through unchanged in both directions
+    pub fn uncompressed(codec: C) -> Self {
+        Self {
+            codec,
+            compress: false,
+            decompressed: None,
+        }
     }
 }
 
@@ -95,6 +193,10 @@ impl<I, C: Encoder<I>> Encoder<I> for CompressionCodec<C> {
         use bytes::BufMut;
         use ultimaonline_net::compression::huffman;
 
+        if !self.compress {
+            return self.codec.encode(pkt, dst);
+        }
+
         let mut tmp = BytesMut::with_capacity(64);
         self.codec.encode(pkt, &mut tmp)?;
         let compressed = huffman::compress(&*tmp);
@@ -105,7 +207,11 @@ impl<I, C: Encoder<I>> Encoder<I> for CompressionCodec<C> {
     }
 }
 
-impl<C: Decoder> Decoder for CompressionCodec<C> {
+impl<C> Decoder for CompressionCodec<C>
+where
+    C: Decoder,
+    C::Error: From<ultimaonline_net::error::Error>,
+{
     type Error = C::Error;
     type Item = C::Item;
 
@@ -113,6 +219,284 @@ impl<C: Decoder> Decoder for CompressionCodec<C> {
         &mut self,
         src: &mut BytesMut,
     ) -> std::result::Result<Option<Self::Item>, Self::Error> {
-        self.codec.decode(src)
+        use bytes::Buf;
+        use ultimaonline_net::compression::huffman;
+
+        let decompressed = match &mut self.decompressed {
+            Some(decompressed) => decompressed,
+            None => return self.codec.decode(src),
+        };
+
+        while let Some((packet, len)) = huffman::decompress(src)? {
+            decompressed.extend_from_slice(&packet);
+            src.advance(len);
+        }
+
+        // The rest of the packets can't be found after one that can't be
+        // decoded, so they are dropped along with it
+        let result = self.codec.decode(decompressed);
+        if result.is_err() {
+            decompressed.clear();
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    define_codec! {
+        Symmetric,
+        send [
+            mobile::WarMode,
+        ],
+        recv [
+            mobile::WarMode,
+        ]
+    }
+
+    const WAR_MODE_BYTES: [u8; 5] = [0x72u8, 0x01, 0x00, 0x32, 0x00];
+
+    fn war_mode() -> mobile::WarMode {
+        mobile::WarMode {
+            war: true,
+            unknown_01: [0x00, 0x32, 0x00],
+        }
+    }
+
+    #[test]
+    fn symmetric_encode() {
+        let mut dst = BytesMut::new();
+        Symmetric
+            .encode(&war_mode(), &mut dst)
+            .expect("Failed to encode packet");
+        assert_eq!(&dst[..], WAR_MODE_BYTES);
+
+        let mut dst = BytesMut::new();
+        Symmetric
+            .encode(&SymmetricFrameSend::from(war_mode()), &mut dst)
+            .expect("Failed to encode frame");
+        assert_eq!(&dst[..], WAR_MODE_BYTES);
+    }
+
+    #[test]
+    fn symmetric_decode() {
+        let mut src = BytesMut::from(&WAR_MODE_BYTES[..]);
+
+        match Symmetric.decode(&mut src).expect("Failed to decode packet") {
+            Some(SymmetricFrameRecv::WarMode(pkt)) => assert_eq!(pkt, war_mode()),
+            None => panic!("Packet was not decoded"),
+        }
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn symmetric_decode_partial() {
+        // Every byte but the last, since the fixed size excludes the id
+        let mut src = BytesMut::from(&WAR_MODE_BYTES[..4]);
+
+        assert!(Symmetric
+            .decode(&mut src)
+            .expect("Failed to decode packet")
+            .is_none());
+        assert_eq!(src.len(), 4);
+    }
+
+    #[test]
+    fn frame_packet_ids() {
+        let frame = InWorldFrameSend::from(war_mode());
+        assert_eq!(frame.packet_id(), (0x72, None));
+        assert_eq!(frame.name(), "mobile::WarMode");
+
+        let frame = InWorldFrameRecv::WindowSize(client_info::WindowSize {
+            width: 800,
+            height: 600,
+        });
+        assert_eq!(frame.packet_id(), (0xBF, Some(0x05)));
+        assert_eq!(frame.name(), "client_info::WindowSize");
+    }
+
+    #[test]
+    fn decompressing_decode() {
+        let mut src = BytesMut::new();
+        CompressionCodec::new(Symmetric)
+            .encode(&war_mode(), &mut src)
+            .expect("Failed to encode packet");
+        CompressionCodec::new(Symmetric)
+            .encode(&war_mode(), &mut src)
+            .expect("Failed to encode packet");
+        assert_ne!(&src[..WAR_MODE_BYTES.len()], WAR_MODE_BYTES);
+
+        let mut codec = CompressionCodec::decompressing(Symmetric);
+        for _ in 0..2 {
+            match codec.decode(&mut src).expect("Failed to decode packet") {
+                Some(SymmetricFrameRecv::WarMode(pkt)) => assert_eq!(pkt, war_mode()),
+                None => panic!("Packet was not decoded"),
+            }
+        }
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn compresses_each_packet_separately() {
+        let packets = [
+            war_mode(),
+            mobile::WarMode {
+                war: false,
+                ..war_mode()
+            },
+            war_mode(),
+        ];
+
+        let mut codec = CompressionCodec::new(Symmetric);
+        let mut stream = BytesMut::new();
+        for pkt in packets.iter() {
+            codec
+                .encode(pkt, &mut stream)
+                .expect("Failed to encode packet");
+        }
+
+        // The same as the packets compressed one at a time, back to back
+        let mut expected = vec![];
+        for pkt in packets.iter() {
+            let mut bytes = BytesMut::new();
+            Symmetric.encode(pkt, &mut bytes).unwrap();
+            expected.extend(ultimaonline_net::compression::huffman::compress(&bytes));
+        }
+
+        assert_eq!(&stream[..], expected.as_slice());
+    }
+
+    define_codec! {
+        Talk,
+        send [
+            chat::TalkRequest,
+        ],
+        recv [
+            chat::TalkRequest,
+        ]
+    }
+
+    #[test]
+    fn decode_split_var_packet() {
+        let talk = chat::TalkRequest::unicode(
+            chat::MessageType::Regular,
+            0x0034,
+            3,
+            "ENU",
+            "Hail, traveler",
+        );
+
+        let mut bytes = BytesMut::new();
+        Talk.encode(&talk, &mut bytes)
+            .expect("Failed to encode packet");
+
+        // Nothing is decoded until the last byte of the packet arrives
+        let mut src = BytesMut::new();
+        for (num, by) in bytes.iter().enumerate() {
+            src.extend_from_slice(&[*by]);
+            let frame = Talk.decode(&mut src).expect("Failed to decode packet");
+
+            if num + 1 < bytes.len() {
+                assert!(frame.is_none());
+            } else {
+                match frame {
+                    Some(TalkFrameRecv::TalkRequest(pkt)) => assert_eq!(pkt, talk),
+                    None => panic!("Packet was not decoded"),
+                }
+            }
+        }
+        assert!(src.is_empty());
+    }
+
+    define_codec! {
+        Extended,
+        send [],
+        recv [
+            client_info::WindowSize,
+        ]
+    }
+
+    #[test]
+    fn decode_split_extended_packet() {
+        let bytes = [
+            0xBFu8, 0x00, 0x0D, 0x00, 0x05, 0x00, 0x00, 0x03, 0x20, 0x00, 0x00, 0x02, 0x58,
+        ];
+
+        let mut src = BytesMut::new();
+        for (num, by) in bytes.iter().enumerate() {
+            src.extend_from_slice(&[*by]);
+            let frame = Extended.decode(&mut src).expect("Failed to decode packet");
+
+            if num + 1 < bytes.len() {
+                assert!(frame.is_none());
+            } else {
+                match frame {
+                    Some(ExtendedFrameRecv::WindowSize(pkt)) => assert_eq!(
+                        pkt,
+                        client_info::WindowSize {
+                            width: 800,
+                            height: 600,
+                        }
+                    ),
+                    None => panic!("Packet was not decoded"),
+                }
+            }
+        }
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn decode_rejects_short_extended_packet() {
+        // The declared size doesn't leave room for the extended id
+        let mut src = BytesMut::from(&[0xBFu8, 0x00, 0x04, 0x00, 0x05][..]);
+
+        assert!(Extended.decode(&mut src).is_err());
+    }
+
+    // WarMode is listed by both this and Symmetric, but only decoded once
+    define_codec! {
+        Included,
+        include [Symmetric, Extended],
+        send [
+            network::PingAck,
+        ],
+        recv [
+            mobile::WarMode,
+            network::PingReq,
+        ]
+    }
+
+    #[test]
+    fn includes_codecs() {
+        let mut dst = BytesMut::new();
+        Included
+            .encode(&war_mode(), &mut dst)
+            .expect("Failed to encode packet");
+        Included
+            .encode(network::PingAck { val: 7 }, &mut dst)
+            .expect("Failed to encode packet");
+        assert_eq!(&dst[..5], WAR_MODE_BYTES);
+
+        dst.extend_from_slice(&[
+            0xBFu8, 0x00, 0x0D, 0x00, 0x05, 0x00, 0x00, 0x03, 0x20, 0x00, 0x00, 0x02, 0x58,
+        ]);
+
+        match Included.decode(&mut dst).expect("Failed to decode packet") {
+            Some(IncludedFrameRecv::WarMode(pkt)) => assert_eq!(pkt, war_mode()),
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+        match Included.decode(&mut dst).expect("Failed to decode packet") {
+            Some(IncludedFrameRecv::PingReq(pkt)) => assert_eq!(pkt.val, 7),
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+        match Included.decode(&mut dst).expect("Failed to decode packet") {
+            Some(IncludedFrameRecv::WindowSize(pkt)) => assert_eq!(pkt.width, 800),
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+        assert!(dst.is_empty());
     }
 }
@@ -1,165 +1,706 @@
 use std::{
     collections::HashSet,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
         Mutex,
     },
+    time::Duration,
 };
-use tokio::sync::mpsc;
+use tokio::{sync::mpsc, time};
+use tokio_stream::{wrappers::UnboundedReceiverStream, StreamExt, StreamMap};
 use tracing::{debug, info, trace, trace_span};
 use ultimaonline_net::{
     error::{Error, Result},
-    packets::movement,
-    types::{Direction, Serial},
+    packets::{char_login, item, mobile, movement, world},
+    types::{Direction, Notoriety, Race, Serial},
 };
 
-use crate::game::client;
+use super::capabilities::Capabilities;
+use super::client::{
+    codecs::{InWorldFrameRecv, InWorldFrameSend},
+    Client, ClientSender, WorldClient,
+};
 
-use super::client::{Client, ClientReceiver, ClientSender, WorldClient};
+// Receiver of a newly connected client's packets, keyed by its client id
+type Joined = (u64, mpsc::UnboundedReceiver<InWorldFrameRecv>);
 
 struct World {
     mob_x: u16,
+    mob_y: u16,
     mob_dir: Direction,
+    mob_hits: u16,
+    mob_hits_sent: u16, // As last sent to the clients it's in range of
+    mob_items: Vec<mobile::Item>,
+    clock: WorldClock,
+}
+
+// Game time passes this many times faster than real time, so that a game
+// day lasts two real hours
+const CLOCK_SPEED: u32 = 12;
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+const DAWN_HOUR: u64 = 6;
+const DUSK_HOUR: u64 = 20;
+
+// Lower light levels are brighter
+const DAY_LIGHT: u8 = 0;
+const NIGHT_LIGHT: u8 = 26;
+
+// Each season lasts a game week, starting from summer
+const SEASON_DAYS: u64 = 7;
+const SEASONS: [world::SeasonType; 4] = [
+    world::SeasonType::Summer,
+    world::SeasonType::Fall,
+    world::SeasonType::Winter,
+    world::SeasonType::Spring,
+];
+
+// Time and date in the game world, which decide the light level and season
+#[derive(Clone, Debug)]
+struct WorldClock {
+    day: u64,
+    time: Duration, // Since midnight
+}
+
+impl WorldClock {
+    fn at(hour: u64, minute: u64, second: u64) -> Self {
+        Self {
+            day: 0,
+            time: Duration::from_secs((hour * 60 + minute) * 60 + second),
+        }
+    }
+
+    // Advances the clock by the real time elapsed, returning the new light
+    // level if it changed
+    fn advance(&mut self, elapsed: Duration) -> Option<u8> {
+        let light = self.light_level();
+
+        let time = (self.time + elapsed * CLOCK_SPEED).as_nanos();
+        self.day += (time / DAY.as_nanos()) as u64;
+        self.time = Duration::from_nanos((time % DAY.as_nanos()) as u64);
+
+        Some(self.light_level()).filter(|&level| level != light)
+    }
+
+    fn season(&self) -> world::SeasonType {
+        SEASONS[(self.day / SEASON_DAYS % SEASONS.len() as u64) as usize]
+    }
+
+    fn light_level(&self) -> u8 {
+        let hour = self.time.as_secs() / 3600;
+        if (DAWN_HOUR..DUSK_HOUR).contains(&hour) {
+            DAY_LIGHT
+        } else {
+            NIGHT_LIGHT
+        }
+    }
+
+    fn game_time(&self) -> world::GameTime {
+        let secs = self.time.as_secs();
+        world::GameTime {
+            hour: (secs / 3600) as u8,
+            minute: (secs / 60 % 60) as u8,
+            second: (secs % 60) as u8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    tick: Duration,         // Time between world updates
+    idle_timeout: Duration, // Clients which send nothing for this long are disconnected
+}
+
+impl ServerConfig {
+    // Updates are scheduled and counted in ticks, so they can't be empty
+    pub fn new(tick: Duration, idle_timeout: Duration) -> Result<Self> {
+        if tick.is_zero() {
+            return Err(Error::Message(
+                "Server tick must be longer than zero".to_string(),
+            ));
+        }
+
+        Ok(Self { tick, idle_timeout })
+    }
+
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            tick: Duration::from_secs(1),
+            idle_timeout: Duration::from_secs(180),
+        }
+    }
 }
 
 pub struct Server {
+    config: ServerConfig,
     shutdown: AtomicBool,
+    ticks: AtomicU64,
+    next_client_id: AtomicU64,
+    next_player_serial: AtomicU32,
     clients: Mutex<Vec<WorldClient>>,
     world: Mutex<World>,
+    join_send: mpsc::UnboundedSender<Joined>,
+    join_recv: Mutex<Option<mpsc::UnboundedReceiver<Joined>>>,
+}
+
+const MOB_SERIAL: Serial = 55858;
+
+// Players are given serials counting up from here
+const FIRST_PLAYER_SERIAL: Serial = 3833;
+const MOB_HITS_MAX: u16 = 100;
+
+// The mob takes a step this often, however long a tick is
+const MOB_STEP: Duration = Duration::from_secs(1);
+
+// Players gain a point of strength this often, as long as it's locked up
+// and they're under their stat cap, until skills are used to train stats
+const STAT_GAIN: Duration = Duration::from_secs(30);
+
+// TODO: Track the actual location of each player
+const PLAYER_LOCATION: (u16, u16) = (3667, 2625);
+const VIEW_RANGE: u16 = 18;
+
+impl World {
+    fn mob_in_range(&self, (x, y): (u16, u16)) -> bool {
+        self.mob_x.abs_diff(x).max(self.mob_y.abs_diff(y)) <= VIEW_RANGE
+    }
+
+    fn mob_state(&self) -> mobile::State {
+        mobile::State {
+            serial: MOB_SERIAL,
+            body: 401,
+            x: self.mob_x,
+            y: self.mob_y,
+            z: 0,
+            direction: self.mob_dir,
+            hue: 1003,
+            flags: mobile::EntityFlags::None,
+            notoriety: Notoriety::Ally,
+        }
+    }
+
+    fn mob_health(&self) -> mobile::UpdateHealth {
+        mobile::UpdateHealth {
+            serial: MOB_SERIAL,
+            max: MOB_HITS_MAX,
+            current: self.mob_hits,
+        }
+    }
+
+    fn mob_appearance(&self) -> mobile::Appearance {
+        mobile::Appearance {
+            state: self.mob_state(),
+            items: self.mob_items.clone().into(),
+        }
+    }
+
+    // Replaces whatever the mob is wearing on the same layer, taking the
+    // item off of any other layer it was on
+    fn equip_mob(&mut self, item: mobile::Item) {
+        self.mob_items
+            .retain(|worn| worn.layer != item.layer && worn.serial != item.serial);
+        self.mob_items.push(item);
+    }
+
+    // Only the items worn by the mob are known so far
+    fn find_item(&self, serial: Serial) -> Option<mobile::Item> {
+        self.mob_items
+            .iter()
+            .find(|item| item.serial == serial)
+            .copied()
+    }
+
+    // Shows everything about the entity to a client which can't see it yet,
+    // such as when it comes into range. Its appearance includes what it's
+    // wearing, unlike a bare state update.
+    fn send_full_entity(&self, client: &mut WorldClient, serial: Serial) -> Result<()> {
+        if serial == MOB_SERIAL {
+            client.send(self.mob_appearance().into())?;
+            client.send(self.mob_health().into())?;
+        } else {
+            client.send(player_appearance(serial).into())?;
+        }
+
+        client.in_range.insert(serial);
+        Ok(())
+    }
+}
+
+// How other players see a player, until characters are tracked
+fn player_appearance(serial: Serial) -> mobile::Appearance {
+    mobile::Appearance {
+        state: mobile::State {
+            serial,
+            body: 401,
+            x: PLAYER_LOCATION.0,
+            y: PLAYER_LOCATION.1,
+            z: 0,
+            direction: Direction::South,
+            hue: 1002,
+            flags: mobile::EntityFlags::None,
+            notoriety: Notoriety::Innocent,
+        },
+        items: vec![].into(),
+    }
 }
 
-const PLAYER_SERIAL: Serial = 3833;
+// Where the player's own client should show them, such as after it has lost
+// track of where they are
+fn player_update(serial: Serial) -> mobile::PlayerUpdate {
+    let state = player_appearance(serial).state;
+    mobile::PlayerUpdate {
+        serial,
+        body: state.body,
+        unknown_06: 0,
+        hue: state.hue,
+        flags: state.flags,
+        x: state.x,
+        y: state.y,
+        unknown_0e: 0,
+        direction: state.direction,
+        z: state.z,
+    }
+}
+
+// A player's status when they enter the world, until characters are tracked
+pub fn player_status(serial: Serial, name: &str, status_version: u8) -> char_login::CharStatus {
+    char_login::CharStatus {
+        serial,
+        name: name.into(),
+        hitpoints: char_login::Attribute {
+            current: 100,
+            maximum: 100,
+        },
+        renamable: false,
+        version: status_version,
+        gender: false, // Male
+        strength: 20,
+        dexterity: 20,
+        intelligence: 20,
+        stamina: char_login::Attribute {
+            current: 100,
+            maximum: 100,
+        },
+        mana: char_login::Attribute {
+            current: 100,
+            maximum: 100,
+        },
+        gold: 0,
+        phys_resist: 50,
+        weight: char_login::Attribute {
+            current: 0,
+            maximum: 100,
+        },
+        race: Race::Human,
+        stat_cap: 300,
+        follower_count: 0,
+        follower_max: 0,
+        fire_resist: 50,
+        cold_resist: 50,
+        poison_resist: 50,
+        energy_resist: 50,
+        luck: 20,
+        damage_min: 0,
+        damage_max: 0,
+        tithing_points: 0,
+        aos_stats: match status_version {
+            6 => Some([Default::default(); 15]),
+            _ => None,
+        },
+    }
+}
 
 impl Server {
-    pub fn new() -> Self {
+    pub fn new(config: ServerConfig) -> Self {
+        let (join_send, join_recv) = mpsc::unbounded_channel();
+
         Server {
+            config,
             shutdown: AtomicBool::new(false),
+            ticks: AtomicU64::new(0),
+            next_client_id: AtomicU64::new(0),
+            next_player_serial: AtomicU32::new(FIRST_PLAYER_SERIAL),
             clients: Mutex::new(vec![]),
             world: Mutex::new(World {
                 mob_x: 3668,
+                mob_y: 2625,
                 mob_dir: Direction::East,
+                mob_hits: MOB_HITS_MAX,
+                mob_hits_sent: MOB_HITS_MAX,
+                mob_items: vec![
+                    mobile::Item {
+                        serial: 0x40000001,
+                        type_id: 0x1EFD, // Fancy Shirt
+                        layer: 0x05,     // Shirt
+                        hue: 1837,
+                    },
+                    mobile::Item {
+                        serial: 0x40000002,
+                        type_id: 0x1539, // Long Pants
+                        layer: 0x04,     // Pants
+                        hue: 1897,
+                    },
+                    mobile::Item {
+                        serial: 0x40000003,
+                        type_id: 0x170B, // Boots
+                        layer: 0x03,     // Shoes
+                        hue: 1900,
+                    },
+                    mobile::Item {
+                        serial: 0x40000004,
+                        type_id: 0x1515, // Cloak
+                        layer: 0x14,     // Cloak
+                        hue: 1811,
+                    },
+                    mobile::Item {
+                        serial: 0x40000005,
+                        type_id: 0x203C, // Long hair
+                        layer: 0x0B,     // Hair
+                        hue: 1111,
+                    },
+                ],
+                clock: WorldClock::at(12, 0, 0),
             }),
+            join_send,
+            join_recv: Mutex::new(Some(join_recv)),
         }
     }
 
     pub async fn run_loop(&self) -> Result<()> {
-        use ultimaonline_net::{packets::mobile, types};
-
         let span = trace_span!("server");
         let _ = span.enter();
 
-        let mut frame = 0;
-        while !self.shutdown.load(Ordering::Relaxed) {
-            frame += 1;
-            trace!("Frame: {}", frame);
-            {
-                // Update world state
-                let mut world = self
-                    .world
-                    .lock()
-                    .map_err(|_| Error::Message("Unable to lock world".to_string()))?;
-                if (frame / 10) % 2 == 0 {
-                    world.mob_x += 1;
-                } else {
-                    world.mob_x -= 1;
+        let mut joins = self
+            .join_recv
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock joins".to_string()))?
+            .take()
+            .ok_or_else(|| Error::Message("Server loop is already running".to_string()))?;
+
+        // Packets from every client, handled as soon as they arrive rather
+        // than waiting for the next tick
+        let mut inputs = StreamMap::new();
+
+        // Unlike sleeping between ticks, the interval doesn't drift by
+        // however long each tick takes to process
+        let mut interval = time::interval(self.config.tick);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if self.shutdown.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    let frame = self.ticks.fetch_add(1, Ordering::Relaxed) + 1;
+                    trace!("Frame: {}", frame);
+                    for id in self.update(frame)? {
+                        inputs.remove(&id);
+                    }
                 }
 
-                if frame % 10 == 0 {
-                    world.mob_dir = match world.mob_dir {
-                        Direction::East => Direction::West,
-                        Direction::West => Direction::East,
-                        _ => Direction::East,
-                    };
+                Some((id, receiver)) = joins.recv() => {
+                    inputs.insert(id, UnboundedReceiverStream::new(receiver));
                 }
 
-                let mut clients = self
-                    .clients
-                    .lock()
-                    .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?;
+                Some((id, packet)) = inputs.next(), if !inputs.is_empty() => {
+                    self.handle_packet(id, packet)?;
+                }
+            }
+        }
 
-                let mut closed_clients = HashSet::<usize>::new();
+        // Dropping the clients closes their channels once they've received
+        // everything already sent to them, so that each connection can send
+        // the rest of its packets before disconnecting
+        self.clients
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?
+            .clear();
 
-                // Receive client packets
-                for (i, client) in clients.iter_mut().enumerate() {
-                    if client.sender.is_closed() {
-                        closed_clients.insert(i);
-                        continue;
-                    }
+        info!("Server shutting down.");
+        Ok(())
+    }
 
-                    loop {
-                        match client.recv()? {
-                            None => break,
-                            Some(client::codecs::InWorldFrameRecv::Request(req)) => {
-                                // Always succeed for now
-                                client.send(
-                                    movement::Success {
-                                        sequence: req.sequence,
-                                        notoriety: types::Notoriety::Ally,
-                                    }
-                                    .into(),
-                                )?;
-                            }
-                            _ => {} // Skip everything
-                        }
-                    }
+    // Returns the ids of the clients which were removed
+    fn update(&self, frame: u64) -> Result<Vec<u64>> {
+        use ultimaonline_net::packets::entity;
+
+        let mob_step_ticks = (MOB_STEP.as_nanos() / self.config.tick.as_nanos()).max(1) as u64;
+        let stat_gain_ticks = (STAT_GAIN.as_nanos() / self.config.tick.as_nanos()).max(1) as u64;
+
+        // Update world state
+        let mut world = self
+            .world
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock world".to_string()))?;
+        if frame.is_multiple_of(mob_step_ticks) {
+            let step = frame / mob_step_ticks;
+            if (step / 10).is_multiple_of(2) {
+                world.mob_x += 1;
+            } else {
+                world.mob_x -= 1;
+            }
+
+            if step.is_multiple_of(10) {
+                world.mob_dir = world.mob_dir.opposite();
+            }
+        }
+        let season = world.clock.season();
+        let light = world.clock.advance(self.config.tick);
+        let season = Some(world.clock.season()).filter(|&changed| changed != season);
+
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?;
+
+        // Remove the players of closed clients from the world, and from the
+        // view of everyone who could see them
+        let (mut closed_ids, mut closed_serials) = (vec![], HashSet::<Serial>::new());
+        clients.retain(|client| {
+            if client.is_closed() {
+                closed_ids.push(client.id);
+                closed_serials.insert(client.serial);
+            }
+            !client.is_closed()
+        });
+
+        for client in clients.iter_mut() {
+            for serial in closed_serials.iter() {
+                if client.in_range.remove(serial) {
+                    client.send(entity::DeleteObject { serial: *serial }.into())?;
                 }
+            }
+        }
+
+        // Every player is outdoors, so they all see dawn and dusk
+        if let Some(level) = light {
+            debug!("Light level changed to {}", level);
+            for client in clients.iter_mut() {
+                client.send(world.clock.game_time().into())?;
+                client.send(world::WorldLightLevel { level }.into())?;
+            }
+        }
 
-                for (i, client) in clients.iter_mut().enumerate() {
-                    if client.sender.is_closed() {
-                        closed_clients.insert(i);
-                        continue;
+        if let Some(season) = season {
+            debug!("Season changed to {:?}", season);
+            for client in clients.iter_mut() {
+                client.send(
+                    world::Season {
+                        season,
+                        play_sound: true,
                     }
+                    .into(),
+                )?;
+            }
+        }
 
-                    client.send(
-                        mobile::State {
-                            serial: 55858,
-                            body: 401,
-                            x: world.mob_x,
-                            y: 2625,
-                            z: 0,
-                            direction: world.mob_dir,
-                            hue: 1003,
-                            flags: mobile::EntityFlags::None,
-                            notoriety: types::Notoriety::Ally,
+        if frame.is_multiple_of(stat_gain_ticks) {
+            for client in clients.iter_mut() {
+                if client.stat_locks.strength == char_login::SkillLock::Up {
+                    client.update_status(|status| {
+                        let total = status.strength + status.dexterity + status.intelligence;
+                        if total < status.stat_cap {
+                            status.strength += 1;
                         }
-                        .into(),
-                    )?;
+                    })?;
                 }
+            }
+        }
 
-                let mut closed_clients: Vec<&usize> = closed_clients.iter().collect();
-                closed_clients.sort();
-                closed_clients.reverse();
-                for i in closed_clients {
-                    clients.remove(*i);
+        // Clients which see the mob come into range are sent its health along
+        // with everything else, so only those already in range need changes
+        let mob_in_range = world.mob_in_range(PLAYER_LOCATION);
+        let health_changed = world.mob_hits != world.mob_hits_sent;
+        world.mob_hits_sent = world.mob_hits;
+        for client in clients.iter_mut() {
+            match (mob_in_range, client.in_range.contains(&MOB_SERIAL)) {
+                (true, true) => {
+                    client.send(world.mob_state().into())?;
+                    if health_changed {
+                        client.send(world.mob_health().into())?;
+                    }
                 }
+                (true, false) => world.send_full_entity(client, MOB_SERIAL)?,
+                (false, true) => {
+                    client.send(entity::DeleteObject { serial: MOB_SERIAL }.into())?;
+                    client.in_range.remove(&MOB_SERIAL);
+                }
+                (false, false) => {}
             }
+        }
+
+        Ok(closed_ids)
+    }
 
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    // Puts the item on the mobile, showing it to everyone who can see them.
+    // Only the mob's equipment is tracked so far.
+    pub fn equip(&self, mobile: Serial, item: mobile::Item) -> Result<()> {
+        if mobile != MOB_SERIAL {
+            return Err(Error::Message(format!(
+                "Equipment of mobile {} isn't tracked",
+                mobile
+            )));
         }
 
+        self.world
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock world".to_string()))?
+            .equip_mob(item);
+
         for client in self
             .clients
             .lock()
             .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?
             .iter_mut()
         {
-            client.close();
+            if client.in_range.contains(&mobile) {
+                client.send(mobile::WornItem::new(mobile, &item).into())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Changes the player's status, sending it to their client if it changed
+    pub fn adjust_status(
+        &self,
+        serial: Serial,
+        change: impl FnOnce(&mut char_login::CharStatus),
+    ) -> Result<()> {
+        self.clients
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?
+            .iter_mut()
+            .find(|client| client.serial == serial && !client.is_closed())
+            .ok_or_else(|| Error::Message(format!("Player {} isn't in the world", serial)))?
+            .update_status(change)
+    }
+
+    fn handle_packet(&self, id: u64, packet: InWorldFrameRecv) -> Result<()> {
+        // Equipping shows the item to everyone who can see the mobile, so
+        // it's handled before the client is looked up
+        if let InWorldFrameRecv::EquipRequest(req) = packet {
+            return self.handle_equip(id, req);
+        }
+
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?;
+        let client = match clients.iter_mut().find(|client| client.id == id) {
+            Some(client) if !client.is_closed() => client,
+            _ => return Ok(()),
+        };
+
+        // Skip everything except movement, stat locks and equipping for now
+        match packet {
+            InWorldFrameRecv::Request(req) => {
+                // Any move in sequence succeeds for now
+                if client.movement.accept(req.sequence) {
+                    client.send(
+                        movement::Success {
+                            sequence: req.sequence,
+                            notoriety: Notoriety::Ally,
+                        }
+                        .into(),
+                    )?;
+                } else {
+                    client.send(
+                        movement::Reject {
+                            sequence: req.sequence,
+                            x: PLAYER_LOCATION.0,
+                            y: PLAYER_LOCATION.1,
+                            movement: Direction::South.into(),
+                            z: 0,
+                        }
+                        .into(),
+                    )?;
+                }
+            }
+            // The client starts counting its moves again after a resync
+            InWorldFrameRecv::Resync(_) => {
+                client.movement.reset();
+                client.send(player_update(client.serial).into())?;
+            }
+            InWorldFrameRecv::StatLockChange(req) => {
+                client.stat_locks.set(req.stat, req.lock);
+                client.send(
+                    char_login::ExtendedStats {
+                        kind: 0x02, // Stat locks
+                        serial: client.serial,
+                        unknown_05: 0x00,
+                        locks: client.stat_locks,
+                    }
+                    .into(),
+                )?;
+            }
+            _ => {}
         }
 
-        info!("Server shutting down.");
         Ok(())
     }
 
-    pub fn new_client(&self) -> Result<Client> {
+    // Puts the item on the mobile for the client, if the world knows of them
+    // both, otherwise telling the client that it couldn't be equipped
+    fn handle_equip(&self, id: u64, req: item::EquipRequest) -> Result<()> {
+        let found = self
+            .world
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock world".to_string()))?
+            .find_item(req.serial);
+
+        match found {
+            Some(found) if req.mobile == MOB_SERIAL => self.equip(
+                req.mobile,
+                mobile::Item {
+                    layer: req.layer,
+                    ..found
+                },
+            ),
+            _ => {
+                debug!(
+                    "Unable to equip item {} on mobile {}",
+                    req.serial, req.mobile
+                );
+                let mut clients = self
+                    .clients
+                    .lock()
+                    .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?;
+                match clients.iter_mut().find(|client| client.id == id) {
+                    Some(client) if !client.is_closed() => client.send(
+                        item::DropReject {
+                            reason: item::DropRejectReason::Unspecified,
+                        }
+                        .into(),
+                    ),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    // Serial for a newly logged in player's character, which no other player has
+    pub fn new_player_serial(&self) -> Serial {
+        self.next_player_serial.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn new_client(&self, status: char_login::CharStatus) -> Result<Client> {
         let (output_send, output_recv) =
             mpsc::unbounded_channel::<<WorldClient as ClientSender>::SendItem>();
-        let (input_send, input_recv) =
-            mpsc::unbounded_channel::<<WorldClient as ClientReceiver>::RecvItem>();
+        let (input_send, input_recv) = mpsc::unbounded_channel::<InWorldFrameRecv>();
 
-        let mut client = WorldClient {
-            sender: output_send,
-            receiver: input_recv,
-        };
+        let id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+        let mut client = WorldClient::new(id, status, output_send);
 
         self.enter_world(&mut client)?;
         debug!("Client completed enter world.");
@@ -169,6 +710,10 @@ impl Server {
             .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?
             .push(client);
 
+        self.join_send
+            .send((id, input_recv))
+            .map_err(|_| Error::Message("Server loop has stopped".to_string()))?;
+
         Ok(Client {
             sender: input_send,
             receiver: output_recv,
@@ -176,77 +721,602 @@ impl Server {
     }
 
     fn enter_world(&self, client: &mut WorldClient) -> Result<()> {
-        use ultimaonline_net::{packets::*, types};
+        use ultimaonline_net::packets::*;
 
         client.send(
             mobile::MobLightLevel {
-                serial: PLAYER_SERIAL,
+                serial: client.serial,
                 level: 30,
             }
             .into(),
         )?;
 
-        client.send(world::WorldLightLevel { level: 30 }.into())?;
-
         let world = self
             .world
             .lock()
             .map_err(|_| Error::Message("Unable to lock world".to_string()))?;
 
+        client.send(world.clock.game_time().into())?;
         client.send(
-            mobile::Appearance {
-                state: mobile::State {
-                    serial: 55858,
-                    body: 401,
-                    x: world.mob_x,
-                    y: 2625,
-                    z: 0,
-                    direction: world.mob_dir,
-                    hue: 1003,
-                    flags: mobile::EntityFlags::None,
-                    notoriety: types::Notoriety::Ally,
-                },
-                items: vec![
-                    mobile::Item {
-                        serial: 0x40000001,
-                        type_id: 0x1EFD, // Fancy Shirt
-                        layer: 0x05,     // Shirt
-                        hue: 1837,
-                    },
-                    mobile::Item {
-                        serial: 0x40000002,
-                        type_id: 0x1539, // Long Pants
-                        layer: 0x04,     // Pants
-                        hue: 1897,
-                    },
-                    mobile::Item {
-                        serial: 0x40000003,
-                        type_id: 0x170B, // Boots
-                        layer: 0x04,     // Shoes
-                        hue: 1900,
-                    },
-                    mobile::Item {
-                        serial: 0x40000004,
-                        type_id: 0x1515, // Cloak
-                        layer: 0x14,     // Cloak
-                        hue: 1811,
-                    },
-                    mobile::Item {
-                        serial: 0x40000005,
-                        type_id: 0x203C, // Long hair
-                        layer: 0x0B,     // Hair
-                        hue: 1111,
-                    },
-                ]
-                .into(),
+            world::Season {
+                season: world.clock.season(),
+                play_sound: false,
+            }
+            .into(),
+        )?;
+        client.send(
+            world::WorldLightLevel {
+                level: world.clock.light_level(),
             }
             .into(),
         )?;
 
+        if world.mob_in_range(PLAYER_LOCATION) {
+            world.send_full_entity(client, MOB_SERIAL)?;
+        }
+
+        // Every player is at the same location for now, so they can all see
+        // each other
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|_| Error::Message("Unable to lock clients vec".to_string()))?;
+        for other in clients.iter_mut() {
+            if other.serial == client.serial || other.is_closed() {
+                continue;
+            }
+
+            world.send_full_entity(other, client.serial)?;
+            world.send_full_entity(client, other.serial)?;
+        }
+
         Ok(())
     }
 
+    // The packet for showing the item in the layout the client understands
+    pub fn world_item(caps: &Capabilities, item: item::WorldItem) -> InWorldFrameSend {
+        if caps.world_item_v2 {
+            item.into()
+        } else {
+            item::WorldItemLegacy::from(item).into()
+        }
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
     pub fn shutdown(&self) {
         self.shutdown.store(true, Ordering::Relaxed)
     }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::Relaxed)
+    }
+
+    // Number of ticks the world has been updated for
+    pub fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::client::ClientReceiver;
+    use std::sync::Arc;
+    use ultimaonline_net::packets::movement;
+
+    #[test]
+    fn rejects_zero_tick() {
+        assert!(ServerConfig::new(Duration::ZERO, Duration::from_secs(60)).is_err());
+        assert!(ServerConfig::new(Duration::from_millis(1), Duration::from_secs(60)).is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn runs_ticks_on_schedule() {
+        const TICKS: u32 = 25;
+        let tick = Duration::from_millis(100);
+
+        let server = Arc::new(Server::new(
+            ServerConfig::new(tick, ServerConfig::default().idle_timeout()).unwrap(),
+        ));
+        let start = time::Instant::now();
+        let task = {
+            let server = server.clone();
+            tokio::spawn(async move { server.run_loop().await })
+        };
+
+        // The first tick happens immediately, so stop just before tick N + 1
+        time::sleep(tick * TICKS - tick / 2).await;
+        server.shutdown();
+        task.await.unwrap().unwrap();
+
+        assert_eq!(server.ticks(), TICKS as u64);
+        assert_eq!(start.elapsed(), tick * TICKS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn handles_packets_within_tick() {
+        let tick = Duration::from_secs(1);
+        let server = Arc::new(Server::new(
+            ServerConfig::new(tick, ServerConfig::default().idle_timeout()).unwrap(),
+        ));
+        let task = {
+            let server = server.clone();
+            tokio::spawn(async move { server.run_loop().await })
+        };
+
+        let mut client = server.new_client(player(1001)).unwrap();
+        time::sleep(tick / 2).await;
+        drain(&mut client);
+
+        // The third move is out of sequence
+        for sequence in [0, 1, 7] {
+            client
+                .send(InWorldFrameRecv::Request(movement::Request {
+                    movement: Direction::East.into(),
+                    sequence,
+                    auth_token: 0,
+                }))
+                .unwrap();
+        }
+        time::sleep(tick / 10).await;
+
+        assert_eq!(server.ticks(), 1);
+        let responses = drain(&mut client)
+            .into_iter()
+            .filter_map(|frame| match frame {
+                InWorldFrameSend::Success(pkt) => Some((pkt.sequence, true)),
+                InWorldFrameSend::Reject(pkt) => Some((pkt.sequence, false)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(responses, [(0, true), (1, true), (7, false)]);
+
+        server.shutdown();
+        task.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn gives_players_their_own_serials() {
+        let server = Server::new(Default::default());
+        let mut first = server
+            .new_client(player(server.new_player_serial()))
+            .unwrap();
+        let mut second = server
+            .new_client(player(server.new_player_serial()))
+            .unwrap();
+
+        let serials = server
+            .clients
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|client| client.serial)
+            .collect::<Vec<_>>();
+        assert_eq!(serials.len(), 2);
+        assert_ne!(serials[0], serials[1]);
+
+        // Each player is shown to the other as a separate mobile
+        let appearances = |frames: Vec<InWorldFrameSend>| {
+            frames
+                .into_iter()
+                .filter_map(|frame| match frame {
+                    InWorldFrameSend::Appearance(pkt) if pkt.state.serial != MOB_SERIAL => {
+                        Some(pkt.state.serial)
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(appearances(drain(&mut first)), [serials[1]]);
+        assert_eq!(appearances(drain(&mut second)), [serials[0]]);
+    }
+
+    fn player(serial: Serial) -> char_login::CharStatus {
+        player_status(serial, "Tester", 6)
+    }
+
+    fn drain(client: &mut Client) -> Vec<InWorldFrameSend> {
+        let mut frames = vec![];
+        while let Some(frame) = client.recv().unwrap() {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    fn deleted(frames: &[InWorldFrameSend]) -> Vec<Serial> {
+        frames
+            .iter()
+            .filter_map(|frame| match frame {
+                InWorldFrameSend::DeleteObject(pkt) => Some(pkt.serial),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn light_levels(frames: &[InWorldFrameSend]) -> Vec<u8> {
+        frames
+            .iter()
+            .filter_map(|frame| match frame {
+                InWorldFrameSend::WorldLightLevel(pkt) => Some(pkt.level),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn advances_clock() {
+        let mut clock = WorldClock::at(5, 59, 0);
+        assert_eq!(clock.light_level(), NIGHT_LIGHT);
+
+        // Five real seconds is a game minute
+        assert_eq!(clock.advance(Duration::from_secs(4)), None);
+        assert_eq!(clock.advance(Duration::from_secs(1)), Some(DAY_LIGHT));
+        assert_eq!(clock.advance(Duration::from_secs(1)), None);
+
+        let time = clock.game_time();
+        assert_eq!((time.hour, time.minute, time.second), (6, 0, 12));
+
+        // Past midnight
+        let mut clock = WorldClock::at(23, 59, 50);
+        assert_eq!(clock.advance(Duration::from_secs(1)), None);
+        let time = clock.game_time();
+        assert_eq!((time.hour, time.minute, time.second), (0, 0, 2));
+    }
+
+    #[test]
+    fn sends_light_level_at_dusk() {
+        let server = Server::new(Default::default());
+        let mut client = server.new_client(player(1001)).unwrap();
+        assert_eq!(light_levels(&drain(&mut client)), [DAY_LIGHT]);
+
+        server.world.lock().unwrap().clock = WorldClock::at(19, 59, 55);
+        server.update(1).unwrap();
+        assert_eq!(light_levels(&drain(&mut client)), [NIGHT_LIGHT]);
+
+        // Only changes are sent
+        server.update(2).unwrap();
+        assert_eq!(light_levels(&drain(&mut client)), Vec::<u8>::new());
+    }
+
+    fn seasons(frames: &[InWorldFrameSend]) -> Vec<world::SeasonType> {
+        frames
+            .iter()
+            .filter_map(|frame| match frame {
+                InWorldFrameSend::Season(pkt) => Some(pkt.season),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sends_season_as_days_pass() {
+        let server = Server::new(Default::default());
+        let mut client = server.new_client(player(1001)).unwrap();
+        assert_eq!(seasons(&drain(&mut client)), [world::SeasonType::Summer]);
+
+        server.world.lock().unwrap().clock = WorldClock {
+            day: SEASON_DAYS - 1,
+            ..WorldClock::at(23, 59, 55)
+        };
+        server.update(1).unwrap();
+        assert_eq!(seasons(&drain(&mut client)), [world::SeasonType::Fall]);
+
+        // Only changes are sent
+        server.update(2).unwrap();
+        assert_eq!(seasons(&drain(&mut client)), Vec::new());
+
+        // Back to the start once the year is over
+        let mut clock = WorldClock {
+            day: SEASON_DAYS * 4 - 1,
+            ..WorldClock::at(23, 59, 55)
+        };
+        assert_eq!(clock.season(), world::SeasonType::Spring);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(clock.season(), world::SeasonType::Summer);
+    }
+
+    #[test]
+    fn sends_equipped_item() {
+        let server = Server::new(Default::default());
+        let mut client = server.new_client(player(1001)).unwrap();
+        drain(&mut client);
+
+        let hat = mobile::Item {
+            serial: 0x40000006,
+            type_id: 0x1715, // Cap
+            layer: 0x06,     // Helm
+            hue: 1150,
+        };
+        server.equip(MOB_SERIAL, hat).unwrap();
+
+        let worn = drain(&mut client)
+            .into_iter()
+            .filter_map(|frame| match frame {
+                InWorldFrameSend::WornItem(pkt) => Some(pkt),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(worn, [mobile::WornItem::new(MOB_SERIAL, &hat)]);
+
+        // Players who arrive later see it as part of the mob's appearance
+        let mut later = server.new_client(player(1002)).unwrap();
+        let items = drain(&mut later)
+            .into_iter()
+            .find_map(|frame| match frame {
+                InWorldFrameSend::Appearance(pkt) if pkt.state.serial == MOB_SERIAL => {
+                    Some(Vec::from(pkt.items))
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(items.last(), Some(&hat));
+
+        assert!(server.equip(1001, hat).is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn handles_equip_request() {
+        let tick = Duration::from_secs(1);
+        let server = Arc::new(Server::new(
+            ServerConfig::new(tick, ServerConfig::default().idle_timeout()).unwrap(),
+        ));
+        let task = {
+            let server = server.clone();
+            tokio::spawn(async move { server.run_loop().await })
+        };
+
+        let mut client = server.new_client(player(1001)).unwrap();
+        time::sleep(tick / 2).await;
+        drain(&mut client);
+
+        // Moving the mob's cloak onto its shirt layer
+        let cloak = server.world.lock().unwrap().mob_items[3];
+        for mobile in [MOB_SERIAL, 1001] {
+            client
+                .send(InWorldFrameRecv::EquipRequest(item::EquipRequest {
+                    serial: cloak.serial,
+                    layer: 0x05,
+                    mobile,
+                }))
+                .unwrap();
+        }
+        time::sleep(tick / 10).await;
+        assert_eq!(server.ticks(), 1);
+
+        let frames = drain(&mut client);
+        let worn = mobile::Item {
+            layer: 0x05,
+            ..cloak
+        };
+        assert!(matches!(
+            frames.as_slice(),
+            [
+                InWorldFrameSend::WornItem(pkt),
+                InWorldFrameSend::DropReject(reject),
+            ] if *pkt == mobile::WornItem::new(MOB_SERIAL, &worn)
+                && reject.reason == item::DropRejectReason::Unspecified
+        ));
+
+        let items = server.world.lock().unwrap().mob_items.clone();
+        assert_eq!(
+            items
+                .iter()
+                .filter(|item| item.serial == cloak.serial)
+                .count(),
+            1
+        );
+        assert!(!items.iter().any(|item| item.layer == 0x14));
+
+        server.shutdown();
+        task.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn resends_mob_entering_range() {
+        let server = Server::new(Default::default());
+        let mut client = server.new_client(player(1001)).unwrap();
+        drain(&mut client);
+
+        server.world.lock().unwrap().mob_x = PLAYER_LOCATION.0 + VIEW_RANGE * 2;
+        server.update(1).unwrap();
+        assert_eq!(deleted(&drain(&mut client)), [MOB_SERIAL]);
+
+        // Coming back into range shows everything about the mob again
+        server.world.lock().unwrap().mob_x = PLAYER_LOCATION.0;
+        server.update(2).unwrap();
+        let world = server.world.lock().unwrap();
+        match drain(&mut client).as_slice() {
+            [InWorldFrameSend::Appearance(appearance), InWorldFrameSend::UpdateHealth(health)] => {
+                assert_eq!(*appearance, world.mob_appearance());
+                assert_eq!(*health, world.mob_health());
+            }
+            frames => panic!("Mob was not shown in full: {:?}", frames.len()),
+        }
+        drop(world);
+
+        // Only its state is sent while it stays in range
+        server.update(3).unwrap();
+        assert!(matches!(
+            drain(&mut client).as_slice(),
+            [InWorldFrameSend::State(_)]
+        ));
+    }
+
+    #[test]
+    fn sends_mob_health_changes() {
+        let server = Server::new(Default::default());
+        let mut client = server.new_client(player(1001)).unwrap();
+        drain(&mut client);
+
+        server.world.lock().unwrap().mob_hits = 40;
+        server.update(1).unwrap();
+        match drain(&mut client).as_slice() {
+            [InWorldFrameSend::State(_), InWorldFrameSend::UpdateHealth(health)] => {
+                assert_eq!(health.current, 40);
+            }
+            frames => panic!("Mob health was not sent: {:?}", frames.len()),
+        }
+
+        // Nothing follows the state when the health stays the same
+        server.update(2).unwrap();
+        assert!(matches!(
+            drain(&mut client).as_slice(),
+            [InWorldFrameSend::State(_)]
+        ));
+    }
+
+    #[test]
+    fn picks_world_item_layout() {
+        let item = item::WorldItem {
+            unknown_00: 0x0001,
+            kind: item::WorldItemKind::Item,
+            serial: 0x40000123,
+            graphic: 0x0EED, // Gold coin
+            graphic_inc: 0,
+            amount: 100,
+            amount_max: 100,
+            x: PLAYER_LOCATION.0,
+            y: PLAYER_LOCATION.1,
+            z: 0,
+            direction: Direction::North,
+            hue: 0,
+            flags: 0x20, // Movable
+            unknown_17: 0,
+        };
+
+        let caps = Capabilities::for_version(&"7.0.15.1".parse().unwrap());
+        assert!(matches!(
+            Server::world_item(&caps, item.clone()),
+            InWorldFrameSend::WorldItem(pkt) if pkt == item
+        ));
+
+        let caps = Capabilities::for_version(&"5.0.9.1".parse().unwrap());
+        assert!(matches!(
+            Server::world_item(&caps, item.clone()),
+            InWorldFrameSend::WorldItemLegacy(pkt) if pkt.item == item
+        ));
+    }
+
+    #[test]
+    fn gains_stats() {
+        let server = Server::new(Default::default());
+        let mut client = server.new_client(player(1001)).unwrap();
+        drain(&mut client);
+
+        let strength = |frames: Vec<InWorldFrameSend>| {
+            frames
+                .into_iter()
+                .filter_map(|frame| match frame {
+                    InWorldFrameSend::CharStatus(pkt) => Some(pkt.strength),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let gain = STAT_GAIN.as_secs();
+        server.update(gain - 1).unwrap();
+        assert_eq!(strength(drain(&mut client)), Vec::<char_login::Stat>::new());
+        server.update(gain).unwrap();
+        assert_eq!(strength(drain(&mut client)), [21]);
+
+        // Locking the stat stops it from going up
+        server
+            .handle_packet(
+                0,
+                InWorldFrameRecv::StatLockChange(char_login::StatLockChange {
+                    stat: char_login::StatType::Strength,
+                    lock: char_login::SkillLock::Locked,
+                }),
+            )
+            .unwrap();
+        assert!(matches!(
+            drain(&mut client).as_slice(),
+            [InWorldFrameSend::ExtendedStats(pkt)]
+                if pkt.locks.strength == char_login::SkillLock::Locked
+        ));
+        server.update(gain * 2).unwrap();
+        assert_eq!(strength(drain(&mut client)), Vec::<char_login::Stat>::new());
+
+        // The server can still change it
+        server
+            .adjust_status(1001, |status| status.strength = 50)
+            .unwrap();
+        assert_eq!(strength(drain(&mut client)), [50]);
+        server.adjust_status(1001, |_| {}).unwrap();
+        assert_eq!(strength(drain(&mut client)), Vec::<char_login::Stat>::new());
+        assert!(server.adjust_status(1002, |_| {}).is_err());
+    }
+
+    #[test]
+    fn resyncs_position() {
+        let server = Server::new(Default::default());
+        let mut client = server.new_client(player(1001)).unwrap();
+        drain(&mut client);
+
+        let request = |sequence| {
+            InWorldFrameRecv::Request(movement::Request {
+                movement: Direction::East.into(),
+                sequence,
+                auth_token: 0,
+            })
+        };
+        server.handle_packet(0, request(0)).unwrap();
+        server.handle_packet(0, request(1)).unwrap();
+        drain(&mut client);
+
+        server
+            .handle_packet(
+                0,
+                InWorldFrameRecv::Resync(movement::Resync { unknown_00: 0 }),
+            )
+            .unwrap();
+        assert!(matches!(
+            drain(&mut client).as_slice(),
+            [InWorldFrameSend::PlayerUpdate(pkt)]
+                if pkt.serial == 1001 && (pkt.x, pkt.y) == PLAYER_LOCATION
+        ));
+
+        // Moves are counted from the start again
+        server.handle_packet(0, request(0)).unwrap();
+        assert!(matches!(
+            drain(&mut client).as_slice(),
+            [InWorldFrameSend::Success(pkt)] if pkt.sequence == 0
+        ));
+    }
+
+    #[test]
+    fn removes_closed_clients() {
+        let server = Server::new(Default::default());
+        let first = server.new_client(player(1001)).unwrap();
+        let mut second = server.new_client(player(1002)).unwrap();
+        let mut third = server.new_client(player(1003)).unwrap();
+
+        // Each player is shown to the players who were already in the world
+        let appearances = drain(&mut second)
+            .into_iter()
+            .filter_map(|frame| match frame {
+                InWorldFrameSend::Appearance(pkt) => Some(pkt.state.serial),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(appearances, [MOB_SERIAL, 1001, 1003]);
+        drain(&mut third);
+
+        drop(first);
+        server.update(1).unwrap();
+
+        assert_eq!(deleted(&drain(&mut second)), [1001]);
+        assert_eq!(deleted(&drain(&mut third)), [1001]);
+
+        // Closing from the world side removes the player as well
+        server.clients.lock().unwrap()[0].close();
+        server.update(2).unwrap();
+
+        assert_eq!(deleted(&drain(&mut third)), [1002]);
+        assert!(second.receiver.try_recv().is_err());
+        assert_eq!(server.clients.lock().unwrap().len(), 1);
+    }
 }
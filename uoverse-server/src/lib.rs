@@ -1,6 +1,10 @@
 #![feature(trait_alias)]
 
+pub mod config;
 pub mod game;
 pub mod login;
+pub mod proxy;
+pub mod tickets;
+pub mod trace;
 
 extern crate uoverse_server_macros as macros;
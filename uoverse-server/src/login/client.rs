@@ -4,6 +4,8 @@ use tokio_stream::StreamExt;
 use tokio_util::codec::Framed;
 use ultimaonline_net::{error::Result, packets::Packet};
 
+use crate::trace::TraceCodec;
+
 pub trait AsyncIo = AsyncRead + AsyncWrite + Unpin + Send + Sync;
 
 // Ensures that the FSM must start with the initial state (Connected)
@@ -11,7 +13,7 @@ struct LoginSequencer;
 
 pub struct Connected<Io: AsyncIo> {
     sequencer: LoginSequencer,
-    framer: Framed<Io, codecs::Connected>,
+    framer: Framed<Io, TraceCodec<codecs::Connected>>,
 }
 
 impl<Io: AsyncIo> Connected<Io> {
@@ -22,14 +24,14 @@ impl<Io: AsyncIo> Connected<Io> {
     pub fn new(io: Io) -> Self {
         Self {
             sequencer: LoginSequencer {},
-            framer: Framed::new(io, codecs::Connected {}),
+            framer: Framed::new(io, TraceCodec::new(codecs::Connected {})),
         }
     }
 }
 
 pub struct Hello<Io: AsyncIo> {
     sequencer: LoginSequencer,
-    framer: Framed<Io, codecs::Hello>,
+    framer: Framed<Io, TraceCodec<codecs::Hello>>,
 }
 
 impl<Io: AsyncIo> Hello<Io> {
@@ -42,14 +44,14 @@ impl<Io: AsyncIo> From<Connected<Io>> for Hello<Io> {
     fn from(val: Connected<Io>) -> Self {
         Self {
             sequencer: val.sequencer,
-            framer: val.framer.map_codec(|_| codecs::Hello),
+            framer: val.framer.map_codec(|_| TraceCodec::new(codecs::Hello)),
         }
     }
 }
 
 pub struct Login<Io: AsyncIo> {
     sequencer: LoginSequencer,
-    framer: Framed<Io, codecs::Login>,
+    framer: Framed<Io, TraceCodec<codecs::Login>>,
 }
 
 impl<Io: AsyncIo> Login<Io> {
@@ -67,14 +69,14 @@ impl<Io: AsyncIo> From<Hello<Io>> for Login<Io> {
     fn from(val: Hello<Io>) -> Self {
         Self {
             sequencer: val.sequencer,
-            framer: val.framer.map_codec(|_| codecs::Login),
+            framer: val.framer.map_codec(|_| TraceCodec::new(codecs::Login)),
         }
     }
 }
 
 pub struct ServerSelect<Io: AsyncIo> {
     sequencer: LoginSequencer,
-    framer: Framed<Io, codecs::ServerSelect>,
+    framer: Framed<Io, TraceCodec<codecs::ServerSelect>>,
 }
 
 impl<Io: AsyncIo> ServerSelect<Io> {
@@ -87,7 +89,9 @@ impl<Io: AsyncIo> From<Login<Io>> for ServerSelect<Io> {
     fn from(val: Login<Io>) -> Self {
         Self {
             sequencer: val.sequencer,
-            framer: val.framer.map_codec(|_| codecs::ServerSelect),
+            framer: val
+                .framer
+                .map_codec(|_| TraceCodec::new(codecs::ServerSelect)),
         }
     }
 }
@@ -95,7 +99,7 @@ impl<Io: AsyncIo> From<Login<Io>> for ServerSelect<Io> {
 pub struct Handoff<Io: AsyncIo> {
     #[allow(dead_code)] // This is a terminal state
     sequencer: LoginSequencer,
-    framer: Framed<Io, codecs::Handoff>,
+    framer: Framed<Io, TraceCodec<codecs::Handoff>>,
 }
 
 impl<Io: AsyncIo> Handoff<Io> {
@@ -113,7 +117,7 @@ impl<Io: AsyncIo> From<ServerSelect<Io>> for Handoff<Io> {
     fn from(val: ServerSelect<Io>) -> Self {
         Self {
             sequencer: val.sequencer,
-            framer: val.framer.map_codec(|_| codecs::Handoff),
+            framer: val.framer.map_codec(|_| TraceCodec::new(codecs::Handoff)),
         }
     }
 }
@@ -143,6 +147,7 @@ pub mod codecs {
         send [
             login::LoginRejection,
             login::ServerList,
+            login::ServerListV6,
         ],
         recv []
     }
@@ -163,3 +168,58 @@ pub mod codecs {
         recv []
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use ultimaonline_net::{packets::login, types::FixedStr};
+
+    fn fixed_str(text: &str) -> [u8; 30] {
+        let mut bytes = [0u8; 30];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        bytes
+    }
+
+    #[tokio::test]
+    async fn decodes_buffered_packets() {
+        // The client sends its hello and login together, in one write
+        let mut bytes = vec![
+            0xEFu8, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x0F, 0x00, 0x00, 0x00, 0x01,
+        ];
+        bytes.push(0x80);
+        bytes.extend_from_slice(&fixed_str("admin"));
+        bytes.extend_from_slice(&fixed_str("hunter2"));
+        bytes.push(0x00);
+
+        let (mut client, server) = tokio::io::duplex(256);
+        client.write_all(&bytes).await.unwrap();
+        drop(client);
+
+        let mut state = Connected::new(server);
+        let codecs::ConnectedFrameRecv::ClientHello(hello) = state
+            .recv()
+            .await
+            .expect("Failed to decode hello")
+            .expect("Hello was not decoded");
+        assert_eq!(hello.seed, 0x12345678);
+        assert_eq!(hello.version, login::ClientVersion::new(7, 0, 15, 1));
+
+        let mut state = Hello::from(state);
+        let codecs::HelloFrameRecv::AccountLogin(account) = state
+            .recv()
+            .await
+            .expect("Failed to decode login")
+            .expect("Login was not decoded");
+        assert_eq!(account.username, FixedStr::<30>::from("admin"));
+        assert_eq!(account.password, FixedStr::<30>::from("hunter2"));
+
+        assert!(state.framer.read_buffer().is_empty());
+        assert!(state
+            .recv()
+            .await
+            .expect("Failed at end of stream")
+            .is_none());
+    }
+}
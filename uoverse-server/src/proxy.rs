@@ -0,0 +1,316 @@
+use bytes::BytesMut;
+use std::fmt;
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::codec::Decoder;
+use tracing::{debug, warn};
+use ultimaonline_net::packets::*;
+
+use crate::macros::define_codec;
+
+// Codecs for decoding everything that is sent in each direction, whatever
+// state the connection is in, so that captured traffic can be logged
+pub mod codecs {
+    use super::*;
+
+    define_codec! {
+        pub LoginClient,
+        send [],
+        recv [
+            login::AccountLogin,
+            login::ClientHello,
+            login::ServerSelection,
+        ]
+    }
+
+    define_codec! {
+        pub LoginServer,
+        send [],
+        recv [
+            login::GameServerHandoff,
+            login::LoginRejection,
+            login::ServerList,
+            login::ServerListV6,
+        ]
+    }
+
+    define_codec! {
+        pub GameClient,
+        send [],
+        recv [
+            action::AttackRequest,
+            action::ClickUse,
+            action::ClickLook,
+            action::TargetResponse,
+            char_login::ResurrectMenu,
+            char_login::SkillLockChange,
+            char_login::StatLockChange,
+            char_select::CreateCharacter,
+            char_select::GameLogin,
+            char_select::VersionResp,
+            chat::OpenWindow,
+            chat::TalkRequest,
+            client_info::ContextMenuRequest,
+            client_info::ContextMenuResponse,
+            client_info::Flags,
+            client_info::Language,
+            client_info::WindowSize,
+            client_info::ViewRange,
+            entity::EntityBatchQuery,
+            gump::CloseStatus,
+            gump::GumpResponse,
+            housing::DesignRequest,
+            housing::ShowPublicContent,
+            item::DropRequest,
+            item::EquipRequest,
+            item::LiftRequest,
+            mobile::ProfileRequest,
+            mobile::Query,
+            mobile::WarMode,
+            movement::Request,
+            movement::Resync,
+            network::PingReq,
+            trade::TradeResponse,
+            vendor::BuyRequest,
+            world::MapPins,
+        ]
+    }
+
+    define_codec! {
+        pub GameServer,
+        send [],
+        recv [
+            action::AttackOk,
+            action::TargetRequest,
+            char_login::CharStatus,
+            char_login::ExtendedStats,
+            char_login::LoginComplete,
+            char_login::LoginConfirmation,
+            char_login::ResurrectMenu,
+            char_login::Skills,
+            char_select::CharList,
+            char_select::Features,
+            char_select::VersionReq,
+            chat::AsciiMessage,
+            chat::ClilocAffixMessage,
+            chat::ClilocMessage,
+            chat::UnicodeMessage,
+            client_info::ContextMenuDisplay,
+            entity::DeleteObject,
+            entity::MegaCliloc,
+            gump::CloseGeneric,
+            gump::CompressedGump,
+            gump::DisplayGump,
+            housing::CustomHouse,
+            item::ContainerContents,
+            item::Corpse,
+            item::DropReject,
+            item::OpenContainer,
+            item::WorldItem,
+            item::WorldItemLegacy,
+            map::MapChange,
+            mobile::Appearance,
+            mobile::BuffBar,
+            mobile::DeathAction,
+            mobile::MobLightLevel,
+            mobile::Paperdoll,
+            mobile::PlayerUpdate,
+            mobile::ProfileResponse,
+            mobile::State,
+            mobile::StatusBarFlag,
+            mobile::UpdateHealth,
+            mobile::UpdateMana,
+            mobile::UpdateStamina,
+            mobile::WarMode,
+            mobile::WornItem,
+            movement::Success,
+            movement::Reject,
+            network::PingAck,
+            trade::SecureTrade,
+            vendor::BuyList,
+            vendor::SellList,
+            world::GameTime,
+            world::GraphicalEffect,
+            world::HuedEffect,
+            world::HuedEffectLegacy,
+            world::MapPins,
+            world::OpenMap,
+            world::PlayMusic,
+            world::Season,
+            world::SoundEffect,
+            world::Weather,
+            world::WorldLightLevel,
+        ]
+    }
+}
+
+// Decodes a copy of the traffic in one direction, logging each frame. Wrap
+// the decoder in a TraceCodec to also see the frames' bytes, including the
+// ones for packets which aren't known.
+pub struct Tap<D> {
+    name: &'static str,
+    decoder: D,
+    buffer: BytesMut,
+    seed: bool, // Whether the stream may start with a seed
+}
+
+impl<D> Tap<D>
+where
+    D: Decoder,
+    D::Item: fmt::Debug,
+    D::Error: fmt::Display,
+{
+    pub fn new(name: &'static str, decoder: D) -> Self {
+        Self {
+            name,
+            decoder,
+            buffer: BytesMut::new(),
+            seed: false,
+        }
+    }
+
+    // Clients send a 4 byte seed before their first packet, unless that
+    // packet is the hello which includes the seed
+    pub fn with_seed(mut self) -> Self {
+        self.seed = true;
+        self
+    }
+
+    pub fn observe(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+
+        if self.seed {
+            if self.buffer.first() == Some(&login::ClientHello::PACKET_ID) {
+                self.seed = false;
+            } else if self.buffer.len() >= 4 {
+                let seed = self.buffer.split_to(4);
+                debug!(direction = self.name, "Seed {:02X?}", &seed[..]);
+                self.seed = false;
+            } else {
+                return;
+            }
+        }
+
+        loop {
+            match self.decoder.decode(&mut self.buffer) {
+                Ok(Some(frame)) => debug!(direction = self.name, "{:?}", frame),
+                Ok(None) => break,
+                // Where the next frame starts isn't known after one which
+                // couldn't be decoded, so skip everything read so far
+                Err(err) => {
+                    warn!(direction = self.name, "Skipping frames: {}", err);
+                    self.buffer.clear();
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Forwards the traffic between a client and a server unchanged, until both
+// sides have closed their connection
+pub async fn forward<C, S, CD, SD>(
+    client: C,
+    server: S,
+    mut client_tap: Tap<CD>,
+    mut server_tap: Tap<SD>,
+) -> io::Result<()>
+where
+    C: AsyncRead + AsyncWrite,
+    S: AsyncRead + AsyncWrite,
+    CD: Decoder,
+    CD::Item: fmt::Debug,
+    CD::Error: fmt::Display,
+    SD: Decoder,
+    SD::Item: fmt::Debug,
+    SD::Error: fmt::Display,
+{
+    let (client_read, client_write) = io::split(client);
+    let (server_read, server_write) = io::split(server);
+
+    tokio::try_join!(
+        pump(client_read, server_write, &mut client_tap),
+        pump(server_read, client_write, &mut server_tap),
+    )?;
+
+    Ok(())
+}
+
+// Copies from one side to the other until the reading side closes, and then
+// closes the writing side
+async fn pump<R, W, D>(mut reader: R, mut writer: W, tap: &mut Tap<D>) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    D: Decoder,
+    D::Item: fmt::Debug,
+    D::Error: fmt::Display,
+{
+    let mut buf = [0u8; 4096];
+    loop {
+        let len = reader.read(&mut buf).await?;
+        if len == 0 {
+            break;
+        }
+
+        writer.write_all(&buf[..len]).await?;
+        tap.observe(&buf[..len]);
+    }
+
+    writer.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::client::codecs::CompressionCodec;
+    use crate::trace::TraceCodec;
+
+    // Sends everything to the other end while reading whatever it sends back
+    async fn exchange(stream: io::DuplexStream, bytes: &[u8]) -> Vec<u8> {
+        let (mut reader, mut writer) = io::split(stream);
+        let mut received = vec![];
+
+        let send = async {
+            writer.write_all(bytes).await.unwrap();
+            writer.shutdown().await.unwrap();
+        };
+        let recv = async { reader.read_to_end(&mut received).await.unwrap() };
+        tokio::join!(send, recv);
+
+        received
+    }
+
+    #[tokio::test]
+    async fn forwards_unchanged() {
+        let (client, client_proxy) = io::duplex(64);
+        let (server_proxy, server) = io::duplex(64);
+
+        let proxy = tokio::spawn(forward(
+            client_proxy,
+            server_proxy,
+            Tap::new("client", TraceCodec::new(codecs::GameClient)).with_seed(),
+            Tap::new(
+                "server",
+                CompressionCodec::decompressing(TraceCodec::new(codecs::GameServer)),
+            ),
+        ));
+
+        // More than fits in the duplex buffers, including a packet which
+        // isn't known and bytes which aren't valid compressed data
+        let from_client = [0x7Fu8, 0x00, 0x00, 0x01, 0x73, 0x00, 0x01]
+            .iter()
+            .copied()
+            .chain((0..=255u8).cycle().take(1000))
+            .collect::<Vec<_>>();
+        let from_server = (0..=255u8).rev().cycle().take(1000).collect::<Vec<_>>();
+
+        let (server_received, client_received) = tokio::join!(
+            exchange(server, &from_server),
+            exchange(client, &from_client)
+        );
+        proxy.await.unwrap().unwrap();
+
+        assert_eq!(server_received, from_client);
+        assert_eq!(client_received, from_server);
+    }
+}
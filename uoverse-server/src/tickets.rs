@@ -0,0 +1,206 @@
+use eyre::{eyre, Context, Result};
+use fs2::FileExt;
+use std::{collections::HashMap, fs, io::ErrorKind, path::PathBuf, process, sync::Mutex};
+
+// Tickets which the login server gives to clients when handing them off to
+// the game server, and which the clients then log in to the game server
+// with as their seed. The login and game servers are separate processes, so
+// they share the tickets through a file when one is configured. Each line of
+// the file has a ticket in hex, a tab, and the account name it was issued to.
+// Changes to the file are made while holding a lock on a separate lock file
+// beside it, since the ticket file itself is replaced on each change.
+#[derive(Debug, Default)]
+pub struct TicketRegistry {
+    path: Option<PathBuf>,
+    tickets: Mutex<HashMap<String, u32>>,
+}
+
+impl TicketRegistry {
+    // Tickets which are only shared within this process
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: Some(path.into()),
+            tickets: Default::default(),
+        }
+    }
+
+    pub fn issue(&self, username: &str) -> Result<u32> {
+        let ticket = rand::random::<u32>();
+        self.update(|tickets| {
+            tickets.insert(username.to_string(), ticket);
+            Ok(())
+        })?;
+
+        Ok(ticket)
+    }
+
+    // Checks the seed that a client logged in to the game server with. Each
+    // ticket can only be redeemed once, even if the seed doesn't match.
+    pub fn redeem(&self, username: &str, seed: u32) -> Result<()> {
+        self.update(|tickets| match tickets.remove(username) {
+            Some(ticket) if ticket == seed => Ok(()),
+            Some(_) => Err(eyre!(
+                "Seed {:08X} does not match the ticket issued to {}",
+                seed,
+                username
+            )),
+            None => Err(eyre!("No ticket was issued to {}", username)),
+        })
+    }
+
+    // Applies the change to the latest tickets, saving them afterwards if
+    // they're shared through a file. The other server is locked out from
+    // reading the file until the change is saved, so neither loses the
+    // other's change.
+    fn update<T>(&self, change: impl FnOnce(&mut HashMap<String, u32>) -> Result<T>) -> Result<T> {
+        let mut tickets = self
+            .tickets
+            .lock()
+            .map_err(|_| eyre!("Unable to lock tickets"))?;
+
+        let path = match &self.path {
+            Some(path) => path,
+            None => return change(&mut tickets),
+        };
+
+        // Unlocked when closed at the end of the change
+        let lock_path = path.with_extension("lock");
+        let lock = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .and_then(|lock| lock.lock_exclusive().map(|_| lock))
+            .wrap_err_with(|| format!("Unable to lock ticket file {}", lock_path.display()))?;
+
+        *tickets = match fs::read_to_string(path) {
+            Ok(contents) => parse_tickets(&contents)
+                .wrap_err_with(|| format!("In ticket file {}", path.display()))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let res = change(&mut tickets);
+
+        // Replace the file all at once, so the other server never reads
+        // part of it. The temporary file is named for this process, so that
+        // it's never mixed up with one the other server left behind.
+        let contents = tickets
+            .iter()
+            .map(|(username, ticket)| format!("{:08X}\t{}\n", ticket, username))
+            .collect::<String>();
+        let temp_path = path.with_extension(format!(
+            "{}.{:08x}.tmp",
+            process::id(),
+            rand::random::<u32>()
+        ));
+        fs::write(&temp_path, contents)
+            .and_then(|_| fs::rename(&temp_path, path))
+            .wrap_err_with(|| format!("Unable to write ticket file {}", path.display()))?;
+
+        drop(lock);
+        res
+    }
+}
+
+fn parse_tickets(contents: &str) -> Result<HashMap<String, u32>> {
+    contents
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (ticket, username) = line
+                .split_once('\t')
+                .ok_or_else(|| eyre!("Ticket line {:?} is missing an account name", line))?;
+            let ticket = u32::from_str_radix(ticket, 16)
+                .wrap_err_with(|| format!("Ticket {:?} is invalid", ticket))?;
+
+            Ok((username.to_string(), ticket))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redeems_tickets() {
+        let tickets = TicketRegistry::new();
+
+        let ticket = tickets.issue("admin").unwrap();
+        tickets.redeem("admin", ticket).unwrap();
+
+        // Tickets can't be used twice
+        assert!(tickets.redeem("admin", ticket).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_seed() {
+        let tickets = TicketRegistry::new();
+
+        let ticket = tickets.issue("admin").unwrap();
+        assert!(tickets.redeem("admin", ticket ^ 0x01).is_err());
+        assert!(tickets.redeem("admin", ticket).is_err());
+
+        let ticket = tickets.issue("guest").unwrap();
+        assert!(tickets.redeem("admin", ticket).is_err());
+    }
+
+    #[test]
+    fn shares_tickets_through_file() {
+        let path = std::env::temp_dir().join(format!("uoverse_tickets_{}", std::process::id()));
+        let (login, game) = (TicketRegistry::open(&path), TicketRegistry::open(&path));
+
+        let ticket = login.issue("admin").unwrap();
+        login.issue("guest").unwrap();
+        assert!(game.redeem("admin", !ticket).is_err());
+
+        let ticket = login.issue("admin").unwrap();
+        game.redeem("admin", ticket).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().ends_with("\tguest\n"));
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(path.with_extension("lock")).unwrap();
+    }
+
+    #[test]
+    fn keeps_concurrent_changes() {
+        const TICKETS: usize = 50;
+
+        let path =
+            std::env::temp_dir().join(format!("uoverse_tickets_concurrent_{}", std::process::id()));
+
+        // Each registry stands in for one of the servers
+        let threads = ["login", "game"]
+            .iter()
+            .map(|server| {
+                let tickets = TicketRegistry::open(&path);
+                std::thread::spawn(move || {
+                    for i in 0..TICKETS {
+                        let username = format!("{}{}", server, i);
+                        let ticket = tickets.issue(&username).unwrap();
+
+                        // Redeeming every other ticket removes it again
+                        if i % 2 == 0 {
+                            tickets.redeem(&username, ticket).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let remaining = parse_tickets(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(remaining.len(), TICKETS);
+        assert!(remaining.contains_key("login1") && remaining.contains_key("game1"));
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(path.with_extension("lock")).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+use bytes::BytesMut;
+use std::fmt::Write;
+use tokio_util::codec::{Decoder, Encoder};
+use tracing::{enabled, trace, Level};
+
+// Wraps a codec to log every frame it encodes or decodes as a hex dump,
+// when trace level logging is enabled. Wrap the codec inside of any
+// compression so that the dumps show the uncompressed packets.
+pub struct TraceCodec<C> {
+    codec: C,
+}
+
+impl<C> TraceCodec<C> {
+    pub fn new(codec: C) -> Self {
+        Self { codec }
+    }
+}
+
+impl<I, C: Encoder<I>> Encoder<I> for TraceCodec<C> {
+    type Error = C::Error;
+
+    fn encode(&mut self, pkt: I, dst: &mut BytesMut) -> std::result::Result<(), Self::Error> {
+        let start = dst.len();
+        self.codec.encode(pkt, dst)?;
+
+        if enabled!(Level::TRACE) {
+            trace!("Sent frame:\n{}", hex_dump(&dst[start..]));
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: Decoder> Decoder for TraceCodec<C> {
+    type Error = C::Error;
+    type Item = C::Item;
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> std::result::Result<Option<Self::Item>, Self::Error> {
+        if !enabled!(Level::TRACE) {
+            return self.codec.decode(src);
+        }
+
+        let received = src.clone();
+        let result = self.codec.decode(src);
+        match &result {
+            Ok(Some(_)) => {
+                let frame = &received[..(received.len() - src.len())];
+                trace!("Received frame:\n{}", hex_dump(frame));
+            }
+            Ok(None) => {}
+            Err(_) => trace!("Failed to decode frame:\n{}", hex_dump(&received)),
+        }
+
+        result
+    }
+}
+
+// Formats the bytes as lines of their offset, 16 bytes in hex, and the
+// same bytes in ASCII with non-printable bytes shown as '.'
+pub fn hex_dump(bytes: &[u8]) -> String {
+    const LINE_LEN: usize = 16;
+
+    let mut dump = String::new();
+    for (line_num, line) in bytes.chunks(LINE_LEN).enumerate() {
+        if line_num > 0 {
+            dump.push('\n');
+        }
+
+        write!(dump, "{:04X} ", line_num * LINE_LEN).unwrap();
+        for by in line {
+            write!(dump, " {:02X}", by).unwrap();
+        }
+        for _ in line.len()..LINE_LEN {
+            dump.push_str("   ");
+        }
+
+        dump.push_str("  ");
+        dump.extend(line.iter().map(|&by| {
+            if by.is_ascii_graphic() || by == b' ' {
+                by as char
+            } else {
+                '.'
+            }
+        }));
+    }
+
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_hex_dump() {
+        let bytes = b"\x1C\x00\x2CHello, Britannia!\x00\xFF";
+
+        assert_eq!(
+            hex_dump(bytes),
+            "0000  1C 00 2C 48 65 6C 6C 6F 2C 20 42 72 69 74 61 6E  ..,Hello, Britan\n\
+             0010  6E 69 61 21 00 FF                                nia!.."
+        );
+        assert_eq!(hex_dump(&[]), "");
+    }
+}
@@ -0,0 +1,7 @@
+// Packet ids are checked when a codec is compiled, so a codec receiving two
+// packets with the same id can only be shown by a build which fails
+#[test]
+fn unique_packet_ids() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/duplicate_recv_id.rs");
+}
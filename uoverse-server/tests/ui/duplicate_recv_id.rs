@@ -0,0 +1,14 @@
+use ultimaonline_net::packets::action;
+use uoverse_server_macros::define_codec;
+
+// Both packets have the id 0xAA, so the decoder can't tell them apart
+define_codec! {
+    pub Combat,
+    send [],
+    recv [
+        action::AttackOk,
+        action::AttackEnd,
+    ]
+}
+
+fn main() {}
@@ -1 +1,3 @@
+pub mod manifest;
+pub mod mul_index;
 pub mod uo_package;
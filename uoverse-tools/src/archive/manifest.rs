@@ -0,0 +1,212 @@
+use std::{
+    fs,
+    io::BufRead,
+    path::{Path, PathBuf},
+};
+
+use super::uo_package::{self, uop_hash, FileType, UOPackage, UOPackageFile};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("manifest line {0} is missing a local path")]
+    MissingPath(usize),
+
+    #[error("manifest name {name:?} is invalid because {err}")]
+    InvalidName {
+        name: String,
+        err: uo_package::Error,
+    },
+
+    #[error("manifest contains {0} more than once")]
+    DuplicateName(String),
+
+    #[error("i/o failure {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManifestEntry {
+    pub name: String, // Logical path within the package
+    pub path: PathBuf,
+    pub hash: u64,
+}
+
+// Maps the logical paths of files in a package, which are only stored as
+// hashes, to local files. Each line of a manifest file has the form
+// `<logical path>=<local path>`, blank lines and lines starting with '#'
+// are ignored. Relative local paths are relative to the directory being
+// packed from or unpacked into.
+#[derive(Clone, Debug, Default)]
+pub struct Manifest {
+    entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let mut manifest = Self::default();
+
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, path) = line
+                .split_once('=')
+                .ok_or(Error::MissingPath(line_num + 1))?;
+            manifest.add(name.trim(), path.trim())?;
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_reader(std::io::BufReader::new(fs::File::open(path)?))
+    }
+
+    pub fn add<P: Into<PathBuf>>(&mut self, name: &str, path: P) -> Result<()> {
+        let hash = uop_hash(name).map_err(|err| Error::InvalidName {
+            name: name.to_string(),
+            err,
+        })?;
+        if self.find(hash).is_some() {
+            return Err(Error::DuplicateName(name.to_string()));
+        }
+
+        self.entries.push(ManifestEntry {
+            name: name.to_string(),
+            path: path.into(),
+            hash,
+        });
+
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[ManifestEntry] {
+        self.entries.as_slice()
+    }
+
+    pub fn find(&self, hash: u64) -> Option<&ManifestEntry> {
+        self.entries.iter().find(|entry| entry.hash == hash)
+    }
+
+    // Reads the local files, hashed by their logical paths
+    pub fn read_files(&self, dir: &Path) -> Result<Vec<UOPackageFile>> {
+        self.entries
+            .iter()
+            .map(|entry| {
+                Ok(UOPackageFile {
+                    hash: entry.hash,
+                    file_type: FileType::Compressed,
+                    timestamp: None,
+                    contents: fs::read(dir.join(&entry.path))?,
+                })
+            })
+            .collect()
+    }
+
+    // Writes every file in the package to its local path within dir
+    pub fn unpack(&self, package: &UOPackage, dir: &Path) -> Result<()> {
+        for file in package.files.iter() {
+            let path = dir.join(self.unpack_path(file.hash));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, file.contents.as_slice())?;
+        }
+
+        Ok(())
+    }
+
+    // Where to unpack the file with the given hash, falling back to the
+    // hash itself for files which aren't in the manifest
+    pub fn unpack_path(&self, hash: u64) -> PathBuf {
+        match self.find(hash) {
+            Some(entry) => entry.path.clone(),
+            None => format!("{:016X}.dat", hash).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{convert::TryInto, io::Cursor};
+
+    #[test]
+    fn parses_manifest() {
+        let manifest = Manifest::from_reader(
+            "# Map files\n\
+             build/map0legacymul/00000000.dat = map/part0.dat\n\
+             \n\
+             build/map0legacymul/00000001.dat=map/part1.dat\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(manifest.entries().len(), 2);
+
+        let hash = uop_hash("build/map0legacymul/00000001.dat").unwrap();
+        let entry = manifest.find(hash).unwrap();
+        assert_eq!(entry.name, "build/map0legacymul/00000001.dat");
+        assert_eq!(entry.path, Path::new("map/part1.dat"));
+
+        assert_eq!(manifest.unpack_path(hash), Path::new("map/part1.dat"));
+        assert_eq!(
+            manifest.unpack_path(0x1F),
+            Path::new("000000000000001F.dat")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert!(matches!(
+            Manifest::from_reader("a=a.dat\nb.dat\n".as_bytes()),
+            Err(Error::MissingPath(2))
+        ));
+        assert!(matches!(
+            Manifest::from_reader("a=a.dat\n=b.dat\n".as_bytes()),
+            Err(Error::InvalidName { .. })
+        ));
+        assert!(matches!(
+            Manifest::from_reader("a=a.dat\na=b.dat\n".as_bytes()),
+            Err(Error::DuplicateName(_))
+        ));
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        let dir = std::env::temp_dir().join(format!("uop_manifest_{}", std::process::id()));
+        let (input, output) = (dir.join("in"), dir.join("out"));
+        fs::create_dir_all(input.join("map")).unwrap();
+        fs::write(input.join("first.dat"), b"first").unwrap();
+        fs::write(input.join("map/second.dat"), b"second").unwrap();
+
+        let manifest = Manifest::from_reader(
+            "build/test/00000000.dat=first.dat\n\
+             build/test/00000001.dat=map/second.dat\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let package: UOPackage = manifest.read_files(&input).unwrap().try_into().unwrap();
+        let mut contents = Cursor::new(vec![]);
+        package.write(&mut contents).unwrap();
+
+        let package = UOPackage::new(&mut Cursor::new(contents.into_inner())).unwrap();
+        let file = package
+            .get_file("build/test/00000001.dat")
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.contents, b"second");
+
+        manifest.unpack(&package, &output).unwrap();
+        assert_eq!(fs::read(output.join("first.dat")).unwrap(), b"first");
+        assert_eq!(fs::read(output.join("map/second.dat")).unwrap(), b"second");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    mem::size_of,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("index is truncated after {0} entries")]
+    TruncatedIndex(usize),
+
+    #[error("index entry {0} does not exist")]
+    MissingEntry(usize),
+
+    #[error("i/o failure {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Entry in an index file (e.g. artidx.mul), locating one asset
+// within the accompanying data file (e.g. art.mul)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IndexEntry {
+    pub lookup: u32,
+    pub length: u32,
+    pub extra: u32, // Meaning depends upon the asset
+}
+
+impl IndexEntry {
+    pub const SIZE: usize = size_of::<u32>() * 3;
+
+    // Lookup and length of entries without any data
+    pub const EMPTY: u32 = 0xFFFFFFFF;
+
+    pub fn empty() -> Self {
+        Self {
+            lookup: Self::EMPTY,
+            length: Self::EMPTY,
+            extra: 0,
+        }
+    }
+
+    fn from_reader<R: Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(Self {
+            lookup: reader.read_u32::<LittleEndian>()?,
+            length: reader.read_u32::<LittleEndian>()?,
+            extra: reader.read_u32::<LittleEndian>()?,
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u32::<LittleEndian>(self.lookup)?;
+        writer.write_u32::<LittleEndian>(self.length)?;
+        writer.write_u32::<LittleEndian>(self.extra)?;
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lookup == Self::EMPTY || self.length == Self::EMPTY || self.length == 0
+    }
+}
+
+// Reads the entries of an index file up front, then the data of each entry
+// from the data file on demand
+pub struct MulIndex<R> {
+    entries: Vec<IndexEntry>,
+    data: R,
+}
+
+impl<R: Read + Seek> MulIndex<R> {
+    pub fn new<I: Read>(index: &mut I, data: R) -> Result<Self> {
+        let mut entries = vec![];
+
+        loop {
+            let mut buf = [0u8; IndexEntry::SIZE];
+            match read_full(index, &mut buf)? {
+                0 => break,
+                IndexEntry::SIZE => entries.push(IndexEntry::from_reader(&mut buf.as_slice())?),
+                _ => return Err(Error::TruncatedIndex(entries.len())),
+            }
+        }
+
+        Ok(Self { entries, data })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[IndexEntry] {
+        self.entries.as_slice()
+    }
+
+    pub fn entry(&self, id: usize) -> Option<&IndexEntry> {
+        self.entries.get(id)
+    }
+
+    // Returns None if the entry has no data
+    pub fn read_entry(&mut self, id: usize) -> Result<Option<Vec<u8>>> {
+        let entry = *self.entry(id).ok_or(Error::MissingEntry(id))?;
+        if entry.is_empty() {
+            return Ok(None);
+        }
+
+        let mut contents = vec![0u8; entry.length as usize];
+        self.data.seek(SeekFrom::Start(entry.lookup.into()))?;
+        self.data.read_exact(contents.as_mut_slice())?;
+
+        Ok(Some(contents))
+    }
+}
+
+// Like read_exact, but returns how much was read if the reader ends early
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut amount = 0;
+    while amount < buf.len() {
+        match reader.read(&mut buf[amount..]) {
+            Ok(0) => break,
+            Ok(read) => amount += read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn index_bytes(entries: &[IndexEntry]) -> Vec<u8> {
+        let mut index = vec![];
+        for entry in entries {
+            entry.write(&mut index).unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn reads_entries() {
+        let data = b"firstsecond".to_vec();
+        let index = index_bytes(&[
+            IndexEntry {
+                lookup: 5,
+                length: 6,
+                extra: 0x00400020,
+            },
+            IndexEntry::empty(),
+            IndexEntry {
+                lookup: 0,
+                length: 5,
+                extra: 0,
+            },
+        ]);
+
+        let mut mul = MulIndex::new(&mut index.as_slice(), Cursor::new(data)).unwrap();
+        assert_eq!(mul.len(), 3);
+        assert_eq!(mul.entry(0).unwrap().extra, 0x00400020);
+
+        assert_eq!(mul.read_entry(0).unwrap(), Some(b"second".to_vec()));
+        assert_eq!(mul.read_entry(1).unwrap(), None);
+        assert_eq!(mul.read_entry(2).unwrap(), Some(b"first".to_vec()));
+        assert!(matches!(mul.read_entry(3), Err(Error::MissingEntry(3))));
+    }
+
+    #[test]
+    fn rejects_truncated_index() {
+        let mut index = index_bytes(&[IndexEntry::empty(), IndexEntry::empty()]);
+        index.truncate(index.len() - 1);
+
+        assert!(matches!(
+            MulIndex::new(&mut index.as_slice(), Cursor::new(vec![])),
+            Err(Error::TruncatedIndex(1))
+        ));
+    }
+
+    #[test]
+    fn rejects_entry_past_data() {
+        let index = index_bytes(&[IndexEntry {
+            lookup: 2,
+            length: 8,
+            extra: 0,
+        }]);
+
+        let mut mul = MulIndex::new(&mut index.as_slice(), Cursor::new(vec![0u8; 4])).unwrap();
+        assert!(matches!(mul.read_entry(0), Err(Error::Io(_))));
+    }
+}
@@ -1,9 +1,10 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 use std::{
+    collections::HashSet,
     convert::{TryFrom, TryInto},
     fmt,
-    io::{Read, Seek, SeekFrom, Write},
+    io::{Cursor, Read, Seek, SeekFrom, Write},
     mem::size_of,
 };
 
@@ -22,6 +23,12 @@ pub enum Error {
     #[error("package cannot contain {0} files")]
     TooManyFiles(usize),
 
+    #[error("package already contains file {0:016X}")]
+    DuplicateFile(u64),
+
+    #[error("package does not contain file {0}")]
+    MissingFile(String),
+
     #[error("hash input encoding is not supported")]
     UnsupportedEncoding,
 
@@ -43,6 +50,9 @@ const FORMAT_MAGIC: u32 = 0xFD23EC43;
 // When writing, align block headers & file entries on 4K page boundaries
 const ALIGNMENT: u64 = 0x1000;
 
+// The first block header follows the package header, within the first page
+const FIRST_BLOCK: u64 = 0x20;
+
 #[derive(Debug)]
 pub struct PackageHdr {
     version: u32,
@@ -62,13 +72,25 @@ impl PackageHdr {
             return Err(Error::InvalidMagic(header));
         }
 
-        Ok(PackageHdr {
+        let header = PackageHdr {
             version: reader.read_u32::<LittleEndian>()?,
             format: reader.read_u32::<LittleEndian>()?,
             first_block: reader.read_u64::<LittleEndian>()?,
             block_size: reader.read_u32::<LittleEndian>()?,
             files_count: reader.read_u32::<LittleEndian>()?,
-        })
+        };
+
+        if header.format != FORMAT_MAGIC {
+            return Err(Error::InvalidData(format!(
+                "package format {:08X} is not {:08X}",
+                header.format, FORMAT_MAGIC
+            )));
+        }
+
+        match header.version {
+            4 | 5 => Ok(header),
+            _ => Err(Error::UnsupportedVersion(header.version)),
+        }
     }
 
     fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
@@ -89,8 +111,9 @@ impl Default for PackageHdr {
         Self {
             version: 5,
             format: FORMAT_MAGIC,
-            first_block: 0x20,
-            block_size: ((ALIGNMENT as usize - BlockHdr::BASE_SIZE) / FileHdr::SIZE) as u32,
+            first_block: FIRST_BLOCK,
+            block_size: (((ALIGNMENT - FIRST_BLOCK) as usize - BlockHdr::BASE_SIZE) / FileHdr::SIZE)
+                as u32,
             files_count: 0,
         }
     }
@@ -106,10 +129,19 @@ struct BlockHdr {
 impl BlockHdr {
     const BASE_SIZE: usize = size_of::<u32>() + size_of::<u64>();
 
-    fn new<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    // The block's entries must all be before the end of the package
+    fn new<R: Read + Seek>(reader: &mut R, end: u64) -> Result<Self> {
+        let block_pos = reader.stream_position()?;
         let files_count = reader.read_u32::<LittleEndian>()?;
         let next_block = reader.read_u64::<LittleEndian>()?;
 
+        if block_pos + Self::size(files_count) as u64 > end {
+            return Err(Error::InvalidData(format!(
+                "block at {:#X} with {} files ends past the end of the package",
+                block_pos, files_count
+            )));
+        }
+
         let mut headers = Vec::<FileHdr>::with_capacity(files_count as usize);
         for _ in 0..files_count {
             headers.push(FileHdr::new(reader)?);
@@ -145,7 +177,7 @@ struct FileHdr {
     compressed_size: u32,
     raw_size: u32,
     hash: u64,
-    _header_crc: u32,
+    header_crc: u32,
     entry_type: u16,
 }
 
@@ -159,7 +191,7 @@ impl FileHdr {
             compressed_size: reader.read_u32::<LittleEndian>()?,
             raw_size: reader.read_u32::<LittleEndian>()?,
             hash: reader.read_u64::<LittleEndian>()?,
-            _header_crc: reader.read_u32::<LittleEndian>()?,
+            header_crc: reader.read_u32::<LittleEndian>()?,
             entry_type: reader.read_u16::<LittleEndian>()?,
         })
     }
@@ -170,11 +202,83 @@ impl FileHdr {
         writer.write_u32::<LittleEndian>(self.compressed_size)?;
         writer.write_u32::<LittleEndian>(self.raw_size)?;
         writer.write_u64::<LittleEndian>(self.hash)?;
-        writer.write_u32::<LittleEndian>(self._header_crc)?;
+        writer.write_u32::<LittleEndian>(self.header_crc)?;
         writer.write_u16::<LittleEndian>(self.entry_type)?;
 
         Ok(())
     }
+
+    fn check_size(&self, kind: &str, actual: u64, expected: u32) -> Result<()> {
+        if actual != expected.into() {
+            return Err(Error::InvalidData(format!(
+                "{} size of file {:016X} is {} but expected {}",
+                kind, self.hash, actual, expected
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn verify_crc<R: Read>(&self, reader: &mut R) -> Result<()> {
+        let mut metadata = vec![0u8; self.header_size as usize];
+        reader.read_exact(metadata.as_mut_slice())?;
+
+        let header_crc = adler32(metadata.as_slice());
+        if header_crc != self.header_crc {
+            return Err(Error::InvalidData(format!(
+                "header CRC for file {:016X} is {:08X} but expected {:08X}",
+                self.hash, header_crc, self.header_crc
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+// Read the entries for every file in the package, without their contents.
+// The blocks are followed from one to the next, however many entries each
+// has, since other tools don't all use the same block size.
+fn read_file_headers<R: Read + Seek>(reader: &mut R, first_block: u64) -> Result<Vec<FileHdr>> {
+    let end = reader.seek(SeekFrom::End(0))?;
+    let mut headers = vec![];
+    let mut visited = HashSet::new();
+
+    let mut block_pos = first_block;
+    while block_pos != 0 {
+        if block_pos.saturating_add(BlockHdr::BASE_SIZE as u64) > end {
+            return Err(Error::InvalidData(format!(
+                "block at {:#X} is past the end of the package",
+                block_pos
+            )));
+        }
+        if !visited.insert(block_pos) {
+            return Err(Error::InvalidData(format!(
+                "block at {:#X} is linked to more than once",
+                block_pos
+            )));
+        }
+
+        reader.seek(SeekFrom::Start(block_pos))?;
+        let block = BlockHdr::new(reader, end)?;
+        for header in block.headers {
+            if header.position == 0 {
+                continue;
+            }
+
+            let size = header.header_size as u64 + header.compressed_size as u64;
+            if header.position.saturating_add(size) > end {
+                return Err(Error::InvalidData(format!(
+                    "file {:016X} ends past the end of the package",
+                    header.hash
+                )));
+            }
+            headers.push(header);
+        }
+
+        block_pos = block.next_block;
+    }
+
+    Ok(headers)
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -228,7 +332,6 @@ impl UOPackageFile {
         }
         reader.seek(SeekFrom::Current(remaining.unwrap() as i64))?;
 
-        // TODO: Verify header CRC
         let mut file = UOPackageFile {
             hash: header.hash,
             file_type,
@@ -240,15 +343,28 @@ impl UOPackageFile {
         Ok(file)
     }
 
-    const HEADER_SIZE_V5: usize = 8; // UOLive files have 137, but we only use the file type field
-    fn read_version5<R: Read + Seek>(reader: &mut R, header: &FileHdr) -> Result<Self> {
+    // UOLive files have 137, but we only use the file type field and the
+    // contents checksum that follows it. The checksum is the Adler32 of the
+    // raw contents, or zero if it wasn't written.
+    const HEADER_SIZE_V5: usize = 8;
+    fn read_version5<R: Read + Seek>(
+        reader: &mut R,
+        header: &FileHdr,
+        verify_contents: bool,
+    ) -> Result<Self> {
         let file_type = reader.read_u16::<LittleEndian>()?.into();
-        let remaining = reader.read_u16::<LittleEndian>()?;
+        let mut remaining = reader.read_u16::<LittleEndian>()?;
+
+        // Only files with metadata laid out like ours have the checksum
+        let mut checksum = 0;
+        if remaining as usize + size_of::<u16>() * 2 == Self::HEADER_SIZE_V5 {
+            checksum = reader.read_u32::<LittleEndian>()?;
+            remaining -= size_of::<u32>() as u16;
+        }
 
         // Rest of header is unknown, skip it
         reader.seek(SeekFrom::Current(remaining as i64))?;
 
-        // TODO: Verify header CRC
         let mut file = UOPackageFile {
             hash: header.hash,
             file_type,
@@ -257,6 +373,15 @@ impl UOPackageFile {
         };
 
         Self::read_contents(reader, header, &mut file.contents)?;
+
+        let contents_checksum = adler32(file.contents.as_slice());
+        if verify_contents && checksum != 0 && contents_checksum != checksum {
+            return Err(Error::InvalidData(format!(
+                "contents checksum for file {:016X} is {:08X} but expected {:08X}",
+                header.hash, contents_checksum, checksum
+            )));
+        }
+
         Ok(file)
     }
 
@@ -267,31 +392,60 @@ impl UOPackageFile {
     ) -> Result<()> {
         match header.entry_type {
             0 => {
+                // Stored as is, so both sizes are the same
+                header.check_size("stored", header.compressed_size.into(), header.raw_size)?;
+
                 let mut reader = reader.take(header.raw_size.into());
                 let amount = reader.read_to_end(contents)?;
-                assert!(amount == header.raw_size as usize);
+                header.check_size("read", amount as u64, header.raw_size)?;
             }
             1 => {
                 let reader = reader.take(header.compressed_size.into());
                 let mut decoder = ZlibDecoder::new(reader);
                 decoder.read_to_end(contents)?;
-                assert!(decoder.total_in() == header.compressed_size.into());
-                assert!(decoder.total_out() == header.raw_size.into());
+                header.check_size("compressed", decoder.total_in(), header.compressed_size)?;
+                header.check_size("decompressed", decoder.total_out(), header.raw_size)?;
+            }
+            entry_type => {
+                return Err(Error::InvalidData(format!(
+                    "entry type {} of file {:016X} is unknown",
+                    entry_type, header.hash
+                )))
             }
-            _ => unimplemented!(),
         }
 
         Ok(())
     }
 
-    fn new<R: Read + Seek>(reader: &mut R, header: &FileHdr, version: u32) -> Result<Self> {
+    // Version 4 metadata has no contents checksum, so it's never verified
+    fn new<R: Read + Seek>(
+        reader: &mut R,
+        header: &FileHdr,
+        version: u32,
+        verify_contents: bool,
+    ) -> Result<Self> {
         match version {
             4 => Self::read_version4(reader, header),
-            5 => Self::read_version5(reader, header),
+            5 => Self::read_version5(reader, header, verify_contents),
             _ => Err(Error::UnsupportedVersion(version)),
         }
     }
 
+    fn read<R: Read + Seek>(
+        reader: &mut R,
+        header: &FileHdr,
+        version: u32,
+        options: OpenOptions,
+    ) -> Result<Self> {
+        reader.seek(SeekFrom::Start(header.position))?;
+        if options.verify_crc {
+            header.verify_crc(reader)?;
+            reader.seek(SeekFrom::Start(header.position))?;
+        }
+
+        Self::new(reader, header, version, options.verify_contents)
+    }
+
     fn write_header_v4<W: Write + Seek>(&self, writer: &mut W) -> Result<usize> {
         let preamble_size = size_of::<u16>() * 2; // file_type and remaining
         let remaining =
@@ -327,7 +481,8 @@ impl UOPackageFile {
         writer.write_u16::<LittleEndian>(self.file_type as u16)?;
         writer.write_u16::<LittleEndian>(remaining as u16)?;
 
-        Ok(remaining)
+        writer.write_u32::<LittleEndian>(adler32(self.contents.as_slice()))?;
+        Ok(remaining - size_of::<u32>())
     }
 
     fn write_header<W: Write + Seek>(&self, writer: &mut W, version: u32) -> Result<usize> {
@@ -338,13 +493,18 @@ impl UOPackageFile {
         }
     }
 
-    fn write<W: Write + Seek>(&self, writer: &mut W, version: u32) -> Result<(u32, u32)> {
-        // Write the file metadata first
-        let remaining = self.write_header(writer, version)?;
+    // Returns the compressed & raw sizes of the contents, and the header CRC
+    fn write<W: Write + Seek>(&self, writer: &mut W, version: u32) -> Result<(u32, u32, u32)> {
+        // Write the file metadata first, padded out to its full size
+        let mut header = Cursor::new(Vec::<u8>::new());
+        let remaining = self.write_header(&mut header, version)?;
+        let mut header = header.into_inner();
+        header.resize(header.len() + remaining, 0);
 
-        // Write the file content
-        writer.seek(SeekFrom::Current(remaining as i64))?;
+        writer.write_all(header.as_slice())?;
+        let header_crc = adler32(header.as_slice());
 
+        // Write the file content
         match self.file_type.is_compressed() {
             true => {
                 let mut encoder = ZlibEncoder::new(writer, Compression::best());
@@ -353,13 +513,13 @@ impl UOPackageFile {
 
                 let compressed_size = encoder.total_out() as u32;
                 let raw_size = encoder.total_in() as u32;
-                Ok((compressed_size, raw_size))
+                Ok((compressed_size, raw_size, header_crc))
             }
             false => {
                 writer.write_all(self.contents.as_slice())?;
 
                 let file_size = self.contents.len() as u32;
-                Ok((file_size, file_size))
+                Ok((file_size, file_size, header_crc))
             }
         }
     }
@@ -376,6 +536,14 @@ impl fmt::Debug for UOPackageFile {
     }
 }
 
+// Packages written before header CRCs and contents checksums were emitted
+// have zero for them, so by default they aren't verified.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct OpenOptions {
+    pub verify_crc: bool,
+    pub verify_contents: bool,
+}
+
 #[derive(Debug)]
 pub struct UOPackage {
     header: PackageHdr,
@@ -386,28 +554,63 @@ impl UOPackage {
     const WRITE_VERSION: u32 = 5;
 
     pub fn new<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        Self::open(reader, OpenOptions::default())
+    }
+
+    pub fn open<R: Read + Seek>(reader: &mut R, options: OpenOptions) -> Result<Self> {
         let header = PackageHdr::new(reader)?;
 
-        match header.version {
-            4 | 5 => {}
-            _ => return Err(Error::UnsupportedVersion(header.version)),
-        }
+        let mut package = UOPackage {
+            header,
+            files: vec![],
+        };
+
+        package.read_files(reader, options)?;
+        Ok(package)
+    }
+
+    // Like open, but decompresses the files on multiple threads once their
+    // contents have been read
+    #[cfg(feature = "parallel")]
+    pub fn open_parallel<R: Read + Seek>(reader: &mut R, options: OpenOptions) -> Result<Self> {
+        let header = PackageHdr::new(reader)?;
 
         let mut package = UOPackage {
             header,
             files: vec![],
         };
 
-        package.read_files(reader)?;
+        package.read_files_parallel(reader, options)?;
         Ok(package)
     }
 
     pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
-        assert!(BlockHdr::size(self.header.block_size) <= ALIGNMENT as usize);
+        self.write_with_version(writer, Self::WRITE_VERSION)
+    }
+
+    pub fn write_with_version<W: Write + Seek>(&self, writer: &mut W, version: u32) -> Result<()> {
+        if !matches!(version, 4 | 5) {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        // Each block header must fit within its page, so it doesn't overlap
+        // the next block or the file entries that follow the blocks
+        let first_block_end =
+            self.header.first_block + BlockHdr::size(self.header.block_size) as u64;
+        if self.header.block_size == 0 || first_block_end > ALIGNMENT {
+            return Err(Error::InvalidData(format!(
+                "blocks of {} files do not fit within {} bytes",
+                self.header.block_size, ALIGNMENT
+            )));
+        }
 
         // Write the package header
         writer.seek(SeekFrom::Start(0))?;
-        self.header.write(writer)?;
+        PackageHdr {
+            version,
+            ..self.header
+        }
+        .write(writer)?;
 
         // Calculate position of first file entry
         let block_size = self.header.block_size as usize;
@@ -422,14 +625,21 @@ impl UOPackage {
             // Round up to next alignment for the block header
             let next_block = ((block_pos / ALIGNMENT) + 1) * ALIGNMENT;
 
-            self.write_block(writer, files, block_pos, Some(next_block), &mut file_pos)?;
+            self.write_block(
+                writer,
+                version,
+                files,
+                block_pos,
+                Some(next_block),
+                &mut file_pos,
+            )?;
 
             block_pos = next_block;
         }
 
         let remainder = file_blocks.remainder();
         if !remainder.is_empty() {
-            self.write_block(writer, remainder, block_pos, None, &mut file_pos)?;
+            self.write_block(writer, version, remainder, block_pos, None, &mut file_pos)?;
         }
 
         Ok(())
@@ -438,6 +648,7 @@ impl UOPackage {
     fn write_block<W: Write + Seek>(
         &self,
         writer: &mut W,
+        version: u32,
         files: &[UOPackageFile],
         block_pos: u64,
         next_block: Option<u64>,
@@ -446,9 +657,9 @@ impl UOPackage {
         let mut headers = Vec::<FileHdr>::with_capacity(files.len());
         for file in files {
             writer.seek(SeekFrom::Start(*file_pos))?;
-            let (compressed_size, raw_size) = file.write(writer, Self::WRITE_VERSION)?;
+            let (compressed_size, raw_size, header_crc) = file.write(writer, version)?;
 
-            let header_size = match Self::WRITE_VERSION {
+            let header_size = match version {
                 4 => Ok(UOPackageFile::HEADER_SIZE_V4),
                 5 => Ok(UOPackageFile::HEADER_SIZE_V5),
                 version => Err(Error::UnsupportedVersion(version)),
@@ -460,7 +671,7 @@ impl UOPackage {
                 compressed_size,
                 raw_size,
                 hash: file.hash,
-                _header_crc: 0,
+                header_crc,
                 entry_type: file.file_type.is_compressed() as u16,
             });
 
@@ -486,24 +697,97 @@ impl UOPackage {
         Ok(self.files.iter().find(|f| f.hash == hash))
     }
 
-    fn read_files<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
-        // Read all of the block headers
-        let mut block_pos = self.header.first_block;
-        while block_pos != 0 {
-            reader.seek(SeekFrom::Start(block_pos))?;
-            let block = BlockHdr::new(reader)?;
-            for header in block.headers {
-                if header.position == 0 {
-                    continue;
-                }
+    pub fn get_file_mut<'a>(&'a mut self, path: &str) -> Result<Option<&'a mut UOPackageFile>> {
+        let hash = uop_hash(path)?;
+        Ok(self.files.iter_mut().find(|f| f.hash == hash))
+    }
 
-                reader.seek(SeekFrom::Start(header.position))?;
-                self.files
-                    .push(UOPackageFile::new(reader, &header, self.header.version)?);
-            }
+    pub fn add_file(&mut self, file: UOPackageFile) -> Result<()> {
+        if self.files.iter().any(|f| f.hash == file.hash) {
+            return Err(Error::DuplicateFile(file.hash));
+        }
+
+        self.files.push(file);
+        self.update_files_count()
+    }
+
+    pub fn remove_file(&mut self, path: &str) -> Result<Option<UOPackageFile>> {
+        let hash = uop_hash(path)?;
+        let file = match self.files.iter().position(|f| f.hash == hash) {
+            Some(index) => self.files.remove(index),
+            None => return Ok(None),
+        };
 
-            block_pos = block.next_block;
+        self.update_files_count()?;
+        Ok(Some(file))
+    }
+
+    // The replacement is stored under the hash of the given path,
+    // and the file it replaced is returned
+    pub fn replace_file(&mut self, path: &str, mut file: UOPackageFile) -> Result<UOPackageFile> {
+        file.hash = uop_hash(path)?;
+
+        match self.files.iter_mut().find(|f| f.hash == file.hash) {
+            Some(existing) => Ok(std::mem::replace(existing, file)),
+            None => Err(Error::MissingFile(path.to_string())),
         }
+    }
+
+    fn update_files_count(&mut self) -> Result<()> {
+        self.header.files_count = self
+            .files
+            .len()
+            .try_into()
+            .map_err(|_| Error::TooManyFiles(self.files.len()))?;
+
+        Ok(())
+    }
+
+    fn read_files<R: Read + Seek>(&mut self, reader: &mut R, options: OpenOptions) -> Result<()> {
+        for header in read_file_headers(reader, self.header.first_block)? {
+            self.files.push(UOPackageFile::read(
+                reader,
+                &header,
+                self.header.version,
+                options,
+            )?);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    fn read_files_parallel<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        options: OpenOptions,
+    ) -> Result<()> {
+        use rayon::prelude::*;
+
+        // Reading is sequential, only decoding what was read is parallel
+        let entries = read_file_headers(reader, self.header.first_block)?
+            .into_iter()
+            .map(|header| {
+                let mut entry =
+                    vec![0u8; header.header_size as usize + header.compressed_size as usize];
+                reader.seek(SeekFrom::Start(header.position))?;
+                reader.read_exact(entry.as_mut_slice())?;
+
+                Ok((header, entry))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let version = self.header.version;
+        self.files = entries
+            .into_par_iter()
+            .map(|(header, entry)| {
+                let header = FileHdr {
+                    position: 0,
+                    ..header
+                };
+                UOPackageFile::read(&mut Cursor::new(entry), &header, version, options)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(())
     }
@@ -523,6 +807,63 @@ impl TryFrom<Vec<UOPackageFile>> for UOPackage {
     }
 }
 
+// Reads only the file entries of a package up front, leaving the contents
+// on disk until a file is requested. Useful for large packages such as maps.
+pub struct UOPackageReader<R> {
+    reader: R,
+    header: PackageHdr,
+    files: Vec<FileHdr>,
+    options: OpenOptions,
+}
+
+impl<R: Read + Seek> UOPackageReader<R> {
+    pub fn new(reader: R) -> Result<Self> {
+        Self::open(reader, OpenOptions::default())
+    }
+
+    pub fn open(mut reader: R, options: OpenOptions) -> Result<Self> {
+        let header = PackageHdr::new(&mut reader)?;
+        let files = read_file_headers(&mut reader, header.first_block)?;
+
+        Ok(Self {
+            reader,
+            header,
+            files,
+            options,
+        })
+    }
+
+    pub fn hashes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.files.iter().map(|header| header.hash)
+    }
+
+    pub fn get_file(&mut self, path: &str) -> Result<Option<UOPackageFile>> {
+        let hash = uop_hash(path)?;
+        match self.files.iter().find(|header| header.hash == hash) {
+            Some(header) => Ok(Some(UOPackageFile::read(
+                &mut self.reader,
+                header,
+                self.header.version,
+                self.options,
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn open_file(&mut self, path: &str) -> Result<Option<impl Read + Seek>> {
+        Ok(self.get_file(path)?.map(|file| Cursor::new(file.contents)))
+    }
+}
+
+impl<R> fmt::Debug for UOPackageReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UOPackageReader")
+            .field("header", &self.header)
+            .field("files", &self.files.len())
+            .finish()
+    }
+}
+
 // UOP file name hash algorithm adapted from
 // https://github.com/ClassicUO/ClassicUO/blob/69857dc07b5d84ecf0e404df3fe3c8514df3a4c7/src/IO/UOFileUop.cs#L198
 // which turns out to just be lookup3 from Bob Jenkins:
@@ -539,10 +880,37 @@ pub fn uop_hash(input: &str) -> Result<u64> {
     Ok(hashers::jenkins::lookup3(input.as_bytes()))
 }
 
+// The header CRC of each file is the Adler-32 checksum of its metadata, and
+// the contents checksum in its version 5 metadata is that of its contents
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &by in data {
+        a = (a + by as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+
+    (b << 16) | a
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const VERIFY_CRC: OpenOptions = OpenOptions {
+        verify_crc: true,
+        verify_contents: false,
+    };
+    const VERIFY_CONTENTS: OpenOptions = OpenOptions {
+        verify_crc: false,
+        verify_contents: true,
+    };
+    const VERIFY_ALL: OpenOptions = OpenOptions {
+        verify_crc: true,
+        verify_contents: true,
+    };
+
     #[test]
     fn hashes_map_paths() {
         let input = "build/map4legacymul/00000000.dat";
@@ -550,4 +918,449 @@ mod tests {
 
         assert_eq!(output, 0xDEA39C8655BA717C);
     }
+
+    fn package_bytes() -> Vec<u8> {
+        let files = vec![UOPackageFile {
+            hash: uop_hash("build/test/00000000.dat").unwrap(),
+            file_type: FileType::Unknown,
+            timestamp: None,
+            contents: b"uoverse".to_vec(),
+        }];
+
+        let package: UOPackage = files.try_into().unwrap();
+        let mut writer = Cursor::new(Vec::<u8>::new());
+        package.write(&mut writer).unwrap();
+
+        writer.into_inner()
+    }
+
+    #[test]
+    fn adler32_checksum() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    }
+
+    #[test]
+    fn verifies_header_crc() {
+        let bytes = package_bytes();
+
+        let package = UOPackage::open(&mut Cursor::new(bytes), VERIFY_CRC).unwrap();
+        assert_eq!(package.files.len(), 1);
+        assert_eq!(package.files[0].contents, b"uoverse");
+    }
+
+    #[test]
+    fn rejects_corrupted_header() {
+        let mut bytes = package_bytes();
+
+        // The single file is written on the page after the block header,
+        // starting with its metadata
+        bytes[ALIGNMENT as usize] ^= 0x01;
+
+        match UOPackage::open(&mut Cursor::new(bytes.as_slice()), VERIFY_CRC) {
+            Err(Error::InvalidData(_)) => {}
+            result => panic!("expected invalid data, got {:?}", result),
+        }
+
+        assert!(
+            UOPackage::open(&mut Cursor::new(bytes.as_slice()), OpenOptions::default()).is_ok()
+        );
+    }
+
+    #[test]
+    fn verifies_contents_checksum() {
+        let bytes = package_bytes();
+
+        // The checksum follows the file type and remaining metadata size
+        let checksum = &bytes[ALIGNMENT as usize + 4..ALIGNMENT as usize + 8];
+        assert_eq!(checksum, adler32(b"uoverse").to_le_bytes());
+
+        let package = UOPackage::open(&mut Cursor::new(bytes), VERIFY_CONTENTS).unwrap();
+        assert_eq!(package.files[0].contents, b"uoverse");
+    }
+
+    #[test]
+    fn rejects_corrupted_contents() {
+        let mut bytes = package_bytes();
+
+        // The contents are stored raw, right after the metadata
+        bytes[ALIGNMENT as usize + UOPackageFile::HEADER_SIZE_V5] ^= 0x01;
+
+        match UOPackage::open(&mut Cursor::new(bytes.as_slice()), VERIFY_CONTENTS) {
+            Err(Error::InvalidData(_)) => {}
+            result => panic!("expected invalid data, got {:?}", result),
+        }
+
+        let package =
+            UOPackage::open(&mut Cursor::new(bytes.as_slice()), OpenOptions::default()).unwrap();
+        assert_eq!(package.files[0].contents, b"toverse");
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn reads_in_parallel() {
+        let files = (0..20u8)
+            .map(|num| UOPackageFile {
+                hash: uop_hash(&format!("build/test/{:08}.dat", num)).unwrap(),
+                file_type: match num % 2 {
+                    0 => FileType::Compressed,
+                    _ => FileType::Unknown,
+                },
+                timestamp: None,
+                contents: (0..(num as usize * 1000))
+                    .map(|by| (by % 251) as u8)
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        let package: UOPackage = files.try_into().unwrap();
+        let mut writer = Cursor::new(Vec::<u8>::new());
+        package.write(&mut writer).unwrap();
+        let bytes = writer.into_inner();
+
+        let sequential = UOPackage::open(&mut Cursor::new(bytes.as_slice()), VERIFY_ALL).unwrap();
+        let parallel =
+            UOPackage::open_parallel(&mut Cursor::new(bytes.as_slice()), VERIFY_ALL).unwrap();
+
+        assert_eq!(parallel.files.len(), sequential.files.len());
+        for (parallel, sequential) in parallel.files.iter().zip(sequential.files.iter()) {
+            assert_eq!(parallel.hash, sequential.hash);
+            assert_eq!(parallel.contents, sequential.contents);
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_file_size() {
+        // The raw size of the only file, within the first block
+        const RAW_SIZE: usize = FIRST_BLOCK as usize + BlockHdr::BASE_SIZE + 16;
+        const ENTRY_TYPE: usize = FIRST_BLOCK as usize + FileHdr::SIZE + BlockHdr::BASE_SIZE - 2;
+
+        let mut bytes = package_bytes();
+        bytes[RAW_SIZE..(RAW_SIZE + 4)].copy_from_slice(&100u32.to_le_bytes());
+
+        let hash = uop_hash("build/test/00000000.dat").unwrap();
+        match UOPackage::open(&mut Cursor::new(bytes.as_slice()), OpenOptions::default()) {
+            Err(Error::InvalidData(msg)) => assert_eq!(
+                msg,
+                format!("stored size of file {:016X} is 7 but expected 100", hash)
+            ),
+            result => panic!("expected invalid data, got {:?}", result),
+        }
+
+        let mut bytes = package_bytes();
+        bytes[ENTRY_TYPE] = 2;
+
+        match UOPackage::open(&mut Cursor::new(bytes.as_slice()), OpenOptions::default()) {
+            Err(Error::InvalidData(msg)) => assert_eq!(
+                msg,
+                format!("entry type 2 of file {:016X} is unknown", hash)
+            ),
+            result => panic!("expected invalid data, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_format() {
+        let mut bytes = package_bytes();
+
+        // The format follows the magic and version
+        bytes[8..12].copy_from_slice(&0x12345678u32.to_le_bytes());
+
+        match UOPackage::open(&mut Cursor::new(bytes.as_slice()), OpenOptions::default()) {
+            Err(Error::InvalidData(msg)) => {
+                assert_eq!(msg, "package format 12345678 is not FD23EC43")
+            }
+            result => panic!("expected invalid data, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn reads_foreign_block_size() {
+        let files = (0..5u8)
+            .map(|num| test_file(&format!("build/test/{:08}.dat", num), &[num; 10]))
+            .collect::<Vec<_>>();
+
+        let mut package: UOPackage = files.try_into().unwrap();
+        package.header.block_size = 2;
+        let mut writer = Cursor::new(Vec::<u8>::new());
+        package.write(&mut writer).unwrap();
+        let mut bytes = writer.into_inner();
+
+        // Only the blocks themselves say how many entries they have
+        bytes[20..24].copy_from_slice(&1000u32.to_le_bytes());
+
+        let package = UOPackage::open(&mut Cursor::new(bytes.as_slice()), VERIFY_CRC).unwrap();
+        assert_eq!(package.files.len(), 5);
+        assert_eq!(
+            package
+                .get_file("build/test/00000004.dat")
+                .unwrap()
+                .map(|f| f.contents.as_slice()),
+            Some(&[4u8; 10][..])
+        );
+    }
+
+    #[test]
+    fn rejects_bad_next_block() {
+        const NEXT_BLOCK: usize = FIRST_BLOCK as usize + size_of::<u32>();
+
+        for (next_block, expected) in [
+            (
+                0xFFFF_FFFF_0000_0000,
+                "block at 0xFFFFFFFF00000000 is past the end",
+            ),
+            (FIRST_BLOCK, "block at 0x20 is linked to more than once"),
+        ] {
+            let mut bytes = package_bytes();
+            bytes[NEXT_BLOCK..(NEXT_BLOCK + 8)].copy_from_slice(&u64::to_le_bytes(next_block));
+
+            match UOPackageReader::open(Cursor::new(bytes.as_slice()), OpenOptions::default()) {
+                Err(Error::InvalidData(msg)) => assert!(msg.starts_with(expected), "{}", msg),
+                result => panic!("expected invalid data, got {:?}", result),
+            }
+        }
+
+        // A block that claims more entries than fit in the package
+        let mut bytes = package_bytes();
+        bytes[FIRST_BLOCK as usize..NEXT_BLOCK].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            UOPackage::open(&mut Cursor::new(bytes.as_slice()), OpenOptions::default()),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    // Counts the bytes read through it, to check what was loaded
+    struct CountingReader<R> {
+        inner: R,
+        count: usize,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let amount = self.inner.read(buf)?;
+            self.count += amount;
+            Ok(amount)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn reads_single_file_lazily() {
+        const FILE_LEN: usize = 0x4000;
+
+        let files = (0..3u8)
+            .map(|num| UOPackageFile {
+                hash: uop_hash(&format!("build/test/{:08}.dat", num)).unwrap(),
+                file_type: FileType::Unknown,
+                timestamp: None,
+                contents: vec![num; FILE_LEN],
+            })
+            .collect::<Vec<_>>();
+
+        let package: UOPackage = files.try_into().unwrap();
+        let mut writer = Cursor::new(Vec::<u8>::new());
+        package.write(&mut writer).unwrap();
+
+        let mut reader = UOPackageReader::open(
+            CountingReader {
+                inner: Cursor::new(writer.into_inner()),
+                count: 0,
+            },
+            VERIFY_CRC,
+        )
+        .unwrap();
+        assert_eq!(reader.hashes().count(), 3);
+
+        // Opening should only read the package header and the block of entries
+        let entries_len = 0x1C + BlockHdr::size(3);
+        assert_eq!(reader.reader.count, entries_len);
+
+        let mut file = reader
+            .open_file("build/test/00000001.dat")
+            .unwrap()
+            .expect("file should be in the package");
+        let mut contents = vec![];
+        file.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, vec![1u8; FILE_LEN]);
+
+        // Beyond the entries, only the requested file's metadata and contents
+        // should have been read, not those of the other files
+        let read_len = reader.reader.count - entries_len;
+        assert!(read_len > FILE_LEN);
+        assert!(read_len <= FILE_LEN + UOPackageFile::HEADER_SIZE_V5 * 2);
+
+        assert!(reader
+            .get_file("build/test/00000003.dat")
+            .unwrap()
+            .is_none());
+    }
+
+    fn test_file(path: &str, contents: &[u8]) -> UOPackageFile {
+        UOPackageFile {
+            hash: uop_hash(path).unwrap(),
+            file_type: FileType::Unknown,
+            timestamp: None,
+            contents: contents.to_vec(),
+        }
+    }
+
+    #[test]
+    fn adds_file() {
+        let mut package: UOPackage = vec![test_file("build/test/00000000.dat", b"first")]
+            .try_into()
+            .unwrap();
+
+        package
+            .add_file(test_file("build/test/00000001.dat", b"second"))
+            .unwrap();
+
+        assert_eq!(package.header.files_count, 2);
+        assert_eq!(
+            package
+                .get_file("build/test/00000001.dat")
+                .unwrap()
+                .map(|f| f.contents.as_slice()),
+            Some(&b"second"[..])
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_file() {
+        let mut package: UOPackage = vec![test_file("build/test/00000000.dat", b"first")]
+            .try_into()
+            .unwrap();
+
+        match package.add_file(test_file("build/test/00000000.dat", b"again")) {
+            Err(Error::DuplicateFile(hash)) => {
+                assert_eq!(hash, uop_hash("build/test/00000000.dat").unwrap())
+            }
+            result => panic!("expected duplicate file, got {:?}", result),
+        }
+
+        assert_eq!(package.header.files_count, 1);
+        assert_eq!(
+            package
+                .get_file("build/test/00000000.dat")
+                .unwrap()
+                .unwrap()
+                .contents,
+            b"first"
+        );
+    }
+
+    #[test]
+    fn replaces_file() {
+        let mut package: UOPackage = vec![test_file("build/test/00000000.dat", b"first")]
+            .try_into()
+            .unwrap();
+
+        let old = package
+            .replace_file("build/test/00000000.dat", test_file("unused", b"replaced"))
+            .unwrap();
+        assert_eq!(old.contents, b"first");
+        assert_eq!(
+            package
+                .get_file("build/test/00000000.dat")
+                .unwrap()
+                .unwrap()
+                .contents,
+            b"replaced"
+        );
+
+        assert!(matches!(
+            package.replace_file("build/test/00000001.dat", test_file("unused", b"missing")),
+            Err(Error::MissingFile(_))
+        ));
+    }
+
+    #[test]
+    fn removes_file_and_writes() {
+        let mut package: UOPackage = vec![
+            test_file("build/test/00000000.dat", b"first"),
+            test_file("build/test/00000001.dat", b"second"),
+            test_file("build/test/00000002.dat", b"third"),
+        ]
+        .try_into()
+        .unwrap();
+
+        let removed = package.remove_file("build/test/00000001.dat").unwrap();
+        assert_eq!(removed.map(|f| f.contents), Some(b"second".to_vec()));
+        assert!(package
+            .remove_file("build/test/00000001.dat")
+            .unwrap()
+            .is_none());
+        assert_eq!(package.header.files_count, 2);
+
+        package
+            .get_file_mut("build/test/00000002.dat")
+            .unwrap()
+            .unwrap()
+            .contents
+            .push(b'!');
+
+        let mut writer = Cursor::new(Vec::<u8>::new());
+        package.write(&mut writer).unwrap();
+
+        writer.set_position(0);
+        let package = UOPackage::open(&mut writer, VERIFY_CRC).unwrap();
+
+        assert_eq!(package.header.files_count, 2);
+        assert_eq!(package.files.len(), 2);
+        assert!(package
+            .get_file("build/test/00000001.dat")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            package
+                .get_file("build/test/00000002.dat")
+                .unwrap()
+                .unwrap()
+                .contents,
+            b"third!"
+        );
+    }
+
+    #[test]
+    fn writes_version4() {
+        let mut files = vec![
+            test_file("build/test/00000000.dat", b"first"),
+            test_file("build/test/00000001.dat", b"second"),
+        ];
+        files[0].timestamp = Some(0x01D6_F2A1_2345_6789);
+        files[1].file_type = FileType::Compressed;
+        files[1].timestamp = Some(0x01D6_F2A1_9876_5432);
+
+        let package: UOPackage = files.try_into().unwrap();
+        let mut writer = Cursor::new(Vec::<u8>::new());
+        package.write_with_version(&mut writer, 4).unwrap();
+
+        writer.set_position(0);
+        let read = UOPackage::open(&mut writer, VERIFY_CRC).unwrap();
+
+        assert_eq!(read.header.version, 4);
+        assert_eq!(read.files.len(), package.files.len());
+        for (read, file) in read.files.iter().zip(&package.files) {
+            assert_eq!(read.hash, file.hash);
+            assert_eq!(read.file_type as u16, file.file_type as u16);
+            assert_eq!(read.timestamp, file.timestamp);
+            assert_eq!(read.contents, file.contents);
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_write_version() {
+        let package: UOPackage = vec![test_file("build/test/00000000.dat", b"first")]
+            .try_into()
+            .unwrap();
+
+        let mut writer = Cursor::new(Vec::<u8>::new());
+        assert!(matches!(
+            package.write_with_version(&mut writer, 3),
+            Err(Error::UnsupportedVersion(3))
+        ));
+    }
 }
@@ -1,38 +1,86 @@
-use std::{convert::TryInto, fs::OpenOptions, io::Write};
+use std::{
+    convert::TryInto,
+    env::args,
+    fs::{File, OpenOptions},
+    io::{BufReader, Write},
+    sync::Arc,
+};
 use uoverse_tools::{
     archive::uo_package::UOPackage,
     map::{Tile, UOMap},
+    tiledata::{Format, TileData},
 };
 
+// Usage: mapgen [--mul] [--tiledata=PATH] [IMAGE [OUTPUT]]
+//
+// Without an image, the map is filled with a single placeholder tile. Given
+// a High Seas tiledata.mul, every tile must be a land tile that it has.
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let prefix = "build/map6legacymul";
 
-    let mut map = UOMap::new(256, 256)?;
-    for x in 0..256 {
-        for y in 0..256 {
-            map.set(
-                x,
-                y,
-                Tile {
-                    kind: 0x0004,
-                    height: 0x00,
-                },
-            )?;
-        }
+    // Write a .uop package unless the classic .mul format is requested
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mul = flags.iter().any(|arg| arg == "--mul");
+    let tile_data_path = flags.iter().find_map(|arg| arg.strip_prefix("--tiledata="));
+    if let Some(flag) = flags
+        .iter()
+        .find(|arg| *arg != "--mul" && !arg.starts_with("--tiledata="))
+    {
+        return Err(format!("unknown option {}", flag).into());
     }
 
-    let files = map.into_files(prefix)?;
-    let package: UOPackage = files.try_into()?;
-    dbg!(&package);
+    let map = match paths.first() {
+        Some(image_path) => UOMap::open_image(image_path)?,
+        None => {
+            let mut map = UOMap::new(256, 256)?;
+            for x in 0..256 {
+                for y in 0..256 {
+                    map.set(
+                        x,
+                        y,
+                        Tile {
+                            kind: 0x0004,
+                            height: 0x00,
+                        },
+                    )?;
+                }
+            }
+            map
+        }
+    };
+
+    let map = match tile_data_path {
+        Some(path) => {
+            let mut reader = BufReader::new(File::open(path)?);
+            let tile_data = TileData::from_reader(&mut reader, Format::HighSeas)?;
+            map.with_tile_data(Arc::new(tile_data))
+        }
+        None => map,
+    };
+
+    let output_path = match paths.get(1) {
+        Some(output_path) => output_path.as_str(),
+        None if mul => "map6.mul",
+        None => "map6LegacyMUL.uop",
+    };
 
-    let mut package_file = OpenOptions::new()
+    let mut map_file = OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
-        .open("map6LegacyMUL.uop")?;
+        .open(output_path)?;
 
-    package.write(&mut package_file)?;
-    package_file.flush()?;
+    if mul {
+        map.write_mul(&mut map_file)?;
+    } else {
+        let files = map.into_files(prefix)?;
+        let package: UOPackage = files.try_into()?;
+        dbg!(&package);
+
+        package.write(&mut map_file)?;
+    }
+    map_file.flush()?;
 
     Ok(())
 }
@@ -1,30 +1,44 @@
+use clap::Parser;
 use std::{
     convert::TryInto,
-    env::args,
     fs::{self, OpenOptions},
     io::Write,
+    path::{Path, PathBuf},
+};
+use uoverse_tools::archive::{
+    manifest::Manifest,
+    uo_package::{uop_hash, FileType, UOPackage, UOPackageFile},
 };
-use uoverse_tools::archive::uo_package::{uop_hash, FileType, UOPackage, UOPackageFile};
 
 type Error = Box<dyn std::error::Error>;
 
+#[derive(Parser)]
+#[command(about = "Pack files into a UOP package")]
+struct Args {
+    /// Path of the package to write
+    package: PathBuf,
+
+    /// Files to include, hashed by the path as given
+    files: Vec<String>,
+
+    /// Manifest of `<logical path>=<local path>` lines, with local paths
+    /// relative to the manifest
+    #[arg(long)]
+    names: Option<PathBuf>,
+}
+
 fn main() -> Result<(), Error> {
-    let mut args = args();
-    if args.len() < 3 {
-        println!(
-            "Usage: {} <package path> <file to include ...>",
-            args.next().unwrap()
-        );
-
-        return Ok(());
-    }
+    let args = Args::parse();
 
-    let mut args = args.skip(1);
-    let package_path = args.next().unwrap();
-    let file_paths: Vec<String> = args.collect();
+    let mut files = match &args.names {
+        Some(names) => {
+            let dir = names.parent().unwrap_or_else(|| Path::new(""));
+            Manifest::open(names)?.read_files(dir)?
+        }
+        None => vec![],
+    };
 
-    let mut files = Vec::<UOPackageFile>::with_capacity(file_paths.len());
-    for path in file_paths {
+    for path in args.files {
         files.push(UOPackageFile {
             hash: uop_hash(path.as_str())?,
             file_type: FileType::Compressed,
@@ -40,7 +54,7 @@ fn main() -> Result<(), Error> {
         .write(true)
         .create(true)
         .truncate(true)
-        .open(package_path)?;
+        .open(args.package)?;
 
     package.write(&mut package_file)?;
     package_file.flush()?;
@@ -1,37 +1,55 @@
 #![feature(io_error_more)]
 
-use std::{env::args, fs, io, path};
-use uoverse_tools::archive::uo_package::UOPackage;
+use clap::Parser;
+use std::{fs, io, path::PathBuf};
+use uoverse_tools::archive::{manifest::Manifest, uo_package::UOPackage};
+
+#[derive(Parser)]
+#[command(about = "Unpack the files of a UOP package")]
+struct Args {
+    /// Path of the package to read
+    package: PathBuf,
+
+    /// Directory to unpack into, defaults to `<package name>_unpack`
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Manifest of `<logical path>=<local path>` lines, used to name the
+    /// unpacked files instead of their hashes
+    #[arg(long)]
+    names: Option<PathBuf>,
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    if args().len() > 1 {
-        let path_arg = args().last().unwrap();
-        let package_path = path::Path::new(&path_arg);
-        let package_name = package_path
-            .file_name()
-            .ok_or(io::Error::new(
-                io::ErrorKind::IsADirectory,
-                "Path must be a UOP file",
-            ))?
-            .to_str()
-            .ok_or(io::Error::new(
-                io::ErrorKind::InvalidFilename,
-                "UOP file path was invalid",
-            ))?;
-
-        let mut package_file = fs::OpenOptions::new().read(true).open(package_path)?;
-        let package = UOPackage::new(&mut package_file)?;
-        dbg!(&package);
-
-        let dir_name = format!("{}_unpack", package_name);
-        fs::create_dir(dir_name.as_str())?;
-        for file in package.files {
-            fs::write(
-                format!("./{}/{:016X}.dat", dir_name, file.hash),
-                file.contents.as_slice(),
-            )?;
-        }
-    }
+    let args = Args::parse();
+
+    let package_name = args
+        .package
+        .file_name()
+        .ok_or(io::Error::new(
+            io::ErrorKind::IsADirectory,
+            "Path must be a UOP file",
+        ))?
+        .to_str()
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidFilename,
+            "UOP file path was invalid",
+        ))?;
+
+    let manifest = match &args.names {
+        Some(names) => Manifest::open(names)?,
+        None => Manifest::default(),
+    };
+
+    let mut package_file = fs::OpenOptions::new().read(true).open(&args.package)?;
+    let package = UOPackage::new(&mut package_file)?;
+    dbg!(&package);
+
+    let dir = args
+        .output
+        .unwrap_or_else(|| format!("{}_unpack", package_name).into());
+    fs::create_dir(&dir)?;
+    manifest.unpack(&package, &dir)?;
 
     Ok(())
 }
@@ -0,0 +1,188 @@
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::{fmt, io::Read};
+
+use crate::tiledata::read_name;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("hues are truncated within group {0}")]
+    Truncated(usize),
+
+    #[error("i/o failure {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Hue {
+    pub colors: [u16; 32], // ARGB1555, from darkest to brightest
+    pub table_start: u16,
+    pub table_end: u16,
+    pub name: String,
+}
+
+impl Hue {
+    fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut colors = [0u16; 32];
+        reader.read_u16_into::<LittleEndian>(&mut colors)?;
+
+        Ok(Self {
+            colors,
+            table_start: reader.read_u16::<LittleEndian>()?,
+            table_end: reader.read_u16::<LittleEndian>()?,
+            name: read_name(reader)?,
+        })
+    }
+
+    pub fn rgb(&self, shade: usize) -> Option<[u8; 3]> {
+        self.colors.get(shade).map(|&color| rgb888(color))
+    }
+}
+
+// Expands the 5-bit channels of an ARGB1555 color to 8 bits each,
+// replicating the high bits so that full intensity maps to 0xFF
+pub fn rgb888(color: u16) -> [u8; 3] {
+    let expand = |channel: u16| {
+        let channel = (channel & 0x1F) as u8;
+        (channel << 3) | (channel >> 2)
+    };
+
+    [expand(color >> 10), expand(color >> 5), expand(color)]
+}
+
+// Hues are stored in groups of 8, each group preceded by a header
+const GROUP_SIZE: usize = 8;
+
+pub struct Hues {
+    hues: Vec<Hue>,
+}
+
+impl Hues {
+    pub const COUNT: usize = 3000;
+
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut hues = Vec::with_capacity(Self::COUNT);
+
+        // The groups continue until the end of the file
+        for group in 0.. {
+            match reader.read_u32::<LittleEndian>() {
+                Ok(_) => {} // Group header, unused
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            }
+
+            for _ in 0..GROUP_SIZE {
+                match Hue::from_reader(reader) {
+                    Ok(hue) => hues.push(hue),
+                    Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                        return Err(Error::Truncated(group))
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(Self { hues })
+    }
+
+    pub fn len(&self) -> usize {
+        self.hues.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hues.is_empty()
+    }
+
+    // Indices are zero-based, so hue N as sent by the client is index N - 1
+    pub fn hue(&self, index: usize) -> Option<&Hue> {
+        self.hues.get(index)
+    }
+}
+
+impl fmt::Debug for Hues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("Hues({})", self.hues.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::WriteBytesExt;
+
+    // Only the hues used by the tests have any data, the rest are black
+    fn hues_data() -> Vec<u8> {
+        let mut data = vec![];
+
+        for index in 0..Hues::COUNT {
+            if index % GROUP_SIZE == 0 {
+                data.write_u32::<LittleEndian>(0).unwrap();
+            }
+
+            let (colors, name): ([u16; 32], &str) = match index {
+                0 => ([0x7FFF; 32], "Color 1 (White)"),
+                1 => {
+                    let mut colors = [0u16; 32];
+                    for (shade, color) in colors.iter_mut().enumerate() {
+                        *color = (shade as u16) << 10; // Ramp of reds
+                    }
+                    (colors, "Color 2")
+                }
+                _ => ([0u16; 32], ""),
+            };
+
+            for color in colors.iter() {
+                data.write_u16::<LittleEndian>(*color).unwrap();
+            }
+            data.write_u16::<LittleEndian>(colors[0]).unwrap();
+            data.write_u16::<LittleEndian>(colors[31]).unwrap();
+
+            let mut padded = [0u8; 20];
+            padded[..name.len()].copy_from_slice(name.as_bytes());
+            data.extend_from_slice(&padded);
+        }
+
+        data
+    }
+
+    #[test]
+    fn parses_hues() {
+        let data = hues_data();
+        let hues = Hues::from_reader(&mut data.as_slice()).unwrap();
+        assert_eq!(hues.len(), Hues::COUNT);
+
+        let white = hues.hue(0).unwrap();
+        assert_eq!(white.name, "Color 1 (White)");
+        assert_eq!(white.colors[0], 0x7FFF);
+        assert_eq!(white.rgb(31), Some([0xFF, 0xFF, 0xFF]));
+
+        let red = hues.hue(1).unwrap();
+        assert_eq!(red.name, "Color 2");
+        assert_eq!((red.colors[1], red.colors[31]), (0x0400, 0x7C00));
+        assert_eq!((red.table_start, red.table_end), (0x0000, 0x7C00));
+        assert_eq!(red.rgb(31), Some([0xFF, 0x00, 0x00]));
+
+        assert!(hues.hue(Hues::COUNT).is_none());
+    }
+
+    #[test]
+    fn converts_to_rgb888() {
+        assert_eq!(rgb888(0x0000), [0x00, 0x00, 0x00]);
+        assert_eq!(rgb888(0x8000), [0x00, 0x00, 0x00]); // Alpha is ignored
+        assert_eq!(rgb888(0x03E0), [0x00, 0xFF, 0x00]);
+        assert_eq!(rgb888(0x001F), [0x00, 0x00, 0xFF]);
+        assert_eq!(rgb888(0x4210), [0x84, 0x84, 0x84]);
+    }
+
+    #[test]
+    fn rejects_truncated_group() {
+        let mut data = hues_data();
+        data.truncate(data.len() - 5);
+
+        assert!(matches!(
+            Hues::from_reader(&mut data.as_slice()),
+            Err(Error::Truncated(374))
+        ));
+    }
+}
@@ -2,4 +2,7 @@
 #![feature(generic_const_exprs)]
 
 pub mod archive;
+pub mod hues;
 pub mod map;
+pub mod multi;
+pub mod tiledata;
@@ -1,11 +1,23 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::{
+    borrow::Cow,
+    collections::HashSet,
     convert::{TryFrom, TryInto},
-    io::{Cursor, Read, Write},
+    fs::File,
+    io::{Cursor, Read, Seek, Write},
     mem::size_of,
+    sync::Arc,
 };
 
-use crate::archive::uo_package::{self, FileType, UOPackage, UOPackageFile};
+use crate::archive::{
+    mul_index,
+    uo_package::{self, FileType, UOPackage, UOPackageFile, UOPackageReader},
+};
+use crate::tiledata::{TileData, TileFlags};
+
+pub mod heightmap;
+pub mod statics;
+pub use statics::{StaticItem, Statics, UOStatics};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -15,19 +27,31 @@ pub enum Error {
     #[error("map position ({x},{y}) is invalid")]
     InvalidPos { x: u32, y: u32 },
 
+    #[error("land tile {0:#06X} is not in the tile data")]
+    InvalidTile(u16),
+
     #[error("map file in package is invalid because {0}")]
     InvalidFile(#[from] uo_package::Error),
 
     #[error("no map files in package")]
     NoFiles,
 
+    #[error("statics index entry for block {0} is invalid")]
+    InvalidStaticsIndex(usize),
+
+    #[error("statics index is invalid because {0}")]
+    InvalidIndex(#[from] mul_index::Error),
+
+    #[error("map image is invalid because {0}")]
+    InvalidImage(#[from] image::ImageError),
+
     #[error("i/o failure {0}")]
     Io(#[from] std::io::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Tile {
     pub kind: u16,
     pub height: u8,
@@ -51,6 +75,7 @@ impl Tile {
     }
 }
 
+#[derive(PartialEq)]
 pub struct Block<const BLOCK_SIZE: u32>
 where
     [(); BLOCK_SIZE as usize]:,
@@ -91,6 +116,15 @@ where
 
         Ok(())
     }
+
+    // Tiles are indexed by y and then x within the block
+    pub fn tiles(&self) -> &[[Tile; BLOCK_SIZE as usize]; BLOCK_SIZE as usize] {
+        &self.tiles
+    }
+
+    pub fn tiles_mut(&mut self) -> &mut [[Tile; BLOCK_SIZE as usize]; BLOCK_SIZE as usize] {
+        &mut self.tiles
+    }
 }
 
 pub struct Map<const BLOCK_SIZE: u32>
@@ -99,7 +133,25 @@ where
 {
     width: u32,
     height: u32,
-    blocks: Vec<Block<BLOCK_SIZE>>, // 2D array, width-major
+    // 2D array, x-major: all of the blocks in the first column from top to
+    // bottom, then the next column, and so on. This is the order blocks are
+    // stored in map files, so it's also the order they're read and written.
+    blocks: Vec<Block<BLOCK_SIZE>>,
+    tile_data: Option<Arc<TileData>>, // For checking tiles, when given
+    dirty: HashSet<usize>,            // Blocks which may have changed since being read
+}
+
+// Whether blocks have changed doesn't matter for comparing maps
+impl<const BLOCK_SIZE: u32> PartialEq for Map<BLOCK_SIZE>
+where
+    [(); BLOCK_SIZE as usize]:,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.blocks == other.blocks
+            && self.tile_data == other.tile_data
+    }
 }
 
 impl<const BLOCK_SIZE: u32> Map<BLOCK_SIZE>
@@ -116,6 +168,8 @@ where
             width,
             height,
             blocks: Vec::with_capacity(blocks_num as usize),
+            tile_data: None,
+            dirty: HashSet::new(),
         };
 
         for id in 0..blocks_num {
@@ -136,6 +190,8 @@ where
             width,
             height,
             blocks: Vec::with_capacity(blocks_num as usize),
+            tile_data: None,
+            dirty: HashSet::new(),
         };
 
         for _ in 0..blocks_num {
@@ -145,7 +201,15 @@ where
         Ok(map)
     }
 
+    // From then on, every tile that is set or written must be a land tile in
+    // the tile data. Tiles which were already set are checked when written.
+    pub fn with_tile_data(mut self, tile_data: Arc<TileData>) -> Self {
+        self.tile_data = Some(tile_data);
+        self
+    }
+
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.validate_tiles()?;
         for block in &self.blocks {
             block.write(writer)?;
         }
@@ -153,8 +217,38 @@ where
         Ok(())
     }
 
+    // A mapN.mul file is the same stream of blocks that gets split up into
+    // files within a .uop package, just without the package around it.
+    pub fn from_mul_reader<R: Read>(reader: &mut R, width: u32, height: u32) -> Result<Self> {
+        Self::from_reader(reader, width, height)
+    }
+
+    pub fn write_mul<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.write(writer)
+    }
+
     pub fn into_files(self, prefix: &str) -> Result<Vec<UOPackageFile>> {
-        let num_files = (self.blocks.len() + Self::BLOCKS_PER_FILE - 1) / Self::BLOCKS_PER_FILE;
+        self.into_files_where(prefix, |_| true)
+    }
+
+    // Only the files containing blocks which were set or borrowed mutably
+    // since the map was created or read, to update a package in place
+    pub fn into_changed_files(self, prefix: &str) -> Result<Vec<UOPackageFile>> {
+        let changed = self
+            .dirty
+            .iter()
+            .map(|index| index / Self::BLOCKS_PER_FILE)
+            .collect::<HashSet<_>>();
+
+        self.into_files_where(prefix, |file_num| changed.contains(&file_num))
+    }
+
+    fn into_files_where(
+        self,
+        prefix: &str,
+        include: impl Fn(usize) -> bool,
+    ) -> Result<Vec<UOPackageFile>> {
+        self.validate_tiles()?;
 
         fn write_blocks_file<const BLOCK_SIZE: u32>(
             blocks: &[Block<BLOCK_SIZE>],
@@ -163,9 +257,7 @@ where
         where
             [(); BLOCK_SIZE as usize]:,
         {
-            let size =
-                Block::<BLOCK_SIZE>::SIZE * blocks.len().min(Map::<BLOCK_SIZE>::BLOCKS_PER_FILE);
-            let mut contents = vec![0u8; size];
+            let mut contents = vec![0u8; Block::<BLOCK_SIZE>::SIZE * blocks.len()];
             let mut buf = contents.as_mut_slice();
 
             for block in blocks {
@@ -180,32 +272,39 @@ where
             })
         }
 
-        // Break the map up into separate files
-        let mut files = Vec::<UOPackageFile>::with_capacity(num_files);
-        let mut file_num = 0u32;
-
-        let mut block_chunks = self.blocks.chunks_exact(Self::BLOCKS_PER_FILE);
-        for chunk in block_chunks.by_ref() {
-            let file_name = Self::file_path(prefix, file_num);
-            files.push(write_blocks_file(chunk, file_name.as_str())?);
-            file_num += 1;
-        }
-
-        let remainder = block_chunks.remainder();
-        if !remainder.is_empty() {
-            let file_name = Self::file_path(prefix, file_num);
-            files.push(write_blocks_file(remainder, file_name.as_str())?);
-        }
+        // Break the map up into separate files, the last of which may have
+        // fewer blocks
+        self.blocks
+            .chunks(Self::BLOCKS_PER_FILE)
+            .enumerate()
+            .filter(|&(file_num, _)| include(file_num))
+            .map(|(file_num, blocks)| {
+                let file_name = Self::file_path(prefix, file_num as u32);
+                write_blocks_file(blocks, file_name.as_str())
+            })
+            .collect()
+    }
 
-        Ok(files)
+    // Each block with its index, in the order they're stored. Every block
+    // that is yielded is treated as changed, since it may be.
+    pub fn blocks_mut(&mut self) -> impl Iterator<Item = (u32, &mut Block<BLOCK_SIZE>)> {
+        let dirty = &mut self.dirty;
+        self.blocks
+            .iter_mut()
+            .enumerate()
+            .map(move |(index, block)| {
+                dirty.insert(index);
+                (index as u32, block)
+            })
     }
 
     pub fn set(&mut self, x: u32, y: u32, tile: Tile) -> Result<()> {
         self.validate_position(x, y)?;
+        self.validate_tile(&tile)?;
 
-        let block_x = x / BLOCK_SIZE;
-        let block_y = y / BLOCK_SIZE;
-        let block = &mut self.blocks[(block_x * (self.height / BLOCK_SIZE) + block_y) as usize];
+        let index = self.block_index(x, y);
+        let block = &mut self.blocks[index];
+        self.dirty.insert(index);
 
         let tile_x = x % BLOCK_SIZE;
         let tile_y = y % BLOCK_SIZE;
@@ -216,37 +315,154 @@ where
     pub fn get(&self, x: u32, y: u32) -> Result<&Tile> {
         self.validate_position(x, y)?;
 
-        let block_x = x / BLOCK_SIZE;
-        let block_y = y / BLOCK_SIZE;
-        let block = &self.blocks[(block_x * (self.height / BLOCK_SIZE) + block_y) as usize];
+        let block = &self.blocks[self.block_index(x, y)];
 
         let tile_x = x % BLOCK_SIZE;
         let tile_y = y % BLOCK_SIZE;
         Ok(&block.tiles[tile_y as usize][tile_x as usize])
     }
 
-    fn validate_dimensions(width: u32, height: u32) -> Result<()> {
-        // Map must be composed of square blocks, does not have to be square
-        if width == 0 || height == 0 || width % BLOCK_SIZE != 0 || height % BLOCK_SIZE != 0 {
-            Err(Error::InvalidSize { width, height })
-        } else {
-            Ok(())
+    // The z a mobile would stand at on the tile at (x, y), or None if there's
+    // nowhere to stand. That's the highest of the land and any surfaces among
+    // the statics on the tile, which isn't blocked by an impassable static.
+    pub fn altitude_at(
+        &self,
+        x: u32,
+        y: u32,
+        statics: &Statics<BLOCK_SIZE>,
+        tile_data: &TileData,
+    ) -> Result<Option<i8>> {
+        // Height of a mobile, which must be clear above where it stands
+        const PERSON_HEIGHT: i32 = 16;
+
+        let land = self.get(x, y)?;
+        let land_passable = tile_data
+            .land(land.kind)
+            .is_none_or(|data| !data.flags.contains(TileFlags::IMPASSABLE));
+
+        let mut surfaces = vec![];
+        let mut blockers = vec![];
+        if land_passable {
+            surfaces.push(self.land_altitude(x, y)? as i32);
+        }
+
+        for item in statics.get_statics(x, y)? {
+            let data = match tile_data.item(item.graphic) {
+                Some(data) => data,
+                None => continue,
+            };
+
+            let (z, height) = (item.z as i32, data.height as i32);
+            if data.flags.contains(TileFlags::SURFACE) {
+                // Stairs and ramps are walked on halfway up
+                let height = match data.flags.contains(TileFlags::BRIDGE) {
+                    true => height / 2,
+                    false => height,
+                };
+                surfaces.push(z + height);
+            } else if data.flags.contains(TileFlags::IMPASSABLE) {
+                blockers.push(z..(z + height).max(z + 1));
+            }
         }
+
+        Ok(surfaces
+            .into_iter()
+            .filter(|&z| {
+                !blockers
+                    .iter()
+                    .any(|blocker| blocker.start < z + PERSON_HEIGHT && z < blocker.end)
+            })
+            .max()
+            .map(|z| z.clamp(i8::MIN as i32, i8::MAX as i32) as i8))
     }
 
-    fn validate_position(&self, x: u32, y: u32) -> Result<()> {
-        if x >= self.width || y >= self.height {
-            Err(Error::InvalidPos { x, y })
+    // Land tiles are drawn stretched between the z of their own corner and
+    // those of their neighbors to the east, south and southeast, so the z
+    // in the middle of the tile is taken along the flatter diagonal.
+    fn land_altitude(&self, x: u32, y: u32) -> Result<i8> {
+        // Off the edge of the map, treat the land as continuing flat
+        let z = |x: u32, y: u32| -> Result<i32> {
+            let x = x.min(self.width - 1);
+            let y = y.min(self.height - 1);
+            Ok(self.get(x, y)?.height as i8 as i32)
+        };
+
+        let top = z(x, y)?;
+        let left = z(x, y + 1)?;
+        let right = z(x + 1, y)?;
+        let bottom = z(x + 1, y + 1)?;
+
+        let avg = if (top - bottom).abs() > (left - right).abs() {
+            (left + right).div_euclid(2)
         } else {
-            Ok(())
+            (top + bottom).div_euclid(2)
+        };
+
+        Ok(avg as i8)
+    }
+
+    fn block_index(&self, x: u32, y: u32) -> usize {
+        block_index::<BLOCK_SIZE>(self.height, x, y)
+    }
+
+    fn validate_dimensions(width: u32, height: u32) -> Result<()> {
+        validate_dimensions::<BLOCK_SIZE>(width, height)
+    }
+
+    fn validate_position(&self, x: u32, y: u32) -> Result<()> {
+        validate_position(self.width, self.height, x, y)
+    }
+
+    fn validate_tile(&self, tile: &Tile) -> Result<()> {
+        match &self.tile_data {
+            Some(tile_data) if tile_data.land(tile.kind).is_none() => {
+                Err(Error::InvalidTile(tile.kind))
+            }
+            _ => Ok(()),
         }
     }
 
+    fn validate_tiles(&self) -> Result<()> {
+        if self.tile_data.is_none() {
+            return Ok(());
+        }
+
+        self.blocks
+            .iter()
+            .flat_map(|block| block.tiles.iter().flatten())
+            .try_for_each(|tile| self.validate_tile(tile))
+    }
+
     fn file_path(prefix: &str, file_num: u32) -> String {
         format!("{}/{:08}.dat", prefix, file_num)
     }
 }
 
+// Index of the block containing the tile at (x, y), see `Map::blocks`
+fn block_index<const BLOCK_SIZE: u32>(height: u32, x: u32, y: u32) -> usize {
+    let block_x = x / BLOCK_SIZE;
+    let block_y = y / BLOCK_SIZE;
+
+    (block_x * (height / BLOCK_SIZE) + block_y) as usize
+}
+
+fn validate_dimensions<const BLOCK_SIZE: u32>(width: u32, height: u32) -> Result<()> {
+    // Map must be composed of square blocks, does not have to be square
+    if width == 0 || height == 0 || width % BLOCK_SIZE != 0 || height % BLOCK_SIZE != 0 {
+        Err(Error::InvalidSize { width, height })
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_position(width: u32, height: u32, x: u32, y: u32) -> Result<()> {
+    if x >= width || y >= height {
+        Err(Error::InvalidPos { x, y })
+    } else {
+        Ok(())
+    }
+}
+
 pub struct Metadata {
     pub width: u32,
     pub height: u32,
@@ -269,56 +485,104 @@ where
     }
 }
 
+// Reads the map files on demand, rather than needing the whole package in memory
+impl<'a, const BLOCK_SIZE: u32, R> TryFrom<(Metadata, &'a mut UOPackageReader<R>)>
+    for Map<BLOCK_SIZE>
+where
+    [(); BLOCK_SIZE as usize]:,
+    R: Read + Seek,
+{
+    type Error = Error;
+
+    fn try_from((metadata, package): (Metadata, &'a mut UOPackageReader<R>)) -> Result<Self> {
+        let mut reader: PackageReader<'_, R> = (metadata.prefix.as_str(), package).try_into()?;
+        Self::from_reader(&mut reader, metadata.width, metadata.height)
+    }
+}
+
 // UO maps use 8x8 blocks
 pub type UOMap = Map<8>;
 
-pub struct PackageReader<'a> {
-    package: &'a UOPackage,
+enum PackageSource<'a, R> {
+    Eager(&'a UOPackage),
+    Lazy(&'a mut UOPackageReader<R>),
+}
+
+impl<'a, R: Read + Seek> PackageSource<'a, R> {
+    fn get_contents(&mut self, path: &str) -> Result<Option<Cow<'a, [u8]>>> {
+        match *self {
+            Self::Eager(package) => Ok(package
+                .get_file(path)?
+                .map(|file| Cow::Borrowed(file.contents.as_slice()))),
+            Self::Lazy(ref mut package) => Ok(package
+                .get_file(path)?
+                .map(|file| Cow::Owned(file.contents))),
+        }
+    }
+}
+
+// Reads the map files of a package as one continuous stream. The reader type
+// is only used when reading the files lazily from a UOPackageReader.
+pub struct PackageReader<'a, R = File> {
+    source: PackageSource<'a, R>,
     prefix: String,
     file_num: u32,
-    inner: Cursor<&'a [u8]>,
+    inner: Cursor<Cow<'a, [u8]>>,
 }
 
-impl<'a> PackageReader<'a> {
+impl<'a, R: Read + Seek> PackageReader<'a, R> {
     fn file_path(prefix: &str, file_num: u32) -> String {
         Map::<0>::file_path(prefix, file_num)
     }
 
-    fn get_next_file(&self) -> Result<Option<&'a UOPackageFile>> {
-        Ok(self
-            .package
-            .get_file(Self::file_path(self.prefix.as_str(), self.file_num + 1).as_str())?)
-    }
-}
-
-impl<'a, 'b> TryFrom<(&'b str, &'a UOPackage)> for PackageReader<'a> {
-    type Error = Error;
-
-    fn try_from((prefix, package): (&'b str, &'a UOPackage)) -> Result<Self> {
+    fn new(prefix: &str, mut source: PackageSource<'a, R>) -> Result<Self> {
         let file_num = 0;
 
-        match package.get_file(Self::file_path(prefix, file_num).as_str())? {
-            Some(file) => Ok(Self {
-                package,
+        match source.get_contents(Self::file_path(prefix, file_num).as_str())? {
+            Some(contents) => Ok(Self {
+                source,
                 prefix: prefix.to_string(),
                 file_num,
-                inner: Cursor::new(&file.contents),
+                inner: Cursor::new(contents),
             }),
             None => Err(Error::NoFiles),
         }
     }
+
+    fn get_next_file(&mut self) -> Result<Option<Cow<'a, [u8]>>> {
+        let path = Self::file_path(self.prefix.as_str(), self.file_num + 1);
+        self.source.get_contents(path.as_str())
+    }
+}
+
+impl<'a, 'b, R: Read + Seek> TryFrom<(&'b str, &'a UOPackage)> for PackageReader<'a, R> {
+    type Error = Error;
+
+    fn try_from((prefix, package): (&'b str, &'a UOPackage)) -> Result<Self> {
+        Self::new(prefix, PackageSource::Eager(package))
+    }
 }
 
-impl<'a> Read for PackageReader<'a> {
+impl<'a, 'b, R: Read + Seek> TryFrom<(&'b str, &'a mut UOPackageReader<R>)>
+    for PackageReader<'a, R>
+{
+    type Error = Error;
+
+    fn try_from((prefix, package): (&'b str, &'a mut UOPackageReader<R>)) -> Result<Self> {
+        Self::new(prefix, PackageSource::Lazy(package))
+    }
+}
+
+impl<'a, R: Read + Seek> Read for PackageReader<'a, R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let mut amount = self.inner.read(buf)?;
 
         if amount == 0 {
-            if let Some(file) = self
+            if let Some(contents) = self
                 .get_next_file()
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
             {
-                self.inner = Cursor::new(&file.contents);
+                self.inner = Cursor::new(contents);
                 self.file_num += 1;
 
                 amount = self.inner.read(buf)?;
@@ -328,3 +592,318 @@ impl<'a> Read for PackageReader<'a> {
         Ok(amount)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiledata::{ItemData, LandData};
+
+    const TILE: Tile = Tile {
+        kind: 0x00A8, // Water
+        height: 0xFB,
+    };
+
+    fn tall_map() -> UOMap {
+        let mut map = UOMap::new(256, 512).unwrap();
+        map.set(200, 400, TILE).unwrap();
+        map
+    }
+
+    const GRASS: u16 = 0x0003;
+    const WATER: u16 = 0x00A8;
+    const TABLE: u16 = 0x0B34;
+    const TREE: u16 = 0x0CCA;
+    const STAIRS: u16 = 0x0736;
+
+    fn tile_data() -> TileData {
+        let mut land = vec![Default::default(); 0x4000];
+        land[WATER as usize] = LandData {
+            flags: TileFlags::WET | TileFlags::IMPASSABLE,
+            ..Default::default()
+        };
+
+        let mut items = vec![ItemData::default(); 0x1000];
+        items[TABLE as usize] = ItemData {
+            flags: TileFlags::SURFACE,
+            height: 6,
+            ..Default::default()
+        };
+        items[TREE as usize] = ItemData {
+            flags: TileFlags::IMPASSABLE,
+            height: 20,
+            ..Default::default()
+        };
+        items[STAIRS as usize] = ItemData {
+            flags: TileFlags::SURFACE | TileFlags::BRIDGE,
+            height: 5,
+            ..Default::default()
+        };
+
+        TileData::new(land, items)
+    }
+
+    fn flat_map() -> UOMap {
+        let mut map = UOMap::new(64, 64).unwrap();
+        for x in 0..64 {
+            for y in 0..64 {
+                map.set(
+                    x,
+                    y,
+                    Tile {
+                        kind: GRASS,
+                        height: 5,
+                    },
+                )
+                .unwrap();
+            }
+        }
+        map
+    }
+
+    fn set_height(map: &mut UOMap, x: u32, y: u32, height: i8) {
+        map.set(
+            x,
+            y,
+            Tile {
+                kind: GRASS,
+                height: height as u8,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn altitude_flat() {
+        let map = flat_map();
+        let statics = UOStatics::new(64, 64).unwrap();
+        let tile_data = tile_data();
+
+        assert_eq!(
+            map.altitude_at(10, 10, &statics, &tile_data).unwrap(),
+            Some(5)
+        );
+        // Edges of the map
+        assert_eq!(
+            map.altitude_at(63, 63, &statics, &tile_data).unwrap(),
+            Some(5)
+        );
+        assert_eq!(
+            map.altitude_at(0, 63, &statics, &tile_data).unwrap(),
+            Some(5)
+        );
+        assert!(map.altitude_at(64, 10, &statics, &tile_data).is_err());
+    }
+
+    #[test]
+    fn altitude_sloped() {
+        let mut map = flat_map();
+        let statics = UOStatics::new(64, 64).unwrap();
+        let tile_data = tile_data();
+
+        // Rising to the southeast, where the east & south corners are level
+        set_height(&mut map, 10, 10, 0);
+        set_height(&mut map, 11, 10, 10);
+        set_height(&mut map, 10, 11, 10);
+        set_height(&mut map, 11, 11, 20);
+        assert_eq!(
+            map.altitude_at(10, 10, &statics, &tile_data).unwrap(),
+            Some(10)
+        );
+
+        // Rising to the east, with a negative corner
+        set_height(&mut map, 20, 20, -3);
+        set_height(&mut map, 20, 21, -3);
+        set_height(&mut map, 21, 20, 0);
+        set_height(&mut map, 21, 21, 0);
+        assert_eq!(
+            map.altitude_at(20, 20, &statics, &tile_data).unwrap(),
+            Some(-2)
+        );
+
+        // Sloping off the edge of the map
+        set_height(&mut map, 63, 63, 9);
+        assert_eq!(
+            map.altitude_at(63, 63, &statics, &tile_data).unwrap(),
+            Some(9)
+        );
+        assert_eq!(
+            map.altitude_at(62, 62, &statics, &tile_data).unwrap(),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn altitude_water() {
+        let mut map = flat_map();
+        map.set(
+            30,
+            30,
+            Tile {
+                kind: WATER,
+                height: (-5i8) as u8,
+            },
+        )
+        .unwrap();
+
+        let mut statics = UOStatics::new(64, 64).unwrap();
+        let tile_data = tile_data();
+        assert_eq!(map.altitude_at(30, 30, &statics, &tile_data).unwrap(), None);
+
+        // A dock over the water can be stood on
+        let dock = StaticItem {
+            graphic: TABLE,
+            z: -2,
+            ..Default::default()
+        };
+        statics.add(30, 30, dock).unwrap();
+        assert_eq!(
+            map.altitude_at(30, 30, &statics, &tile_data).unwrap(),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn altitude_statics() {
+        let map = flat_map();
+        let mut statics = UOStatics::new(64, 64).unwrap();
+        let tile_data = tile_data();
+
+        let item = |graphic: u16, z: i8| StaticItem {
+            graphic,
+            z,
+            ..Default::default()
+        };
+        statics.add(12, 12, item(TABLE, 5)).unwrap();
+        statics.add(13, 12, item(TREE, 5)).unwrap();
+        statics.add(14, 12, item(STAIRS, 5)).unwrap();
+        statics.add(15, 12, item(TREE, 40)).unwrap();
+
+        assert_eq!(
+            map.altitude_at(12, 12, &statics, &tile_data).unwrap(),
+            Some(11)
+        );
+        assert_eq!(map.altitude_at(13, 12, &statics, &tile_data).unwrap(), None);
+        assert_eq!(
+            map.altitude_at(14, 12, &statics, &tile_data).unwrap(),
+            Some(7)
+        );
+        // Far enough overhead not to be in the way
+        assert_eq!(
+            map.altitude_at(15, 12, &statics, &tile_data).unwrap(),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn validates_tiles() {
+        // Past the end of the land tiles
+        let unknown = Tile {
+            kind: 0x4000,
+            height: 0,
+        };
+
+        let mut map = flat_map();
+        map.set(10, 10, unknown).unwrap();
+        map.write(&mut vec![]).unwrap();
+
+        // Tiles which were set before are checked when the map is written
+        let mut map = map.with_tile_data(Arc::new(tile_data()));
+        assert!(matches!(
+            map.write(&mut vec![]),
+            Err(Error::InvalidTile(0x4000))
+        ));
+
+        map.set(10, 10, TILE).unwrap();
+        assert!(matches!(
+            map.set(20, 20, unknown),
+            Err(Error::InvalidTile(0x4000))
+        ));
+        assert_eq!(map.get(20, 20).unwrap().kind, GRASS);
+        map.write(&mut vec![]).unwrap();
+    }
+
+    #[test]
+    fn non_square_round_trip() {
+        let mut contents = vec![];
+        tall_map().write(&mut contents).unwrap();
+
+        // The tile is the first in block (25, 50), which is stored after
+        // the 25 columns of 64 blocks to its left and the 50 above it
+        let offset = (25 * 64 + 50) * Block::<8>::SIZE + size_of::<u32>();
+        assert_eq!(contents[offset..offset + Tile::SIZE], [0xA8, 0x00, 0xFB]);
+
+        let map = UOMap::from_reader(&mut contents.as_slice(), 256, 512).unwrap();
+
+        assert_eq!(map.get(200, 400).unwrap(), &TILE);
+        assert!(matches!(
+            map.get(400, 200),
+            Err(Error::InvalidPos { x: 400, y: 200 })
+        ));
+        assert_eq!(map.get(200, 399).unwrap(), &Tile::default());
+        assert_eq!(map.get(199, 400).unwrap(), &Tile::default());
+    }
+
+    #[test]
+    fn mul_round_trip() {
+        let map = tall_map();
+
+        let mut contents = vec![];
+        map.write_mul(&mut contents).unwrap();
+        assert_eq!(contents.len(), 32 * 64 * Block::<8>::SIZE);
+
+        let read = UOMap::from_mul_reader(&mut contents.as_slice(), 256, 512).unwrap();
+        assert!(read == map);
+    }
+
+    #[test]
+    fn changed_files() {
+        // Two files of blocks
+        let prefix = "build/map0legacymul";
+        let map = UOMap::new(1024, 512).unwrap();
+        assert!(map.into_changed_files(prefix).unwrap().is_empty());
+
+        // Block (112, 1) comes after the first 0x1000 blocks
+        let mut map = UOMap::new(1024, 512).unwrap();
+        map.set(900, 10, TILE).unwrap();
+        let files = map.into_changed_files(prefix).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].hash,
+            uo_package::uop_hash("build/map0legacymul/00000001.dat").unwrap()
+        );
+        assert_eq!(files[0].contents.len(), 0x1000 * Block::<8>::SIZE);
+
+        let mut map = UOMap::new(1024, 512).unwrap();
+        let (index, block) = map.blocks_mut().next().unwrap();
+        block.tiles_mut()[1][2] = TILE;
+        assert_eq!(index, 0);
+        assert_eq!(map.get(2, 1).unwrap(), &TILE);
+
+        let files = map.into_changed_files(prefix).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].hash,
+            uo_package::uop_hash("build/map0legacymul/00000000.dat").unwrap()
+        );
+    }
+
+    #[test]
+    fn non_square_package_round_trip() {
+        let prefix = "build/map0legacymul";
+        let package: UOPackage = tall_map().into_files(prefix).unwrap().try_into().unwrap();
+
+        let map: UOMap = (
+            Metadata {
+                width: 256,
+                height: 512,
+                prefix: prefix.to_string(),
+            },
+            package,
+        )
+            .try_into()
+            .unwrap();
+
+        assert_eq!(map.get(200, 400).unwrap(), &TILE);
+        assert_eq!(map.get(200, 399).unwrap(), &Tile::default());
+    }
+}
@@ -0,0 +1,72 @@
+use image::{Rgb, RgbImage};
+use std::path::Path;
+
+use super::{Map, Result, Tile};
+
+// Each pixel of a heightmap image describes one tile: the red and green
+// channels hold the high and low bytes of the tile kind, and the blue
+// channel holds the height.
+impl<const BLOCK_SIZE: u32> Map<BLOCK_SIZE>
+where
+    [(); BLOCK_SIZE as usize]:,
+{
+    pub fn from_image(image: &RgbImage) -> Result<Self> {
+        let mut map = Self::new(image.width(), image.height())?;
+
+        for (x, y, pixel) in image.enumerate_pixels() {
+            map.set(x, y, pixel_tile(pixel))?;
+        }
+
+        Ok(map)
+    }
+
+    pub fn open_image<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_image(&image::open(path)?.to_rgb8())
+    }
+}
+
+fn pixel_tile(&Rgb([high, low, height]): &Rgb<u8>) -> Tile {
+    Tile {
+        kind: u16::from_be_bytes([high, low]),
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Error, UOMap};
+
+    #[test]
+    fn from_image() {
+        let image = RgbImage::from_fn(16, 16, |x, y| {
+            let kind = if x < 8 { 0x0003 } else { 0x00A8 }; // Grass and water
+            Rgb([(kind >> 8) as u8, kind as u8, (x + y) as u8])
+        });
+
+        let map = UOMap::from_image(&image).unwrap();
+        for x in 0..16 {
+            for y in 0..16 {
+                let tile = map.get(x, y).unwrap();
+                assert_eq!(*tile, pixel_tile(image.get_pixel(x, y)));
+            }
+        }
+
+        assert_eq!(map.get(7, 2).unwrap().kind, 0x0003);
+        assert_eq!(map.get(8, 2).unwrap().kind, 0x00A8);
+        assert_eq!(map.get(15, 15).unwrap().height, 30);
+    }
+
+    #[test]
+    fn rejects_partial_blocks() {
+        let image = RgbImage::new(16, 12);
+
+        assert!(matches!(
+            UOMap::from_image(&image),
+            Err(Error::InvalidSize {
+                width: 16,
+                height: 12
+            })
+        ));
+    }
+}
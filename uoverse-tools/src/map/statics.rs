@@ -0,0 +1,224 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{Read, Seek, Write},
+    mem::size_of,
+};
+
+use super::{block_index, validate_dimensions, validate_position, Error, Result};
+use crate::archive::mul_index::{IndexEntry, MulIndex};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StaticItem {
+    pub graphic: u16,
+    pub x: u8, // Relative to the block
+    pub y: u8, // Relative to the block
+    pub z: i8,
+    pub hue: u16,
+}
+
+impl StaticItem {
+    const SIZE: usize =
+        size_of::<u16>() + (size_of::<u8>() * 2) + size_of::<i8>() + size_of::<u16>();
+
+    fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            graphic: reader.read_u16::<LittleEndian>()?,
+            x: reader.read_u8()?,
+            y: reader.read_u8()?,
+            z: reader.read_i8()?,
+            hue: reader.read_u16::<LittleEndian>()?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u16::<LittleEndian>(self.graphic)?;
+        writer.write_u8(self.x)?;
+        writer.write_u8(self.y)?;
+        writer.write_i8(self.z)?;
+        writer.write_u16::<LittleEndian>(self.hue)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct StaticsBlock {
+    items: Vec<StaticItem>,
+}
+
+pub struct Statics<const BLOCK_SIZE: u32> {
+    width: u32,
+    height: u32,
+    blocks: Vec<StaticsBlock>, // 2D array, x-major like `Map::blocks`
+}
+
+impl<const BLOCK_SIZE: u32> Statics<BLOCK_SIZE> {
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        validate_dimensions::<BLOCK_SIZE>(width, height)?;
+
+        let blocks_num = (width * height) / (BLOCK_SIZE * BLOCK_SIZE);
+        Ok(Self {
+            width,
+            height,
+            blocks: vec![Default::default(); blocks_num as usize],
+        })
+    }
+
+    pub fn from_readers<I, R>(
+        index: &mut I,
+        reader: &mut R,
+        width: u32,
+        height: u32,
+    ) -> Result<Self>
+    where
+        I: Read,
+        R: Read + Seek,
+    {
+        validate_dimensions::<BLOCK_SIZE>(width, height)?;
+
+        let blocks_num = (width * height) / (BLOCK_SIZE * BLOCK_SIZE);
+        let mut statics = Self {
+            width,
+            height,
+            blocks: Vec::with_capacity(blocks_num as usize),
+        };
+
+        let mut index = MulIndex::new(index, reader)?;
+        if index.len() < blocks_num as usize {
+            return Err(Error::InvalidStaticsIndex(index.len()));
+        }
+
+        for block_num in 0..(blocks_num as usize) {
+            let contents = match index.read_entry(block_num)? {
+                Some(contents) => contents,
+                None => {
+                    statics.blocks.push(Default::default());
+                    continue;
+                }
+            };
+
+            if !contents.len().is_multiple_of(StaticItem::SIZE) {
+                return Err(Error::InvalidStaticsIndex(block_num));
+            }
+
+            let mut block = StaticsBlock {
+                items: Vec::with_capacity(contents.len() / StaticItem::SIZE),
+            };
+            let mut contents = contents.as_slice();
+            while !contents.is_empty() {
+                block.items.push(StaticItem::from_reader(&mut contents)?);
+            }
+            statics.blocks.push(block);
+        }
+
+        Ok(statics)
+    }
+
+    pub fn write<I: Write, W: Write>(&self, index: &mut I, writer: &mut W) -> Result<()> {
+        let mut lookup = 0u32;
+
+        for block in &self.blocks {
+            let entry = match block.items.len() {
+                0 => IndexEntry::empty(),
+                num_items => IndexEntry {
+                    lookup,
+                    length: (num_items * StaticItem::SIZE) as u32,
+                    extra: 0,
+                },
+            };
+
+            for item in &block.items {
+                item.write(writer)?;
+            }
+
+            if !entry.is_empty() {
+                lookup += entry.length;
+            }
+            entry.write(index)?;
+        }
+
+        Ok(())
+    }
+
+    // Places the item on the tile at (x, y), replacing its block-relative position
+    pub fn add(&mut self, x: u32, y: u32, item: StaticItem) -> Result<()> {
+        validate_position(self.width, self.height, x, y)?;
+
+        let block = &mut self.blocks[block_index::<BLOCK_SIZE>(self.height, x, y)];
+        block.items.push(StaticItem {
+            x: (x % BLOCK_SIZE) as u8,
+            y: (y % BLOCK_SIZE) as u8,
+            ..item
+        });
+
+        Ok(())
+    }
+
+    pub fn get_statics(&self, x: u32, y: u32) -> Result<Vec<&StaticItem>> {
+        validate_position(self.width, self.height, x, y)?;
+
+        let block = &self.blocks[block_index::<BLOCK_SIZE>(self.height, x, y)];
+        let (tile_x, tile_y) = ((x % BLOCK_SIZE) as u8, (y % BLOCK_SIZE) as u8);
+        Ok(block
+            .items
+            .iter()
+            .filter(|item| item.x == tile_x && item.y == tile_y)
+            .collect())
+    }
+}
+
+// UO maps use 8x8 blocks
+pub type UOStatics = Statics<8>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trip() {
+        let tree = StaticItem {
+            graphic: 0x0CCA, // Tree trunk
+            z: 0,
+            ..Default::default()
+        };
+        let leaves = StaticItem {
+            graphic: 0x0CCB, // Tree leaves
+            z: 0,
+            hue: 0x0481,
+            ..Default::default()
+        };
+        let sign = StaticItem {
+            graphic: 0x0BD2, // Sign post
+            z: 5,
+            ..Default::default()
+        };
+
+        let mut statics = UOStatics::new(256, 512).unwrap();
+        statics.add(203, 405, tree).unwrap();
+        statics.add(203, 405, leaves).unwrap();
+        statics.add(204, 402, sign).unwrap();
+
+        let (mut index, mut contents) = (vec![], vec![]);
+        statics.write(&mut index, &mut contents).unwrap();
+
+        assert_eq!(index.len(), 32 * 64 * 12);
+        assert_eq!(contents.len(), 3 * StaticItem::SIZE);
+
+        let statics =
+            UOStatics::from_readers(&mut index.as_slice(), &mut Cursor::new(contents), 256, 512)
+                .unwrap();
+
+        let items = statics.get_statics(203, 405).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].graphic, items[0].x, items[0].y), (0x0CCA, 3, 5));
+        assert_eq!((items[1].graphic, items[1].hue), (0x0CCB, 0x0481));
+
+        let items = statics.get_statics(204, 402).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!((items[0].graphic, items[0].z), (0x0BD2, 5));
+
+        assert!(statics.get_statics(202, 405).unwrap().is_empty());
+        assert!(statics.get_statics(211, 405).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::{
+    fmt,
+    io::{Read, Seek},
+    mem::size_of,
+};
+
+use crate::{
+    archive::mul_index::{self, MulIndex},
+    tiledata::Format,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("multi {0} does not contain a whole number of components")]
+    InvalidMulti(usize),
+
+    #[error("multi index is invalid because {0}")]
+    InvalidIndex(#[from] mul_index::Error),
+
+    #[error("i/o failure {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// One component of a multi, positioned relative to the multi's center
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MultiItem {
+    pub graphic: u16,
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+    pub flags: u32, // Zero for components which aren't shown
+}
+
+impl MultiItem {
+    const SIZE: usize = size_of::<u16>() + (size_of::<i16>() * 3) + size_of::<u32>();
+
+    // High Seas added another 32 bits after the flags, which are unused
+    fn size(format: Format) -> usize {
+        match format {
+            Format::Classic => Self::SIZE,
+            Format::HighSeas => Self::SIZE + size_of::<u32>(),
+        }
+    }
+
+    fn from_reader<R: Read>(reader: &mut R, format: Format) -> Result<Self> {
+        let item = Self {
+            graphic: reader.read_u16::<LittleEndian>()?,
+            x: reader.read_i16::<LittleEndian>()?,
+            y: reader.read_i16::<LittleEndian>()?,
+            z: reader.read_i16::<LittleEndian>()?,
+            flags: reader.read_u32::<LittleEndian>()?,
+        };
+
+        if format == Format::HighSeas {
+            reader.read_u32::<LittleEndian>()?;
+        }
+
+        Ok(item)
+    }
+}
+
+// A house, boat or other structure built out of many items
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Multi {
+    pub items: Vec<MultiItem>,
+}
+
+pub struct Multis {
+    multis: Vec<Option<Multi>>,
+}
+
+impl Multis {
+    pub fn from_readers<I, R>(index: &mut I, reader: R, format: Format) -> Result<Self>
+    where
+        I: Read,
+        R: Read + Seek,
+    {
+        let mut index = MulIndex::new(index, reader)?;
+        let item_size = MultiItem::size(format);
+
+        let mut multis = Vec::with_capacity(index.len());
+        for id in 0..index.len() {
+            let contents = match index.read_entry(id)? {
+                Some(contents) => contents,
+                None => {
+                    multis.push(None);
+                    continue;
+                }
+            };
+
+            if !contents.len().is_multiple_of(item_size) {
+                return Err(Error::InvalidMulti(id));
+            }
+
+            let mut items = Vec::with_capacity(contents.len() / item_size);
+            let mut contents = contents.as_slice();
+            while !contents.is_empty() {
+                items.push(MultiItem::from_reader(&mut contents, format)?);
+            }
+            multis.push(Some(Multi { items }));
+        }
+
+        Ok(Self { multis })
+    }
+
+    pub fn len(&self) -> usize {
+        self.multis.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.multis.is_empty()
+    }
+
+    pub fn multi(&self, id: usize) -> Option<&Multi> {
+        self.multis.get(id).and_then(Option::as_ref)
+    }
+}
+
+impl fmt::Debug for Multis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("Multis({})", self.multis.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::mul_index::IndexEntry;
+    use byteorder::WriteBytesExt;
+    use std::io::Cursor;
+
+    fn components() -> [MultiItem; 2] {
+        [
+            MultiItem {
+                graphic: 0x0064, // Floor
+                x: -1,
+                y: 0,
+                z: 0,
+                flags: 1,
+            },
+            MultiItem {
+                graphic: 0x0066, // Wall
+                x: 1,
+                y: -2,
+                z: 7,
+                flags: 1,
+            },
+        ]
+    }
+
+    // The multi with id 1 has the components, the one before it is empty
+    fn multi_bytes(format: Format) -> (Vec<u8>, Vec<u8>) {
+        let mut data = vec![];
+        for item in components().iter() {
+            data.write_u16::<LittleEndian>(item.graphic).unwrap();
+            data.write_i16::<LittleEndian>(item.x).unwrap();
+            data.write_i16::<LittleEndian>(item.y).unwrap();
+            data.write_i16::<LittleEndian>(item.z).unwrap();
+            data.write_u32::<LittleEndian>(item.flags).unwrap();
+            if format == Format::HighSeas {
+                data.write_u32::<LittleEndian>(0).unwrap();
+            }
+        }
+
+        let mut index = vec![];
+        IndexEntry::empty().write(&mut index).unwrap();
+        IndexEntry {
+            lookup: 0,
+            length: data.len() as u32,
+            extra: 0,
+        }
+        .write(&mut index)
+        .unwrap();
+
+        (index, data)
+    }
+
+    #[test]
+    fn parses_multis() {
+        for format in [Format::Classic, Format::HighSeas] {
+            let (index, data) = multi_bytes(format);
+            let multis =
+                Multis::from_readers(&mut index.as_slice(), Cursor::new(data), format).unwrap();
+
+            assert_eq!(multis.len(), 2);
+            assert_eq!(multis.multi(0), None);
+            assert_eq!(multis.multi(1).unwrap().items, components());
+            assert_eq!(multis.multi(2), None);
+        }
+    }
+
+    #[test]
+    fn rejects_partial_component() {
+        let (index, data) = multi_bytes(Format::HighSeas);
+
+        // Two High Seas components aren't a whole number of classic ones
+        assert!(matches!(
+            Multis::from_readers(&mut index.as_slice(), Cursor::new(data), Format::Classic),
+            Err(Error::InvalidMulti(1))
+        ));
+    }
+}
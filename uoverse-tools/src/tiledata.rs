@@ -0,0 +1,314 @@
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::{fmt, io::Read};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("tile data is truncated within item group {0}")]
+    Truncated(usize),
+
+    #[error("i/o failure {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Clients from High Seas onwards use 64-bit flags, earlier ones use 32-bit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Classic,
+    HighSeas,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct TileFlags(pub u64);
+
+impl TileFlags {
+    pub const BACKGROUND: Self = Self(0x00000001);
+    pub const WEAPON: Self = Self(0x00000002);
+    pub const TRANSPARENT: Self = Self(0x00000004);
+    pub const TRANSLUCENT: Self = Self(0x00000008);
+    pub const WALL: Self = Self(0x00000010);
+    pub const DAMAGING: Self = Self(0x00000020);
+    pub const IMPASSABLE: Self = Self(0x00000040);
+    pub const WET: Self = Self(0x00000080); // Water
+    pub const SURFACE: Self = Self(0x00000200);
+    pub const BRIDGE: Self = Self(0x00000400);
+    pub const STACKABLE: Self = Self(0x00000800);
+    pub const WINDOW: Self = Self(0x00001000);
+    pub const NO_SHOOT: Self = Self(0x00002000);
+    pub const FOLIAGE: Self = Self(0x00020000);
+    pub const CONTAINER: Self = Self(0x00200000);
+    pub const WEARABLE: Self = Self(0x00400000);
+    pub const LIGHT_SOURCE: Self = Self(0x00800000);
+    pub const ANIMATION: Self = Self(0x01000000);
+    pub const ROOF: Self = Self(0x10000000);
+    pub const DOOR: Self = Self(0x20000000);
+    pub const STAIR_BACK: Self = Self(0x40000000);
+    pub const STAIR_RIGHT: Self = Self(0x80000000);
+
+    pub fn contains(&self, flags: Self) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+}
+
+impl std::ops::BitOr for TileFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Debug for TileFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("TileFlags({:#018X})", self.0))
+    }
+}
+
+fn read_flags<R: Read>(reader: &mut R, format: Format) -> Result<TileFlags> {
+    Ok(TileFlags(match format {
+        Format::Classic => reader.read_u32::<LittleEndian>()? as u64,
+        Format::HighSeas => reader.read_u64::<LittleEndian>()?,
+    }))
+}
+
+// Names are ASCII, padded out with nulls
+pub(crate) fn read_name<R: Read>(reader: &mut R) -> std::io::Result<String> {
+    let mut name = [0u8; 20];
+    reader.read_exact(&mut name)?;
+
+    let len = name.iter().position(|&by| by == 0).unwrap_or(name.len());
+    Ok(String::from_utf8_lossy(&name[..len]).into_owned())
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LandData {
+    pub flags: TileFlags,
+    pub texture: u16,
+    pub name: String,
+}
+
+impl LandData {
+    fn from_reader<R: Read>(reader: &mut R, format: Format) -> Result<Self> {
+        Ok(Self {
+            flags: read_flags(reader, format)?,
+            texture: reader.read_u16::<LittleEndian>()?,
+            name: read_name(reader)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ItemData {
+    pub flags: TileFlags,
+    pub weight: u8,
+    pub quality: u8, // Layer for wearables, light id for light sources
+    pub height: u8,
+    pub name: String,
+}
+
+impl ItemData {
+    fn from_reader<R: Read>(reader: &mut R, format: Format) -> Result<Self> {
+        let flags = read_flags(reader, format)?;
+        let weight = reader.read_u8()?;
+        let quality = reader.read_u8()?;
+
+        // Skip misc data, quantity, animation, hue, stacking offset and value
+        let mut unused = [0u8; 10];
+        reader.read_exact(&mut unused)?;
+
+        Ok(Self {
+            flags,
+            weight,
+            quality,
+            height: reader.read_u8()?,
+            name: read_name(reader)?,
+        })
+    }
+}
+
+// Entries are stored in groups of 32, each group preceded by a header
+const GROUP_SIZE: usize = 32;
+
+#[derive(PartialEq)]
+pub struct TileData {
+    land: Vec<LandData>,
+    items: Vec<ItemData>,
+}
+
+impl TileData {
+    const LAND_COUNT: usize = 0x4000;
+
+    pub fn new(land: Vec<LandData>, items: Vec<ItemData>) -> Self {
+        Self { land, items }
+    }
+
+    pub fn from_reader<R: Read>(reader: &mut R, format: Format) -> Result<Self> {
+        let mut tile_data = Self {
+            land: Vec::with_capacity(Self::LAND_COUNT),
+            items: vec![],
+        };
+
+        for _ in 0..(Self::LAND_COUNT / GROUP_SIZE) {
+            reader.read_u32::<LittleEndian>()?; // Group header, unused
+
+            for _ in 0..GROUP_SIZE {
+                tile_data.land.push(LandData::from_reader(reader, format)?);
+            }
+        }
+
+        // The item groups continue until the end of the file
+        for group in 0.. {
+            match reader.read_u32::<LittleEndian>() {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            }
+
+            for _ in 0..GROUP_SIZE {
+                match ItemData::from_reader(reader, format) {
+                    Ok(item) => tile_data.items.push(item),
+                    Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                        return Err(Error::Truncated(group))
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(tile_data)
+    }
+
+    pub fn land(&self, tile_id: u16) -> Option<&LandData> {
+        self.land.get(tile_id as usize)
+    }
+
+    pub fn item(&self, graphic: u16) -> Option<&ItemData> {
+        self.items.get(graphic as usize)
+    }
+}
+
+impl fmt::Debug for TileData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "TileData(land: {}, items: {})",
+            self.land.len(),
+            self.items.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::WriteBytesExt;
+
+    const ITEM_COUNT: usize = 0x1000;
+
+    fn write_flags(data: &mut Vec<u8>, flags: TileFlags, format: Format) {
+        match format {
+            Format::Classic => data.write_u32::<LittleEndian>(flags.0 as u32).unwrap(),
+            Format::HighSeas => data.write_u64::<LittleEndian>(flags.0).unwrap(),
+        }
+    }
+
+    fn write_name(data: &mut Vec<u8>, name: &str) {
+        let mut padded = [0u8; 20];
+        padded[..name.len()].copy_from_slice(name.as_bytes());
+        data.extend_from_slice(&padded);
+    }
+
+    // Only the tiles used by the tests have any data, the rest are empty
+    fn tile_data(format: Format) -> Vec<u8> {
+        let mut data = vec![];
+
+        for id in 0..TileData::LAND_COUNT {
+            if id % GROUP_SIZE == 0 {
+                data.write_u32::<LittleEndian>(0).unwrap();
+            }
+
+            let (flags, texture, name) = match id {
+                0x0003 => (TileFlags::default(), 0x0003, "grass"),
+                0x00A8 => (TileFlags::WET | TileFlags::IMPASSABLE, 0x0002, "water"),
+                _ => (TileFlags::default(), 0, ""),
+            };
+            write_flags(&mut data, flags, format);
+            data.write_u16::<LittleEndian>(texture).unwrap();
+            write_name(&mut data, name);
+        }
+
+        for id in 0..ITEM_COUNT {
+            if id % GROUP_SIZE == 0 {
+                data.write_u32::<LittleEndian>(0).unwrap();
+            }
+
+            let (flags, weight, quality, height, name) = match id {
+                0x0CCA => (TileFlags::IMPASSABLE, 0xFF, 0, 20, "tree"),
+                0x0EED => (TileFlags::STACKABLE, 0, 0, 0, "gold coin"),
+                0x0B8F => (TileFlags::SURFACE | TileFlags::BRIDGE, 0xFF, 0, 1, "dirt"),
+                _ => (TileFlags::default(), 0, 0, 0, ""),
+            };
+            write_flags(&mut data, flags, format);
+            data.extend_from_slice(&[weight, quality]);
+            data.extend_from_slice(&[0u8; 10]);
+            data.push(height);
+            write_name(&mut data, name);
+        }
+
+        data
+    }
+
+    fn check_known_tiles(tile_data: &TileData) {
+        let water = tile_data.land(0x00A8).unwrap();
+        assert!(water.flags.contains(TileFlags::WET));
+        assert!(water.flags.contains(TileFlags::IMPASSABLE));
+        assert_eq!(water.texture, 0x0002);
+        assert_eq!(water.name, "water");
+
+        let grass = tile_data.land(0x0003).unwrap();
+        assert!(!grass.flags.contains(TileFlags::IMPASSABLE));
+        assert_eq!(grass.name, "grass");
+
+        let tree = tile_data.item(0x0CCA).unwrap();
+        assert!(tree.flags.contains(TileFlags::IMPASSABLE));
+        assert_eq!((tree.weight, tree.height), (0xFF, 20));
+        assert_eq!(tree.name, "tree");
+
+        let gold = tile_data.item(0x0EED).unwrap();
+        assert!(gold.flags.contains(TileFlags::STACKABLE));
+        assert_eq!(gold.name, "gold coin");
+
+        let dirt = tile_data.item(0x0B8F).unwrap();
+        assert!(dirt.flags.contains(TileFlags::SURFACE | TileFlags::BRIDGE));
+        assert_eq!(dirt.height, 1);
+
+        assert!(tile_data.item(ITEM_COUNT as u16).is_none());
+    }
+
+    #[test]
+    fn parses_classic() {
+        let data = tile_data(Format::Classic);
+        let tile_data = TileData::from_reader(&mut data.as_slice(), Format::Classic).unwrap();
+
+        check_known_tiles(&tile_data);
+    }
+
+    #[test]
+    fn parses_high_seas() {
+        let data = tile_data(Format::HighSeas);
+        let tile_data = TileData::from_reader(&mut data.as_slice(), Format::HighSeas).unwrap();
+
+        check_known_tiles(&tile_data);
+    }
+
+    #[test]
+    fn rejects_truncated_group() {
+        let mut data = tile_data(Format::HighSeas);
+        data.truncate(data.len() - 5);
+
+        assert!(matches!(
+            TileData::from_reader(&mut data.as_slice(), Format::HighSeas),
+            Err(Error::Truncated(0x7F))
+        ));
+    }
+}